@@ -0,0 +1,92 @@
+//! A cache of values queried from adb, e.g. the features of a device.
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Mutex, OnceLock};
+
+use crate::AdbResult;
+
+/// A thread-safe cache keyed by strings.
+///
+/// The cache doesn't take part in the comparison of its owner, i.e. all caches are equal,
+/// and a clone starts with a copy of the cached values.
+#[derive(Debug, Default)]
+pub(crate) struct Cache<V>(Mutex<HashMap<String, V>>);
+
+impl<V: Clone> Cache<V> {
+    /// Returns the value cached for `key`, or computes it with `f` and caches it if `f` succeeds.
+    ///
+    /// The lock isn't held while computing, so concurrent misses may compute the value twice.
+    pub(crate) fn get_or_try_insert<F>(&self, key: &str, f: F) -> AdbResult<V>
+    where
+        F: FnOnce() -> AdbResult<V>,
+    {
+        if let Some(value) = self.lock().get(key) {
+            return Ok(value.clone());
+        }
+        let value = f()?;
+        self.lock().insert(key.to_string(), value.clone());
+        Ok(value)
+    }
+
+    /// Locks the map, ignoring poisoning since the map is never left half-updated.
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, V>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<V: Clone> Clone for Cache<V> {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.lock().clone()))
+    }
+}
+
+impl<V> PartialEq for Cache<V> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<V> Eq for Cache<V> {}
+
+/// A value computed once, e.g. the adb binary resolved in `PATH`, reset by replacing it.
+///
+/// Like [`Cache`], it doesn't take part in the comparison of its owner.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Once<V>(OnceLock<V>);
+
+impl<V> Deref for Once<V> {
+    type Target = OnceLock<V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<V> PartialEq for Once<V> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<V> Eq for Once<V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AdbError;
+
+    #[test]
+    fn test_get_or_try_insert() {
+        let cache = Cache::default();
+        assert_eq!(cache.get_or_try_insert("a", || Ok(1)).unwrap(), 1);
+        assert_eq!(cache.get_or_try_insert("a", || Ok(2)).unwrap(), 1);
+        assert!(cache
+            .get_or_try_insert("b", || Err(AdbError::NulArgument("\0".into())))
+            .is_err());
+        assert_eq!(cache.get_or_try_insert("b", || Ok(3)).unwrap(), 3);
+        let clone = cache.clone();
+        assert_eq!(clone.get_or_try_insert("a", || Ok(4)).unwrap(), 1);
+        assert!(cache == Cache::default());
+    }
+}
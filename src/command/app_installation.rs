@@ -4,18 +4,50 @@
 //! - `install-multiple [-lrtsdpg] [--instant] PACKAGE...`: Push multiple APKs to the device for a single package and install them
 //! - `install-multi-package [-lrtsdg] [--instant] PACKAGE...`: Push one or more packages to the device and install them atomically
 //! - `uninstall [-k] APPLICATION_ID`: Remove this APPLICATION_ID from the device.
+//! - `shell pm install-create | install-write | install-commit | install-abandon`: Install with a session.
 //!
 //! See [App Installation Commands](https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/master/docs/user/adb.1.md#app-installation)
 
 use std::ffi::{OsStr, OsString};
-use std::process::Command;
+use std::fmt::Display;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::str::FromStr;
+use std::time::Duration;
 
-use crate::command::AdbCommandBuilder;
-use crate::{Adb, AdbCommand};
+use crate::command::{process, AdbCommandBuilder};
+use crate::error::ParseError;
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
+
+/// Where the package manager installs an application.
+///
+/// Not to be confused with the global option `-s SERIAL`, the install location is a `pm` flag
+/// emitted after the install subcommand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InstallLocation {
+    /// `--install-location 0`: Let the system decide, respecting the manifest of the application.
+    Auto,
+    /// `-f`: Install on the internal system memory.
+    Internal,
+    /// `-s`: Install on the shared mass storage (SD card).
+    External,
+}
+
+impl InstallLocation {
+    /// The `pm` flag of the install location.
+    fn args(self) -> &'static [&'static str] {
+        match self {
+            InstallLocation::Auto => &["--install-location", "0"],
+            InstallLocation::Internal => &["-f"],
+            InstallLocation::External => &["-s"],
+        }
+    }
+}
 
 /// `install [-lrtsdg] [--instant] PACKAGE`: Push a single package to the device and install it
 /// - `-r`: Replace existing application.
 /// - `-t`: Allow test packages.
+/// - `-s`: Install the application on the shared mass storage (SD card).
 /// - `-d`: Allow version code downgrade (debuggable packages only).
 /// - `-g`: Grant all runtime permissions.
 /// - `--abi ABI`: Override platform's default ABI.
@@ -33,12 +65,14 @@ use crate::{Adb, AdbCommand};
 #[derive(Debug, Clone)]
 pub struct AdbInstall<'a, S1: AsRef<OsStr>, S2: AsRef<OsStr>> {
     acb: AdbCommandBuilder<'a>,
+    /// `-l`: Forward lock application.
     l: bool,
     /// `-r`: Replace existing application.
     r: bool,
     /// `-t`: Allow test packages.
     t: bool,
-    s: bool,
+    /// `-s`/`-f`/`--install-location`: Where to install the application.
+    install_location: Option<InstallLocation>,
     /// `-d`: Allow version code downgrade (debuggable packages only).
     d: bool,
     /// `-g`: Grant all runtime permissions.
@@ -63,6 +97,8 @@ pub struct AdbInstall<'a, S1: AsRef<OsStr>, S2: AsRef<OsStr>> {
     version_check_agent: bool,
     /// `--local-agent`: Locate agent files from local source build (instead of SDK location).
     local_agent: bool,
+    /// Extra arguments forwarded verbatim to the package manager, after the standard flags.
+    pm_args: Vec<OsString>,
     /// `PACKAGE`: The package to install.
     package: S2,
 }
@@ -74,7 +110,7 @@ impl<'a, S: AsRef<OsStr>> AdbInstall<'a, S, S> {
             l: false,
             r: false,
             t: false,
-            s: false,
+            install_location: None,
             d: false,
             g: false,
             abi: None,
@@ -87,6 +123,7 @@ impl<'a, S: AsRef<OsStr>> AdbInstall<'a, S, S> {
             date_check_agent: false,
             version_check_agent: false,
             local_agent: false,
+            pm_args: Vec::new(),
             package,
         }
     }
@@ -97,6 +134,7 @@ where
     S1: AsRef<OsStr>,
     S2: AsRef<OsStr>,
 {
+    /// `-l`: Forward lock application, deprecated and ignored by newer package managers.
     pub fn l(mut self) -> Self {
         self.l = true;
         self
@@ -114,8 +152,20 @@ where
         self
     }
 
-    pub fn s(mut self) -> Self {
-        self.s = true;
+    /// `-s`: Install the application on the shared mass storage (SD card),
+    /// shortcut for `install_location(InstallLocation::External)`.
+    ///
+    /// Not to be confused with the global option `-s SERIAL`
+    /// ([`AdbCommandBuilder::s_serial`]), which must precede the subcommand.
+    pub fn s(self) -> Self {
+        self.install_location(InstallLocation::External)
+    }
+
+    /// Where to install the application, see [`InstallLocation`].
+    ///
+    /// The previous location will be overwritten.
+    pub fn install_location(mut self, location: InstallLocation) -> Self {
+        self.install_location = Some(location);
         self
     }
 
@@ -140,7 +190,7 @@ where
             l: self.l,
             r: self.r,
             t: self.t,
-            s: self.s,
+            install_location: self.install_location,
             d: self.d,
             g: self.g,
             abi: Some(abi),
@@ -153,6 +203,7 @@ where
             date_check_agent: self.date_check_agent,
             version_check_agent: self.version_check_agent,
             local_agent: self.local_agent,
+            pm_args: self.pm_args,
             package: self.package,
         }
     }
@@ -211,6 +262,29 @@ where
         self
     }
 
+    /// Appends an extra argument for `pm install`, after the standard flags but before the packages.
+    ///
+    /// The argument is forwarded verbatim to the package manager,
+    /// so options not covered by this builder (e.g. `--install-location 1`, `--full`, `--dont-kill`)
+    /// can be used. See `adb shell pm help` for the available options.
+    pub fn pm_arg<T: AsRef<OsStr>>(mut self, arg: T) -> Self {
+        self.pm_args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Appends extra arguments for `pm install`, after the standard flags but before the packages.
+    ///
+    /// See [`Self::pm_arg`] for more information.
+    pub fn pm_args<T, I>(mut self, args: I) -> Self
+    where
+        T: AsRef<OsStr>,
+        I: IntoIterator<Item = T>,
+    {
+        self.pm_args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
     /// `PACKAGE`: The package to install.
     ///
     /// The previous package will be overwritten.
@@ -220,7 +294,7 @@ where
             l: self.l,
             r: self.r,
             t: self.t,
-            s: self.s,
+            install_location: self.install_location,
             d: self.d,
             g: self.g,
             abi: self.abi,
@@ -233,9 +307,41 @@ where
             date_check_agent: self.date_check_agent,
             version_check_agent: self.version_check_agent,
             local_agent: self.local_agent,
+            pm_args: self.pm_args,
             package,
         }
     }
+
+    /// Executes the command, parsing the result reported by the package manager.
+    ///
+    /// The exit status of adb alone is unreliable for installations,
+    /// so the `Success` or `Failure [REASON]` line in the output decides the result,
+    /// see [`InstallOutcome::from_output`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::InstallFailed`] with the reason (e.g. `INSTALL_FAILED_INSUFFICIENT_STORAGE`)
+    /// if the installation fails, or [`AdbError::Parse`] if adb succeeds without reporting a result.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbError};
+    /// # let adb = Adb::new().unwrap();
+    /// use adbr::command::app_installation::InstallFailureReason;
+    ///
+    /// match adb.install("/path/to/app.apk").run() {
+    ///     Ok(()) => println!("installed"),
+    ///     Err(AdbError::InstallFailed(InstallFailureReason::AlreadyExists)) => {
+    ///         adb.install("/path/to/app.apk").r().run().expect("reinstall failed")
+    ///     }
+    ///     Err(AdbError::InstallFailed(reason)) => println!("install failed: {}", reason),
+    ///     Err(e) => println!("{}", e),
+    /// }
+    /// ```
+    pub fn run(self) -> AdbResult<()> {
+        InstallOutcome::from_output(&self.output()?)?.into_result()
+    }
 }
 
 impl<'a, S1, S2> AdbCommand for AdbInstall<'a, S1, S2>
@@ -255,8 +361,8 @@ where
         if self.t {
             cmd.arg("-t");
         }
-        if self.s {
-            cmd.arg("-s");
+        if let Some(location) = self.install_location {
+            cmd.args(location.args());
         }
         if self.d {
             cmd.arg("-d");
@@ -294,15 +400,27 @@ where
         if self.local_agent {
             cmd.arg("--local-agent");
         }
+        cmd.args(self.pm_args);
         cmd.arg(self.package);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(&self.abi)?;
+        process::check_nul(&self.pm_args)?;
+        process::check_nul([&self.package])
+    }
 }
 
 impl Adb {
     /// `install [-lrtsdg] [--instant] PACKAGE`: Push a single package to the device and install it
     /// - `-r`: Replace existing application.
     /// - `-t`: Allow test packages.
+    /// - `-s`: Install the application on the shared mass storage (SD card).
     /// - `-d`: Allow version code downgrade (debuggable packages only).
     /// - `-g`: Grant all runtime permissions.
     /// - `--abi ABI`: Override platform's default ABI.
@@ -330,15 +448,105 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb install -r /path/to/app.apk` failed");
     /// ```
-    pub fn install<S: AsRef<OsStr>>(&self, package: S) -> AdbInstall<S, S> {
+    pub fn install<S: AsRef<OsStr>>(&self, package: S) -> AdbInstall<'_, S, S> {
         AdbInstall::new(self.command(), package)
     }
+
+    /// `install PACKAGE` with a time bound, returning the parsed [`InstallOutcome`].
+    ///
+    /// If the installation doesn't finish within `timeout`, the adb child process is killed
+    /// instead of being left running in the background.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Timeout`] if the installation is killed,
+    /// or [`AdbError::Parse`] if the output reports no result, see [`InstallOutcome::from_output`].
+    ///
+    /// # Examples
+    ///
+    /// `adb install /path/to/app.apk`, killed after 5 minutes
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// use adbr::command::app_installation::InstallOutcome;
+    ///
+    /// let outcome = adb
+    ///     .install_with_timeout("/path/to/app.apk", Duration::from_secs(300))
+    ///     .expect("`adb install /path/to/app.apk` failed");
+    /// assert_eq!(outcome, InstallOutcome::Success);
+    /// ```
+    pub fn install_with_timeout<S: AsRef<OsStr>>(
+        &self,
+        package: S,
+        timeout: Duration,
+    ) -> AdbResult<InstallOutcome> {
+        let output = self.install(package).output_timeout(timeout)?;
+        InstallOutcome::from_output(&output)
+    }
+
+    /// `install -r -g APK` followed by `shell am start -n COMPONENT`:
+    /// Install (or replace) an application granting its runtime permissions, then launch `component`.
+    ///
+    /// The activity is only started if the installation succeeds.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::InstallFailed`] if the package manager reports a failure,
+    /// or [`AdbError::ShellCommandFailed`] if `am start` reports an error
+    /// (e.g. `Error: Activity class {...} does not exist.`), which it does with a zero exit code.
+    ///
+    /// # Examples
+    ///
+    /// `adb install -r -g /path/to/app.apk && adb shell am start -n com.example/.MainActivity`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.install_launch("/path/to/app.apk", "com.example/.MainActivity")
+    ///     .expect("failed to install and launch com.example");
+    /// ```
+    pub fn install_launch<S: AsRef<OsStr>>(&self, apk: S, component: &str) -> AdbResult<()> {
+        install_then_start(
+            self.install(apk).r().g(),
+            self.shell_cmd(["am", "start", "-n", component]),
+        )
+    }
+}
+
+/// Runs `install`, then `start` if the installation succeeds, checking both.
+fn install_then_start<I: AdbCommand, S: AdbCommand>(install: I, start: S) -> AdbResult<()> {
+    InstallOutcome::from_output(&install.output()?)?.into_result()?;
+    check_am_start(&start.output()?)
+}
+
+/// Checks the output of `am start`, which may report an error with a zero exit code.
+///
+/// # Errors
+///
+/// Returns [`AdbError::NonZeroExit`] if `am start` exits with a non-zero status,
+/// or [`AdbError::ShellCommandFailed`] with the error line if the output reports an error.
+fn check_am_start(output: &Output) -> AdbResult<()> {
+    process::check(output)?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| line.starts_with("Error:"))
+    {
+        Some(line) => Err(AdbError::ShellCommandFailed(line.to_string())),
+        None => Ok(()),
+    }
 }
 
 impl<'a> AdbCommandBuilder<'a> {
     /// `install [-lrtsdg] [--instant] PACKAGE`: Push a single package to the device and install it
     /// - `-r`: Replace existing application.
     /// - `-t`: Allow test packages.
+    /// - `-s`: Install the application on the shared mass storage (SD card).
     /// - `-d`: Allow version code downgrade (debuggable packages only).
     /// - `-g`: Grant all runtime permissions.
     /// - `--abi ABI`: Override platform's default ABI.
@@ -360,13 +568,171 @@ impl<'a> AdbCommandBuilder<'a> {
     }
 }
 
+/// The outcome of an installation reported by the package manager.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InstallOutcome {
+    /// `Success`: The package is installed.
+    Success,
+    /// `Failure [REASON]`: The package is not installed, with the reason reported by the package manager.
+    Failure(String),
+}
+
+impl InstallOutcome {
+    /// Gets the outcome from the output of an install command.
+    ///
+    /// Stdout is searched first, then stderr. If neither contains a result and adb failed,
+    /// the last line of stderr is used as the failure reason.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Parse`] if neither contains a result although adb succeeded,
+    /// since the exit status alone doesn't tell whether the package is installed.
+    pub fn from_output(output: &Output) -> AdbResult<Self> {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if let Ok(outcome) = stdout.parse().or_else(|_| stderr.parse()) {
+            return Ok(outcome);
+        }
+        if output.status.success() {
+            Err(ParseError::with_description(
+                format!("{}{}", stdout, stderr).trim(),
+                "InstallOutcome",
+                "no `Success` or `Failure [REASON]` line",
+            )
+            .into())
+        } else {
+            let reason = stderr
+                .lines()
+                .rev()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map_or_else(|| output.status.to_string(), String::from);
+            Ok(InstallOutcome::Failure(reason))
+        }
+    }
+
+    /// Returns `true` if the outcome is [`InstallOutcome::Success`].
+    pub fn is_success(&self) -> bool {
+        matches!(self, InstallOutcome::Success)
+    }
+
+    /// Converts the outcome into a result.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::InstallFailed`] with the parsed reason if the outcome is a failure.
+    pub fn into_result(self) -> AdbResult<()> {
+        match self {
+            InstallOutcome::Success => Ok(()),
+            InstallOutcome::Failure(reason) => Err(AdbError::InstallFailed(reason.parse()?)),
+        }
+    }
+}
+
+impl Display for InstallOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallOutcome::Success => write!(f, "Success"),
+            InstallOutcome::Failure(reason) => write!(f, "Failure [{}]", reason),
+        }
+    }
+}
+
+impl FromStr for InstallOutcome {
+    type Err = AdbError;
+
+    /// Parses the last `Success` or `Failure [REASON]` line in `s`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        for line in s.lines().rev().map(str::trim) {
+            if line == "Success" {
+                return Ok(InstallOutcome::Success);
+            }
+            if let Some(start) = line.find("Failure [") {
+                let reason = &line[start + "Failure [".len()..];
+                let reason = reason.rfind(']').map_or(reason, |end| &reason[..end]);
+                return Ok(InstallOutcome::Failure(reason.to_string()));
+            }
+        }
+        Err(AdbError::Parse(ParseError::with_description(
+            s,
+            "InstallOutcome",
+            "No install result found",
+        )))
+    }
+}
+
+/// The reason of an installation failure, i.e. the `REASON` in `Failure [REASON]`.
+///
+/// Common `INSTALL_FAILED_*` codes have their own variants, so that callers can react to them,
+/// e.g. retry with `-r` on [`InstallFailureReason::AlreadyExists`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InstallFailureReason {
+    /// `INSTALL_FAILED_INSUFFICIENT_STORAGE`: Not enough space on the device.
+    InsufficientStorage,
+    /// `INSTALL_FAILED_ALREADY_EXISTS`: The package is already installed, retry with `-r`.
+    AlreadyExists,
+    /// `INSTALL_FAILED_VERSION_DOWNGRADE`: The installed version code is newer, retry with `-d`.
+    VersionDowngrade,
+    /// `INSTALL_FAILED_INVALID_APK`: The APK is invalid or corrupted.
+    InvalidApk,
+    /// `INSTALL_FAILED_MISSING_SHARED_LIBRARY`: A shared library required by the package is missing.
+    MissingSharedLibrary,
+    /// `INSTALL_FAILED_UPDATE_INCOMPATIBLE`: The installed package is signed with a different key.
+    UpdateIncompatible,
+    /// Any other reason, as reported by the package manager.
+    Other(String),
+}
+
+impl Display for InstallFailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallFailureReason::InsufficientStorage => {
+                write!(f, "INSTALL_FAILED_INSUFFICIENT_STORAGE")
+            }
+            InstallFailureReason::AlreadyExists => write!(f, "INSTALL_FAILED_ALREADY_EXISTS"),
+            InstallFailureReason::VersionDowngrade => write!(f, "INSTALL_FAILED_VERSION_DOWNGRADE"),
+            InstallFailureReason::InvalidApk => write!(f, "INSTALL_FAILED_INVALID_APK"),
+            InstallFailureReason::MissingSharedLibrary => {
+                write!(f, "INSTALL_FAILED_MISSING_SHARED_LIBRARY")
+            }
+            InstallFailureReason::UpdateIncompatible => {
+                write!(f, "INSTALL_FAILED_UPDATE_INCOMPATIBLE")
+            }
+            InstallFailureReason::Other(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl FromStr for InstallFailureReason {
+    type Err = AdbError;
+
+    /// Parses the code of the reason, ignoring the message after it
+    /// (e.g. `INSTALL_FAILED_ALREADY_EXISTS: Attempt to re-install`).
+    ///
+    /// Unknown reasons are kept as a whole in [`InstallFailureReason::Other`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let code = s.split(':').next().unwrap_or_default().trim();
+        Ok(match code {
+            "INSTALL_FAILED_INSUFFICIENT_STORAGE" => InstallFailureReason::InsufficientStorage,
+            "INSTALL_FAILED_ALREADY_EXISTS" => InstallFailureReason::AlreadyExists,
+            "INSTALL_FAILED_VERSION_DOWNGRADE" => InstallFailureReason::VersionDowngrade,
+            "INSTALL_FAILED_INVALID_APK" => InstallFailureReason::InvalidApk,
+            "INSTALL_FAILED_MISSING_SHARED_LIBRARY" => InstallFailureReason::MissingSharedLibrary,
+            "INSTALL_FAILED_UPDATE_INCOMPATIBLE" => InstallFailureReason::UpdateIncompatible,
+            _ => InstallFailureReason::Other(s.to_string()),
+        })
+    }
+}
+
 /// `install-multiple [-lrtsdpg] [--instant] PACKAGE...`: Push multiple APKs to the device for a single package and install them
 /// - `-r`: Replace existing application.
 /// - `-t`: Allow test packages.
+/// - `-s`: Install the application on the shared mass storage (SD card).
 /// - `-d`: Allow version code downgrade (debuggable packages only).
 /// - `-p`: Partial application install.
 /// - `-g`: Grant all runtime permissions.
-/// - `--abi ABI`: Override platform's default ABI.
+/// - `--abi ABI`: Override platform's default ABI, may be repeated for split APKs.
 /// - `--instant`: Cause the app to be installed as an ephemeral install app.
 /// - `--no-streaming`: Always push APK to device and invoke Package Manager as separate steps.
 /// - `--streaming`: Force streaming APK directly into Package Manager.
@@ -381,12 +747,14 @@ impl<'a> AdbCommandBuilder<'a> {
 #[derive(Debug, Clone)]
 pub struct AdbInstallMultiple<'a, S: AsRef<OsStr>> {
     acb: AdbCommandBuilder<'a>,
+    /// `-l`: Forward lock application.
     l: bool,
     /// `-r`: Replace existing application.
     r: bool,
     /// `-t`: Allow test packages.
     t: bool,
-    s: bool,
+    /// `-s`/`-f`/`--install-location`: Where to install the application.
+    install_location: Option<InstallLocation>,
     /// `-d`: Allow version code downgrade (debuggable packages only).
     d: bool,
     /// `-p`: Partial application install.
@@ -395,6 +763,8 @@ pub struct AdbInstallMultiple<'a, S: AsRef<OsStr>> {
     g: bool,
     /// `--abi ABI`: Override platform's default ABI.
     abi: Option<S>,
+    /// `--abi ABI...`: The ABIs set by [`Self::abis`], emitted after [`Self::abi`].
+    abis: Vec<OsString>,
     /// `--instant`: Cause the app to be installed as an ephemeral install app.
     instant: bool,
     /// `--no-streaming`: Always push APK to device and invoke Package Manager as separate steps.
@@ -413,6 +783,8 @@ pub struct AdbInstallMultiple<'a, S: AsRef<OsStr>> {
     version_check_agent: bool,
     /// `--local-agent`: Locate agent files from local source build (instead of SDK location).
     local_agent: bool,
+    /// Extra arguments forwarded verbatim to the package manager, after the standard flags.
+    pm_args: Vec<OsString>,
     /// `PACKAGE...`: The packages to install.
     packages: Vec<OsString>,
 }
@@ -424,11 +796,12 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiple<'a, S> {
             l: false,
             r: false,
             t: false,
-            s: false,
+            install_location: None,
             d: false,
             p: false,
             g: false,
             abi: None,
+            abis: Vec::new(),
             instant: false,
             no_streaming: false,
             streaming: false,
@@ -438,10 +811,12 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiple<'a, S> {
             date_check_agent: false,
             version_check_agent: false,
             local_agent: false,
+            pm_args: Vec::new(),
             packages,
         }
     }
 
+    /// `-l`: Forward lock application, deprecated and ignored by newer package managers.
     pub fn l(mut self) -> Self {
         self.l = true;
         self
@@ -459,8 +834,20 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiple<'a, S> {
         self
     }
 
-    pub fn s(mut self) -> Self {
-        self.s = true;
+    /// `-s`: Install the application on the shared mass storage (SD card),
+    /// shortcut for `install_location(InstallLocation::External)`.
+    ///
+    /// Not to be confused with the global option `-s SERIAL`
+    /// ([`AdbCommandBuilder::s_serial`]), which must precede the subcommand.
+    pub fn s(self) -> Self {
+        self.install_location(InstallLocation::External)
+    }
+
+    /// Where to install the application, see [`InstallLocation`].
+    ///
+    /// The previous location will be overwritten.
+    pub fn install_location(mut self, location: InstallLocation) -> Self {
+        self.install_location = Some(location);
         self
     }
 
@@ -476,20 +863,38 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiple<'a, S> {
         self
     }
 
+    /// `--abi ABI...`: Override platform's default ABI with several ABIs, e.g. for split APKs.
+    ///
+    /// `--abi` is repeated for every ABI in order.
+    /// The previous ABIs (including the one set by [`Self::abi`]) will be overwritten.
+    pub fn abis<S1, I>(mut self, abis: I) -> Self
+    where
+        S1: AsRef<OsStr>,
+        I: IntoIterator<Item = S1>,
+    {
+        self.abi = None;
+        self.abis = abis
+            .into_iter()
+            .map(|abi| abi.as_ref().to_os_string())
+            .collect();
+        self
+    }
+
     /// `--abi ABI`: Override platform's default ABI.
     ///
-    /// The previous ABI will be overwritten.
+    /// The previous ABIs (including the ones set by [`Self::abis`]) will be overwritten.
     pub fn abi<S1: AsRef<OsStr>>(self, abi: S1) -> AdbInstallMultiple<'a, S1> {
         AdbInstallMultiple {
             acb: self.acb,
             l: self.l,
             r: self.r,
             t: self.t,
-            s: self.s,
+            install_location: self.install_location,
             d: self.d,
             p: self.p,
             g: self.g,
             abi: Some(abi),
+            abis: Vec::new(),
             instant: self.instant,
             no_streaming: self.no_streaming,
             streaming: self.streaming,
@@ -499,6 +904,7 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiple<'a, S> {
             date_check_agent: self.date_check_agent,
             version_check_agent: self.version_check_agent,
             local_agent: self.local_agent,
+            pm_args: self.pm_args,
             packages: self.packages,
         }
     }
@@ -557,6 +963,29 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiple<'a, S> {
         self
     }
 
+    /// Appends an extra argument for `pm install`, after the standard flags but before the packages.
+    ///
+    /// The argument is forwarded verbatim to the package manager,
+    /// so options not covered by this builder (e.g. `--install-location 1`, `--full`, `--dont-kill`)
+    /// can be used. See `adb shell pm help` for the available options.
+    pub fn pm_arg<T: AsRef<OsStr>>(mut self, arg: T) -> Self {
+        self.pm_args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Appends extra arguments for `pm install`, after the standard flags but before the packages.
+    ///
+    /// See [`Self::pm_arg`] for more information.
+    pub fn pm_args<T, I>(mut self, args: I) -> Self
+    where
+        T: AsRef<OsStr>,
+        I: IntoIterator<Item = T>,
+    {
+        self.pm_args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
     /// `PACKAGE...`: The packages to install.
     ///
     /// The previous packages will be overwritten.
@@ -589,8 +1018,8 @@ where
         if self.t {
             cmd.arg("-t");
         }
-        if self.s {
-            cmd.arg("-s");
+        if let Some(location) = self.install_location {
+            cmd.args(location.args());
         }
         if self.d {
             cmd.arg("-d");
@@ -604,6 +1033,9 @@ where
         if let Some(abi) = self.abi {
             cmd.arg("--abi").arg(abi);
         }
+        for abi in self.abis {
+            cmd.arg("--abi").arg(abi);
+        }
         if self.instant {
             cmd.arg("--instant");
         }
@@ -631,15 +1063,28 @@ where
         if self.local_agent {
             cmd.arg("--local-agent");
         }
+        cmd.args(self.pm_args);
         cmd.args(self.packages);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(&self.abi)?;
+        process::check_nul(&self.abis)?;
+        process::check_nul(&self.pm_args)?;
+        process::check_nul(&self.packages)
+    }
 }
 
 impl Adb {
     /// `install-multiple [-lrtsdpg] [--instant] PACKAGE...`: Push multiple APKs to the device for a single package and install them
     /// - `-r`: Replace existing application.
     /// - `-t`: Allow test packages.
+    /// - `-s`: Install the application on the shared mass storage (SD card).
     /// - `-d`: Allow version code downgrade (debuggable packages only).
     /// - `-p`: Partial application install.
     /// - `-g`: Grant all runtime permissions.
@@ -668,7 +1113,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb install-multiple -r /path/to/app1.apk /path/to/app2.apk` failed");
     /// ```
-    pub fn install_multiple<S, I>(&self, packages: I) -> AdbInstallMultiple<S>
+    pub fn install_multiple<S, I>(&self, packages: I) -> AdbInstallMultiple<'_, S>
     where
         S: AsRef<OsStr>,
         I: IntoIterator<Item = S>,
@@ -687,6 +1132,7 @@ impl<'a> AdbCommandBuilder<'a> {
     /// `install-multiple [-lrtsdpg] [--instant] PACKAGE...`: Push multiple APKs to the device for a single package and install them
     /// - `-r`: Replace existing application.
     /// - `-t`: Allow test packages.
+    /// - `-s`: Install the application on the shared mass storage (SD card).
     /// - `-d`: Allow version code downgrade (debuggable packages only).
     /// - `-p`: Partial application install.
     /// - `-g`: Grant all runtime permissions.
@@ -722,9 +1168,10 @@ impl<'a> AdbCommandBuilder<'a> {
 /// `install-multi-package [-lrtsdg] [--instant] PACKAGE...`: Push one or more packages to the device and install them atomically
 /// - `-r`: Replace existing application.
 /// - `-t`: Allow test packages.
+/// - `-s`: Install the application on the shared mass storage (SD card).
 /// - `-d`: Allow version code downgrade (debuggable packages only).
 /// - `-g`: Grant all runtime permissions.
-/// - `--abi ABI`: Override platform's default ABI.
+/// - `--abi ABI`: Override platform's default ABI, may be repeated for split APKs.
 /// - `--instant`: Cause the app to be installed as an ephemeral install app.
 /// - `--no-streaming`: Always push APK to device and invoke Package Manager as separate steps.
 /// - `--streaming`: Force streaming APK directly into Package Manager.
@@ -739,18 +1186,22 @@ impl<'a> AdbCommandBuilder<'a> {
 #[derive(Debug, Clone)]
 pub struct AdbInstallMultiPackage<'a, S: AsRef<OsStr>> {
     acb: AdbCommandBuilder<'a>,
+    /// `-l`: Forward lock application.
     l: bool,
     /// `-r`: Replace existing application.
     r: bool,
     /// `-t`: Allow test packages.
     t: bool,
-    s: bool,
+    /// `-s`/`-f`/`--install-location`: Where to install the application.
+    install_location: Option<InstallLocation>,
     /// `-d`: Allow version code downgrade (debuggable packages only).
     d: bool,
     /// `-g`: Grant all runtime permissions.
     g: bool,
     /// `--abi ABI`: Override platform's default ABI.
     abi: Option<S>,
+    /// `--abi ABI...`: The ABIs set by [`Self::abis`], emitted after [`Self::abi`].
+    abis: Vec<OsString>,
     /// `--instant`: Cause the app to be installed as an ephemeral install app.
     instant: bool,
     /// `--no-streaming`: Always push APK to device and invoke Package Manager as separate steps.
@@ -769,6 +1220,8 @@ pub struct AdbInstallMultiPackage<'a, S: AsRef<OsStr>> {
     version_check_agent: bool,
     /// `--local-agent`: Locate agent files from local source build (instead of SDK location).
     local_agent: bool,
+    /// Extra arguments forwarded verbatim to the package manager, after the standard flags.
+    pm_args: Vec<OsString>,
     /// `PACKAGE...`: The packages to install.
     packages: Vec<OsString>,
 }
@@ -780,10 +1233,11 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiPackage<'a, S> {
             l: false,
             r: false,
             t: false,
-            s: false,
+            install_location: None,
             d: false,
             g: false,
             abi: None,
+            abis: Vec::new(),
             instant: false,
             no_streaming: false,
             streaming: false,
@@ -793,10 +1247,12 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiPackage<'a, S> {
             date_check_agent: false,
             version_check_agent: false,
             local_agent: false,
+            pm_args: Vec::new(),
             packages,
         }
     }
 
+    /// `-l`: Forward lock application, deprecated and ignored by newer package managers.
     pub fn l(mut self) -> Self {
         self.l = true;
         self
@@ -814,8 +1270,20 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiPackage<'a, S> {
         self
     }
 
-    pub fn s(mut self) -> Self {
-        self.s = true;
+    /// `-s`: Install the application on the shared mass storage (SD card),
+    /// shortcut for `install_location(InstallLocation::External)`.
+    ///
+    /// Not to be confused with the global option `-s SERIAL`
+    /// ([`AdbCommandBuilder::s_serial`]), which must precede the subcommand.
+    pub fn s(self) -> Self {
+        self.install_location(InstallLocation::External)
+    }
+
+    /// Where to install the application, see [`InstallLocation`].
+    ///
+    /// The previous location will be overwritten.
+    pub fn install_location(mut self, location: InstallLocation) -> Self {
+        self.install_location = Some(location);
         self
     }
 
@@ -831,19 +1299,37 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiPackage<'a, S> {
         self
     }
 
+    /// `--abi ABI...`: Override platform's default ABI with several ABIs, e.g. for split APKs.
+    ///
+    /// `--abi` is repeated for every ABI in order.
+    /// The previous ABIs (including the one set by [`Self::abi`]) will be overwritten.
+    pub fn abis<S1, I>(mut self, abis: I) -> Self
+    where
+        S1: AsRef<OsStr>,
+        I: IntoIterator<Item = S1>,
+    {
+        self.abi = None;
+        self.abis = abis
+            .into_iter()
+            .map(|abi| abi.as_ref().to_os_string())
+            .collect();
+        self
+    }
+
     /// `--abi ABI`: Override platform's default ABI.
     ///
-    /// The previous ABI will be overwritten.
+    /// The previous ABIs (including the ones set by [`Self::abis`]) will be overwritten.
     pub fn abi<S1: AsRef<OsStr>>(self, abi: S1) -> AdbInstallMultiPackage<'a, S1> {
         AdbInstallMultiPackage {
             acb: self.acb,
             l: self.l,
             r: self.r,
             t: self.t,
-            s: self.s,
+            install_location: self.install_location,
             d: self.d,
             g: self.g,
             abi: Some(abi),
+            abis: Vec::new(),
             instant: self.instant,
             no_streaming: self.no_streaming,
             streaming: self.streaming,
@@ -853,6 +1339,7 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiPackage<'a, S> {
             date_check_agent: self.date_check_agent,
             version_check_agent: self.version_check_agent,
             local_agent: self.local_agent,
+            pm_args: self.pm_args,
             packages: self.packages,
         }
     }
@@ -911,6 +1398,29 @@ impl<'a, S: AsRef<OsStr>> AdbInstallMultiPackage<'a, S> {
         self
     }
 
+    /// Appends an extra argument for `pm install`, after the standard flags but before the packages.
+    ///
+    /// The argument is forwarded verbatim to the package manager,
+    /// so options not covered by this builder (e.g. `--install-location 1`, `--full`, `--dont-kill`)
+    /// can be used. See `adb shell pm help` for the available options.
+    pub fn pm_arg<T: AsRef<OsStr>>(mut self, arg: T) -> Self {
+        self.pm_args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Appends extra arguments for `pm install`, after the standard flags but before the packages.
+    ///
+    /// See [`Self::pm_arg`] for more information.
+    pub fn pm_args<T, I>(mut self, args: I) -> Self
+    where
+        T: AsRef<OsStr>,
+        I: IntoIterator<Item = T>,
+    {
+        self.pm_args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
     /// `PACKAGE...`: The packages to install.
     ///
     /// The previous packages will be overwritten.
@@ -943,8 +1453,8 @@ where
         if self.t {
             cmd.arg("-t");
         }
-        if self.s {
-            cmd.arg("-s");
+        if let Some(location) = self.install_location {
+            cmd.args(location.args());
         }
         if self.d {
             cmd.arg("-d");
@@ -955,6 +1465,9 @@ where
         if let Some(abi) = self.abi {
             cmd.arg("--abi").arg(abi);
         }
+        for abi in self.abis {
+            cmd.arg("--abi").arg(abi);
+        }
         if self.instant {
             cmd.arg("--instant");
         }
@@ -982,15 +1495,28 @@ where
         if self.local_agent {
             cmd.arg("--local-agent");
         }
+        cmd.args(self.pm_args);
         cmd.args(self.packages);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(&self.abi)?;
+        process::check_nul(&self.abis)?;
+        process::check_nul(&self.pm_args)?;
+        process::check_nul(&self.packages)
+    }
 }
 
 impl Adb {
     /// `install-multi-package [-lrtsdg] [--instant] PACKAGE...`: Push one or more packages to the device and install them atomically
     /// - `-r`: Replace existing application.
     /// - `-t`: Allow test packages.
+    /// - `-s`: Install the application on the shared mass storage (SD card).
     /// - `-d`: Allow version code downgrade (debuggable packages only).
     /// - `-g`: Grant all runtime permissions.
     /// - `--abi ABI`: Override platform's default ABI.
@@ -1018,7 +1544,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb install-multi-package -r /path/to/app1.apk /path/to/app2.apk` failed");
     /// ```
-    pub fn install_multi_package<S, I>(&self, packages: I) -> AdbInstallMultiPackage<S>
+    pub fn install_multi_package<S, I>(&self, packages: I) -> AdbInstallMultiPackage<'_, S>
     where
         S: AsRef<OsStr>,
         I: IntoIterator<Item = S>,
@@ -1037,6 +1563,7 @@ impl<'a> AdbCommandBuilder<'a> {
     /// `install-multi-package [-lrtsdg] [--instant] PACKAGE...`: Push one or more packages to the device and install them atomically
     /// - `-r`: Replace existing application.
     /// - `-t`: Allow test packages.
+    /// - `-s`: Install the application on the shared mass storage (SD card).
     /// - `-d`: Allow version code downgrade (debuggable packages only).
     /// - `-g`: Grant all runtime permissions.
     /// - `--abi ABI`: Override platform's default ABI.
@@ -1117,6 +1644,14 @@ impl<'a, S: AsRef<OsStr>> AdbCommand for AdbUninstall<'a, S> {
         cmd.arg(self.application_id);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.application_id])
+    }
 }
 
 impl Adb {
@@ -1135,7 +1670,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb uninstall com.example.app` failed");
     /// ```
-    pub fn uninstall<S: AsRef<OsStr>>(&self, application_id: S) -> AdbUninstall<S> {
+    pub fn uninstall<S: AsRef<OsStr>>(&self, application_id: S) -> AdbUninstall<'_, S> {
         AdbUninstall::new(self.command(), application_id)
     }
 }
@@ -1150,3 +1685,801 @@ impl<'a> AdbCommandBuilder<'a> {
         AdbUninstall::new(self, application_id)
     }
 }
+
+/// The id of an install session, created by `pm install-create`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InstallSessionId(pub u32);
+
+impl Display for InstallSessionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for InstallSessionId {
+    type Err = AdbError;
+
+    /// Parses the session id from the output of `pm install-create`,
+    /// i.e. `Success: created install session [ID]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.lines()
+            .map(str::trim)
+            .find_map(|line| line.strip_prefix("Success: created install session ["))
+            .and_then(|id| id.strip_suffix(']'))
+            .ok_or_else(|| {
+                ParseError::with_description(s, "InstallSessionId", "No created install session")
+                    .into()
+            })
+            .and_then(|id| {
+                id.parse()
+                    .map(InstallSessionId)
+                    .map_err(|e| ParseError::with_source(id, "InstallSessionId", e).into())
+            })
+    }
+}
+
+/// `shell pm install-create [-rtdg]`: Create an install session.
+/// - `-r`: Replace existing application.
+/// - `-t`: Allow test packages.
+/// - `-d`: Allow version code downgrade (debuggable packages only).
+/// - `-g`: Grant all runtime permissions.
+///
+/// See also `adb shell pm help` for more options.
+#[derive(Debug, Clone)]
+pub struct AdbInstallCreate<'a> {
+    acb: AdbCommandBuilder<'a>,
+    /// `-r`: Replace existing application.
+    r: bool,
+    /// `-t`: Allow test packages.
+    t: bool,
+    /// `-d`: Allow version code downgrade (debuggable packages only).
+    d: bool,
+    /// `-g`: Grant all runtime permissions.
+    g: bool,
+}
+
+impl<'a> AdbInstallCreate<'a> {
+    fn new(acb: AdbCommandBuilder<'a>) -> Self {
+        Self {
+            acb,
+            r: false,
+            t: false,
+            d: false,
+            g: false,
+        }
+    }
+
+    /// `-r`: Replace existing application.
+    pub fn r(mut self) -> Self {
+        self.r = true;
+        self
+    }
+
+    /// `-t`: Allow test packages.
+    pub fn t(mut self) -> Self {
+        self.t = true;
+        self
+    }
+
+    /// `-d`: Allow version code downgrade (debuggable packages only).
+    pub fn d(mut self) -> Self {
+        self.d = true;
+        self
+    }
+
+    /// `-g`: Grant all runtime permissions.
+    pub fn g(mut self) -> Self {
+        self.g = true;
+        self
+    }
+
+    /// Creates the install session, returning its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command fails or the session id can't be found in its output.
+    pub fn run(self) -> AdbResult<InstallSessionId> {
+        process::stdout_string(self.output()?)?.parse()
+    }
+}
+
+impl<'a> AdbCommand for AdbInstallCreate<'a> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("shell").arg("pm").arg("install-create");
+        if self.r {
+            cmd.arg("-r");
+        }
+        if self.t {
+            cmd.arg("-t");
+        }
+        if self.d {
+            cmd.arg("-d");
+        }
+        if self.g {
+            cmd.arg("-g");
+        }
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+}
+
+/// `shell pm install-write SESSION NAME PATH`: Write an APK on the device into an install session.
+#[derive(Debug, Clone)]
+pub struct AdbInstallWrite<'a, S1: AsRef<OsStr>, S2: AsRef<OsStr>> {
+    acb: AdbCommandBuilder<'a>,
+    /// `SESSION`: The install session.
+    session: InstallSessionId,
+    /// `NAME`: The name of the APK in the session, e.g. `base.apk`.
+    name: S1,
+    /// `PATH`: The path of the APK on the device.
+    path: S2,
+}
+
+impl<'a, S1: AsRef<OsStr>, S2: AsRef<OsStr>> AdbCommand for AdbInstallWrite<'a, S1, S2> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("shell")
+            .arg("pm")
+            .arg("install-write")
+            .arg(self.session.to_string())
+            .arg(self.name)
+            .arg(self.path);
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([self.name.as_ref(), self.path.as_ref()])
+    }
+}
+
+/// `shell pm install-commit SESSION`: Commit an install session, installing all written APKs.
+#[derive(Debug, Clone)]
+pub struct AdbInstallCommit<'a> {
+    acb: AdbCommandBuilder<'a>,
+    /// `SESSION`: The install session.
+    session: InstallSessionId,
+}
+
+impl<'a> AdbCommand for AdbInstallCommit<'a> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("shell")
+            .arg("pm")
+            .arg("install-commit")
+            .arg(self.session.to_string());
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+}
+
+/// `shell pm install-abandon SESSION`: Abandon an install session, deleting all written APKs.
+#[derive(Debug, Clone)]
+pub struct AdbInstallAbandon<'a> {
+    acb: AdbCommandBuilder<'a>,
+    /// `SESSION`: The install session.
+    session: InstallSessionId,
+}
+
+impl<'a> AdbCommand for AdbInstallAbandon<'a> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("shell")
+            .arg("pm")
+            .arg("install-abandon")
+            .arg(self.session.to_string());
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+}
+
+/// The parameters of an install session created by [`Adb::create_install_session`],
+/// i.e. the flags of `pm install-create`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InstallSessionParams {
+    /// `-r`: Replace existing application.
+    pub replace: bool,
+    /// `-t`: Allow test packages.
+    pub allow_test: bool,
+    /// `-d`: Allow version code downgrade (debuggable packages only).
+    pub allow_downgrade: bool,
+    /// `-g`: Grant all runtime permissions.
+    pub grant_permissions: bool,
+}
+
+impl InstallSessionParams {
+    /// Applies the parameters to `install-create`.
+    fn apply(self, mut create: AdbInstallCreate) -> AdbInstallCreate {
+        create.r = self.replace;
+        create.t = self.allow_test;
+        create.d = self.allow_downgrade;
+        create.g = self.grant_permissions;
+        create
+    }
+}
+
+/// Checks the output of a `pm install-*` command reporting `Success` (e.g. `Success: streamed 42 bytes`).
+///
+/// # Errors
+///
+/// Returns [`AdbError::ShellCommandFailed`] with the output if it reports no success,
+/// e.g. `Error: java.lang.IllegalStateException: ...`.
+fn check_pm_success(output: &Output) -> AdbResult<()> {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if output.status.success() && stdout.trim_start().starts_with("Success") {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(AdbError::ShellCommandFailed(
+        format!("{}{}", stdout, stderr).trim().to_string(),
+    ))
+}
+
+impl Adb {
+    /// `shell pm install-create`: Create an install session with `params`, returning its id.
+    ///
+    /// Unlike the one-shot install commands, a session allows multi-step installs:
+    /// write any number of APKs with [`Self::write_session`], then install them atomically
+    /// with [`Self::commit_session`] or discard them with [`Self::abandon_session`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command fails or the session id can't be found in its output.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # use adbr::command::app_installation::InstallSessionParams;
+    /// # let adb = Adb::new().unwrap();
+    /// let params = InstallSessionParams {
+    ///     replace: true,
+    ///     ..Default::default()
+    /// };
+    /// let session = adb.create_install_session(params).unwrap();
+    /// let written = adb
+    ///     .write_session(session, "base.apk", "app/base.apk")
+    ///     .and_then(|_| adb.write_session(session, "split_config.arm64_v8a.apk", "app/split.apk"));
+    /// match written {
+    ///     Ok(()) => adb.commit_session(session).unwrap(),
+    ///     Err(_) => adb.abandon_session(session).unwrap(),
+    /// }
+    /// ```
+    pub fn create_install_session(
+        &self,
+        params: InstallSessionParams,
+    ) -> AdbResult<InstallSessionId> {
+        params.apply(self.install_create()).run()
+    }
+
+    /// Writes the local APK `apk` into the install `session` as `name` (e.g. `base.apk`).
+    ///
+    /// The APK is pushed to a temporary file in `/data/local/tmp`,
+    /// written with `shell pm install-write SESSION NAME PATH`, then the temporary file is removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the push fails,
+    /// or [`AdbError::ShellCommandFailed`] if `install-write` doesn't report a success.
+    pub fn write_session<S: AsRef<OsStr>, P: AsRef<Path>>(
+        &self,
+        session: InstallSessionId,
+        name: S,
+        apk: P,
+    ) -> AdbResult<()> {
+        let remote = format!("/data/local/tmp/adbr-session-{}.apk", session);
+        process::check(&self.push([apk.as_ref()], &remote).output()?)?;
+        let result = self
+            .install_write(session, name, &remote)
+            .output()
+            .and_then(|output| check_pm_success(&output));
+        // the written APK is kept by the session, so the temporary file is no longer needed
+        let _ = self.shell().args(["rm", "-f", &remote]).output();
+        result
+    }
+
+    /// `shell pm install-commit SESSION`: Commit the install `session`, installing all written APKs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::InstallFailed`] if the package manager reports `Failure [REASON]`.
+    pub fn commit_session(&self, session: InstallSessionId) -> AdbResult<()> {
+        InstallOutcome::from_output(&self.install_commit(session).output()?)?.into_result()
+    }
+
+    /// `shell pm install-abandon SESSION`: Abandon the install `session`, deleting all written APKs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::ShellCommandFailed`] if `install-abandon` doesn't report a success,
+    /// e.g. the session doesn't exist.
+    pub fn abandon_session(&self, session: InstallSessionId) -> AdbResult<()> {
+        check_pm_success(&self.install_abandon(session).output()?)
+    }
+
+    /// `shell pm install-create [-rtdg]`: Create an install session.
+    /// - `-r`: Replace existing application.
+    /// - `-t`: Allow test packages.
+    /// - `-d`: Allow version code downgrade (debuggable packages only).
+    /// - `-g`: Grant all runtime permissions.
+    ///
+    /// Write APKs into the session with [`Adb::install_write`],
+    /// then install them with [`Adb::install_commit`] or discard them with [`Adb::install_abandon`].
+    ///
+    /// # Examples
+    ///
+    /// `adb shell pm install-create -r`, `adb shell pm install-write SESSION base.apk /data/local/tmp/base.apk`,
+    /// then `adb shell pm install-commit SESSION`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let session = adb.install_create()
+    ///     .r()
+    ///     .run()
+    ///     .expect("`adb shell pm install-create -r` failed");
+    /// adb.install_write(session, "base.apk", "/data/local/tmp/base.apk")
+    ///     .status()
+    ///     .expect("`adb shell pm install-write` failed");
+    /// adb.install_commit(session)
+    ///     .status()
+    ///     .expect("`adb shell pm install-commit` failed");
+    /// ```
+    pub fn install_create(&self) -> AdbInstallCreate<'_> {
+        AdbInstallCreate::new(self.command())
+    }
+
+    /// `shell pm install-write SESSION NAME PATH`: Write an APK on the device into an install session.
+    ///
+    /// See [`Adb::install_create`] for more information.
+    pub fn install_write<S1, S2>(
+        &self,
+        session: InstallSessionId,
+        name: S1,
+        path: S2,
+    ) -> AdbInstallWrite<'_, S1, S2>
+    where
+        S1: AsRef<OsStr>,
+        S2: AsRef<OsStr>,
+    {
+        self.command().install_write(session, name, path)
+    }
+
+    /// `shell pm install-commit SESSION`: Commit an install session, installing all written APKs.
+    ///
+    /// See [`Adb::install_create`] for more information.
+    pub fn install_commit(&self, session: InstallSessionId) -> AdbInstallCommit<'_> {
+        self.command().install_commit(session)
+    }
+
+    /// `shell pm install-abandon SESSION`: Abandon an install session, deleting all written APKs.
+    ///
+    /// See [`Adb::install_create`] for more information.
+    pub fn install_abandon(&self, session: InstallSessionId) -> AdbInstallAbandon<'_> {
+        self.command().install_abandon(session)
+    }
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `shell pm install-create [-rtdg]`: Create an install session.
+    ///
+    /// See [`Adb::install_create`] for more information.
+    pub fn install_create(self) -> AdbInstallCreate<'a> {
+        AdbInstallCreate::new(self)
+    }
+
+    /// `shell pm install-write SESSION NAME PATH`: Write an APK on the device into an install session.
+    ///
+    /// See [`Adb::install_create`] for more information.
+    pub fn install_write<S1, S2>(
+        self,
+        session: InstallSessionId,
+        name: S1,
+        path: S2,
+    ) -> AdbInstallWrite<'a, S1, S2>
+    where
+        S1: AsRef<OsStr>,
+        S2: AsRef<OsStr>,
+    {
+        AdbInstallWrite {
+            acb: self,
+            session,
+            name,
+            path,
+        }
+    }
+
+    /// `shell pm install-commit SESSION`: Commit an install session, installing all written APKs.
+    ///
+    /// See [`Adb::install_create`] for more information.
+    pub fn install_commit(self, session: InstallSessionId) -> AdbInstallCommit<'a> {
+        AdbInstallCommit { acb: self, session }
+    }
+
+    /// `shell pm install-abandon SESSION`: Abandon an install session, deleting all written APKs.
+    ///
+    /// See [`Adb::install_create`] for more information.
+    pub fn install_abandon(self, session: InstallSessionId) -> AdbInstallAbandon<'a> {
+        AdbInstallAbandon { acb: self, session }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::{args, output, Sh};
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_then_start() {
+        let adb = Adb::default();
+        let success = || {
+            Sh::new(
+                adb.command(),
+                "echo 'Performing Streamed Install'; echo Success",
+            )
+        };
+        let failure = || {
+            Sh::new(
+                adb.command(),
+                "echo 'adb: failed to install app.apk: Failure [INSTALL_FAILED_VERSION_DOWNGRADE]'; exit 1",
+            )
+        };
+        let started = || {
+            Sh::new(
+                adb.command(),
+                "echo 'Starting: Intent { cmp=com.example/.Main }'",
+            )
+        };
+        let missing = || {
+            Sh::new(
+                adb.command(),
+                "echo 'Error type 3'; echo 'Error: Activity class {com.example/.Main} does not exist.'",
+            )
+        };
+
+        let marker =
+            std::env::temp_dir().join(format!("adbr-install-then-start-{}", std::process::id()));
+        let marked = || {
+            Sh::new(
+                adb.command(),
+                format!(
+                    "touch '{}'; echo 'Starting: Intent {{ cmp=com.example/.Main }}'",
+                    marker.display()
+                ),
+            )
+        };
+
+        assert!(install_then_start(success(), started()).is_ok());
+        // the activity isn't started if the installation fails
+        assert!(matches!(
+            install_then_start(failure(), marked()),
+            Err(AdbError::InstallFailed(_))
+        ));
+        assert!(!marker.exists());
+        assert!(install_then_start(success(), marked()).is_ok());
+        assert!(marker.exists());
+        std::fs::remove_file(&marker).unwrap();
+        assert!(matches!(
+            install_then_start(success(), missing()),
+            Err(AdbError::ShellCommandFailed(line)) if line.ends_with("does not exist.")
+        ));
+    }
+
+    #[test]
+    fn test_install_outcome_from_str() {
+        assert_eq!(
+            "Performing Streamed Install\nSuccess\n"
+                .parse::<InstallOutcome>()
+                .unwrap(),
+            InstallOutcome::Success
+        );
+        assert_eq!(
+            "Performing Streamed Install\n\
+             adb: failed to install app.apk: Failure [INSTALL_FAILED_ALREADY_EXISTS: Attempt to re-install]\n"
+                .parse::<InstallOutcome>()
+                .unwrap(),
+            InstallOutcome::Failure(
+                "INSTALL_FAILED_ALREADY_EXISTS: Attempt to re-install".to_string()
+            )
+        );
+        assert_eq!(
+            "Failure [INSTALL_FAILED_INSUFFICIENT_STORAGE]"
+                .parse::<InstallOutcome>()
+                .unwrap(),
+            InstallOutcome::Failure("INSTALL_FAILED_INSUFFICIENT_STORAGE".to_string())
+        );
+        assert!("Performing Streamed Install\n"
+            .parse::<InstallOutcome>()
+            .is_err());
+        assert!("".parse::<InstallOutcome>().is_err());
+    }
+
+    #[test]
+    fn test_install_outcome_into_result() {
+        let result = |code, stdout, stderr| {
+            InstallOutcome::from_output(&output(code, stdout, stderr))?.into_result()
+        };
+
+        assert!(result(0, "Performing Streamed Install\nSuccess\n", "").is_ok());
+        // some adb versions exit with 0 even if the installation fails
+        assert!(matches!(
+            result(0, "Failure [INSTALL_FAILED_VERSION_DOWNGRADE]\n", ""),
+            Err(AdbError::InstallFailed(
+                InstallFailureReason::VersionDowngrade
+            ))
+        ));
+        assert!(matches!(
+            result(
+                1,
+                "Performing Streamed Install\n",
+                "adb: failed to install app.apk: Failure [INSTALL_FAILED_INSUFFICIENT_STORAGE]\n"
+            ),
+            Err(AdbError::InstallFailed(
+                InstallFailureReason::InsufficientStorage
+            ))
+        ));
+        // the exit status alone doesn't tell whether the package is installed
+        assert!(result(0, "Performing Streamed Install\n", "")
+            .unwrap_err()
+            .is_parse());
+        assert!(matches!(
+            result(1, "", "error: device offline\n"),
+            Err(AdbError::InstallFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_install_failure_reason_from_str() {
+        for (s, reason) in [
+            (
+                "INSTALL_FAILED_ALREADY_EXISTS: Attempt to re-install com.example.app",
+                InstallFailureReason::AlreadyExists,
+            ),
+            (
+                "INSTALL_FAILED_INSUFFICIENT_STORAGE",
+                InstallFailureReason::InsufficientStorage,
+            ),
+            (
+                "INSTALL_FAILED_UPDATE_INCOMPATIBLE: Existing package signatures do not match",
+                InstallFailureReason::UpdateIncompatible,
+            ),
+            (
+                "INSTALL_PARSE_FAILED_NO_CERTIFICATES: No signature found",
+                InstallFailureReason::Other(
+                    "INSTALL_PARSE_FAILED_NO_CERTIFICATES: No signature found".to_string(),
+                ),
+            ),
+        ] {
+            assert_eq!(s.parse::<InstallFailureReason>().unwrap(), reason);
+        }
+        assert_eq!(
+            InstallFailureReason::VersionDowngrade
+                .to_string()
+                .parse::<InstallFailureReason>()
+                .unwrap(),
+            InstallFailureReason::VersionDowngrade
+        );
+    }
+
+    #[test]
+    fn test_install_outcome_display() {
+        for outcome in [
+            InstallOutcome::Success,
+            InstallOutcome::Failure("INSTALL_FAILED_INVALID_APK".to_string()),
+        ] {
+            assert_eq!(
+                outcome.to_string().parse::<InstallOutcome>().unwrap(),
+                outcome
+            );
+        }
+    }
+
+    #[test]
+    fn test_install_session_id_from_str() {
+        assert_eq!(
+            "Success: created install session [1234567890]\n"
+                .parse::<InstallSessionId>()
+                .unwrap(),
+            InstallSessionId(1234567890)
+        );
+        assert_eq!(
+            "\r\nSuccess: created install session [42]\r\n"
+                .parse::<InstallSessionId>()
+                .unwrap(),
+            InstallSessionId(42)
+        );
+        assert!("Success: created install session []"
+            .parse::<InstallSessionId>()
+            .is_err());
+        assert!("Success: created install session [abc]"
+            .parse::<InstallSessionId>()
+            .is_err());
+        assert!("Error: java.lang.IllegalArgumentException"
+            .parse::<InstallSessionId>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_install_session_args() {
+        let adb = Adb::default();
+        let session = InstallSessionId(42);
+        assert_eq!(
+            args(adb.install_create().r().g()),
+            ["shell", "pm", "install-create", "-r", "-g"]
+        );
+        assert_eq!(
+            args(adb.install_write(session, "base.apk", "/data/local/tmp/a.apk")),
+            [
+                "shell",
+                "pm",
+                "install-write",
+                "42",
+                "base.apk",
+                "/data/local/tmp/a.apk"
+            ]
+        );
+        assert_eq!(
+            args(adb.install_commit(session)),
+            ["shell", "pm", "install-commit", "42"]
+        );
+        assert_eq!(
+            args(adb.install_abandon(session)),
+            ["shell", "pm", "install-abandon", "42"]
+        );
+    }
+
+    #[test]
+    fn test_install_session_params() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(InstallSessionParams::default().apply(adb.install_create())),
+            ["shell", "pm", "install-create"]
+        );
+        let params = InstallSessionParams {
+            replace: true,
+            allow_test: true,
+            allow_downgrade: true,
+            grant_permissions: true,
+        };
+        assert_eq!(
+            args(params.apply(adb.install_create())),
+            ["shell", "pm", "install-create", "-r", "-t", "-d", "-g"]
+        );
+    }
+
+    #[test]
+    fn test_check_pm_success() {
+        assert!(check_pm_success(&output(0, "Success: streamed 42 bytes\n", "")).is_ok());
+        assert!(check_pm_success(&output(0, "Success\n", "")).is_ok());
+        assert!(matches!(
+            check_pm_success(&output(0, "Error: java.lang.SecurityException\n", "")),
+            Err(AdbError::ShellCommandFailed(out)) if out == "Error: java.lang.SecurityException"
+        ));
+        assert!(check_pm_success(&output(1, "Success\n", "")).is_err());
+    }
+
+    #[test]
+    fn test_install_location() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.install("app.apk").s()),
+            ["install", "-s", "app.apk"]
+        );
+        assert_eq!(
+            args(
+                adb.install("app.apk")
+                    .s()
+                    .install_location(InstallLocation::Internal)
+            ),
+            ["install", "-f", "app.apk"]
+        );
+        assert_eq!(
+            args(
+                adb.install_multiple(["base.apk"])
+                    .install_location(InstallLocation::Auto)
+            ),
+            ["install-multiple", "--install-location", "0", "base.apk"]
+        );
+        assert_eq!(
+            args(
+                adb.install_multi_package(["a.apk"])
+                    .install_location(InstallLocation::External)
+            ),
+            ["install-multi-package", "-s", "a.apk"]
+        );
+    }
+
+    #[test]
+    fn test_abis() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(
+                adb.install_multiple(["base.apk", "split.apk"])
+                    .abis(["arm64-v8a", "armeabi-v7a"])
+            ),
+            [
+                "install-multiple",
+                "--abi",
+                "arm64-v8a",
+                "--abi",
+                "armeabi-v7a",
+                "base.apk",
+                "split.apk"
+            ]
+        );
+        assert_eq!(
+            args(
+                adb.install_multi_package(["a.apk", "b.apk"])
+                    .abi("x86")
+                    .abis(["x86_64", "x86"])
+            ),
+            [
+                "install-multi-package",
+                "--abi",
+                "x86_64",
+                "--abi",
+                "x86",
+                "a.apk",
+                "b.apk"
+            ]
+        );
+        assert_eq!(
+            args(
+                adb.install_multiple(["base.apk"])
+                    .abis(["x86_64", "x86"])
+                    .abi("arm64-v8a")
+            ),
+            ["install-multiple", "--abi", "arm64-v8a", "base.apk"]
+        );
+    }
+
+    #[test]
+    fn test_pm_args() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(
+                adb.install("app.apk")
+                    .r()
+                    .pm_arg("--dont-kill")
+                    .pm_args(["--install-location", "1"])
+            ),
+            [
+                "install",
+                "-r",
+                "--dont-kill",
+                "--install-location",
+                "1",
+                "app.apk"
+            ]
+        );
+        assert_eq!(
+            args(
+                adb.install_multiple(["base.apk", "split.apk"])
+                    .pm_arg("--full")
+            ),
+            ["install-multiple", "--full", "base.apk", "split.apk"]
+        );
+        assert_eq!(
+            args(
+                adb.install_multi_package(["a.apk", "b.apk"])
+                    .g()
+                    .pm_arg("--full")
+            ),
+            ["install-multi-package", "-g", "--full", "a.apk", "b.apk"]
+        );
+    }
+}
@@ -0,0 +1,486 @@
+//! App management commands, run through the remote shell.
+//!
+//! - `shell am force-stop PACKAGE`: Force stop everything associated with `PACKAGE`.
+//! - `shell pm clear PACKAGE`: Delete all data associated with `PACKAGE`.
+//! - `shell pidof NAME`: Find the PIDs of the processes named `NAME`, see [`Adb::pidof`].
+//! - `shell kill PID`: Kill a process, see [`Adb::kill_pid`].
+//! - `shell dumpsys meminfo PACKAGE`: Memory usage of `PACKAGE`, see [`Adb::meminfo`].
+
+use std::ffi::OsStr;
+use std::process::Command;
+use std::str::FromStr;
+
+use crate::command::{process, AdbCommandBuilder, ShellResult};
+use crate::error::ParseError;
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
+
+/// `shell am force-stop PACKAGE`: Force stop everything associated with `PACKAGE`.
+#[derive(Debug, Clone)]
+pub struct AdbForceStop<'a, S: AsRef<OsStr>> {
+    acb: AdbCommandBuilder<'a>,
+    /// `PACKAGE`: The package to stop.
+    package: S,
+}
+
+impl<'a, S: AsRef<OsStr>> AdbForceStop<'a, S> {
+    /// Executes the command like [`AdbCommand::checked_status`].
+    pub fn run(self) -> AdbResult<()> {
+        self.checked_status()
+    }
+}
+
+impl<'a, S: AsRef<OsStr>> AdbCommand for AdbForceStop<'a, S> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("shell")
+            .arg("am")
+            .arg("force-stop")
+            .arg(self.package);
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.package])
+    }
+}
+
+impl Adb {
+    /// `shell am force-stop PACKAGE`: Force stop everything associated with `PACKAGE`.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell am force-stop com.example.app`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.force_stop("com.example.app")
+    ///     .run()
+    ///     .expect("`adb shell am force-stop com.example.app` failed");
+    /// ```
+    pub fn force_stop<S: AsRef<OsStr>>(&self, package: S) -> AdbForceStop<'_, S> {
+        self.command().force_stop(package)
+    }
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `shell am force-stop PACKAGE`: Force stop everything associated with `PACKAGE`.
+    ///
+    /// See [`Adb::force_stop`] for more information.
+    pub fn force_stop<S: AsRef<OsStr>>(self, package: S) -> AdbForceStop<'a, S> {
+        AdbForceStop { acb: self, package }
+    }
+}
+
+/// Parses the output of `pm clear PACKAGE`, returning whether the data was cleared,
+/// since `pm clear` exits with 0 even if the data isn't cleared.
+///
+/// # Errors
+///
+/// Returns [`AdbError::PackageNotFound`] if `output` reports an unknown package
+/// (i.e. `Unknown package: PACKAGE`),
+/// or [`AdbError::ShellCommandFailed`] if it reports neither `Success` nor `Failed`.
+fn parse_pm_clear(output: &str, package: &str) -> AdbResult<bool> {
+    if output.lines().any(|line| line.trim() == "Success") {
+        Ok(true)
+    } else if output.contains("Unknown package:") {
+        Err(AdbError::PackageNotFound(package.to_string()))
+    } else if output.lines().any(|line| line.trim().starts_with("Failed")) {
+        Ok(false)
+    } else {
+        Err(AdbError::ShellCommandFailed(output.to_string()))
+    }
+}
+
+/// `shell pm clear PACKAGE`: Delete all data associated with `PACKAGE`.
+#[derive(Debug, Clone)]
+pub struct AdbClearAppData<'a, S: AsRef<OsStr>> {
+    acb: AdbCommandBuilder<'a>,
+    /// `PACKAGE`: The package to clear.
+    package: S,
+}
+
+impl<'a, S: AsRef<OsStr>> AdbClearAppData<'a, S> {
+    /// Executes the command, checking that `pm clear` printed `Success`.
+    ///
+    /// # Errors
+    ///
+    /// Like [`Self::cleared`], and returns [`AdbError::ShellCommandFailed`]
+    /// with the output of `pm clear` if it printed `Failed`,
+    /// since the exit status of `pm clear` doesn't reflect the failure.
+    pub fn run(self) -> AdbResult<()> {
+        match self.clear()? {
+            (true, _) => Ok(()),
+            (false, output) => Err(AdbError::ShellCommandFailed(output)),
+        }
+    }
+
+    /// Executes the command, returning whether `pm clear` printed `Success` rather than `Failed`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::PackageNotFound`] if the package isn't installed,
+    /// [`AdbError::NonZeroExit`] if adb fails otherwise,
+    /// or [`AdbError::ShellCommandFailed`] if `pm clear` printed something else.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// assert!(adb.clear_app_data("com.example.app").cleared().unwrap());
+    /// ```
+    pub fn cleared(self) -> AdbResult<bool> {
+        self.clear().map(|(cleared, _)| cleared)
+    }
+
+    /// Executes the command, returning whether the data was cleared
+    /// and the output (stdout then stderr) of `pm clear`, see [`parse_pm_clear`].
+    fn clear(self) -> AdbResult<(bool, String)> {
+        let package = self.package.as_ref().to_string_lossy().into_owned();
+        let output = self.output()?;
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let result = parse_pm_clear(&text, &package);
+        if matches!(result, Err(AdbError::ShellCommandFailed(_))) {
+            process::check(&output)?;
+        }
+        result.map(|cleared| (cleared, text))
+    }
+}
+
+impl<'a, S: AsRef<OsStr>> AdbCommand for AdbClearAppData<'a, S> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("shell").arg("pm").arg("clear").arg(self.package);
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.package])
+    }
+}
+
+impl Adb {
+    /// `shell pm clear PACKAGE`: Delete all data associated with `PACKAGE`.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell pm clear com.example.app`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.clear_app_data("com.example.app")
+    ///     .run()
+    ///     .expect("`adb shell pm clear com.example.app` failed");
+    /// ```
+    pub fn clear_app_data<S: AsRef<OsStr>>(&self, package: S) -> AdbClearAppData<'_, S> {
+        self.command().clear_app_data(package)
+    }
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `shell pm clear PACKAGE`: Delete all data associated with `PACKAGE`.
+    ///
+    /// See [`Adb::clear_app_data`] for more information.
+    pub fn clear_app_data<S: AsRef<OsStr>>(self, package: S) -> AdbClearAppData<'a, S> {
+        AdbClearAppData { acb: self, package }
+    }
+}
+
+/// Parses the space-separated PIDs printed by `pidof`.
+fn parse_pids(stdout: &str) -> AdbResult<Vec<u32>> {
+    stdout
+        .split_whitespace()
+        .map(|pid| {
+            pid.parse()
+                .map_err(|e| ParseError::with_source(pid, "PID (u32)", e).into())
+        })
+        .collect()
+}
+
+/// The error of a failed remote command, with its stderr or stdout if stderr is empty.
+pub(crate) fn shell_failure(result: ShellResult) -> AdbError {
+    if result.stderr.is_empty() {
+        AdbError::ShellCommandFailed(result.stdout)
+    } else {
+        AdbError::ShellCommandFailed(result.stderr)
+    }
+}
+
+/// The memory usage of an app, parsed from the `App Summary` of `dumpsys meminfo PACKAGE`.
+///
+/// All values are PSS in kilobytes, rows missing from the output are [`None`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemInfo {
+    /// `Java Heap`: The Java heap.
+    pub java_heap_kb: Option<u64>,
+    /// `Native Heap`: The native heap.
+    pub native_heap_kb: Option<u64>,
+    /// `Code`: The mapped code, e.g. `.so`, `.dex` and `.oat` files.
+    pub code_kb: Option<u64>,
+    /// `Stack`: The thread stacks.
+    pub stack_kb: Option<u64>,
+    /// `Graphics`: The GPU memory, e.g. textures.
+    pub graphics_kb: Option<u64>,
+    /// `TOTAL PSS` (or `TOTAL` on older versions): The total PSS.
+    pub total_pss_kb: Option<u64>,
+}
+
+impl FromStr for MemInfo {
+    type Err = AdbError;
+
+    /// Parses the labeled rows of the `App Summary`, e.g. `Java Heap:  4492  15932`,
+    /// falling back to the `TOTAL` row of the main table for the total PSS.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut info = MemInfo::default();
+        let mut table_total = None;
+        let summary = s.find("App Summary").map(|i| &s[i..]);
+        for line in s.lines() {
+            let mut tokens = line.split_whitespace();
+            if tokens.next() == Some("TOTAL") {
+                table_total = table_total.or(tokens.next().and_then(|n| n.parse().ok()));
+            }
+        }
+        for line in summary.unwrap_or_default().lines() {
+            let Some((label, rest)) = line.split_once(':') else {
+                continue;
+            };
+            let Some(value) = rest.split_whitespace().next() else {
+                continue;
+            };
+            let field = match label.trim() {
+                "Java Heap" => &mut info.java_heap_kb,
+                "Native Heap" => &mut info.native_heap_kb,
+                "Code" => &mut info.code_kb,
+                "Stack" => &mut info.stack_kb,
+                "Graphics" => &mut info.graphics_kb,
+                "TOTAL PSS" | "TOTAL" => &mut info.total_pss_kb,
+                _ => continue,
+            };
+            *field = Some(
+                value
+                    .parse()
+                    .map_err(|e| ParseError::with_source(value, "u64", e))?,
+            );
+        }
+        info.total_pss_kb = info.total_pss_kb.or(table_total);
+        if info == MemInfo::default() {
+            return Err(
+                ParseError::with_description(s, "MemInfo", "Missing meminfo summary").into(),
+            );
+        }
+        Ok(info)
+    }
+}
+
+impl Adb {
+    /// `shell dumpsys meminfo PACKAGE`: Get the memory usage of the running `package`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::ShellCommandFailed`] if `package` has no running process,
+    /// or [`AdbError::Parse`] if the output has no recognizable summary.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let info = adb.meminfo("com.example.app").unwrap();
+    /// println!("total PSS: {:?} KB", info.total_pss_kb);
+    /// ```
+    pub fn meminfo(&self, package: &str) -> AdbResult<MemInfo> {
+        let stdout =
+            process::stdout_string(self.shell_cmd(["dumpsys", "meminfo", package]).output()?)?;
+        if stdout.trim_start().starts_with("No process found") {
+            return Err(AdbError::ShellCommandFailed(stdout.trim().to_string()));
+        }
+        stdout.parse()
+    }
+
+    /// `shell pidof NAME`: Find the PIDs of the processes named `name`.
+    ///
+    /// Returns an empty [`Vec`] if no process matches, which `pidof` reports with exit code `1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::ShellCommandFailed`] if `pidof` fails otherwise,
+    /// or [`AdbError::Parse`] if it prints anything but PIDs.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// for pid in adb.pidof("com.example.app").unwrap() {
+    ///     adb.kill_pid(pid).unwrap();
+    /// }
+    /// ```
+    pub fn pidof(&self, name: &str) -> AdbResult<Vec<u32>> {
+        let result = self.shell_run(["pidof", name])?;
+        match result.exit_code {
+            0 => parse_pids(&result.stdout),
+            1 if result.stdout.trim().is_empty() => Ok(Vec::new()),
+            _ => Err(shell_failure(result)),
+        }
+    }
+
+    /// `shell kill PID`: Kill the process `pid` with `SIGTERM`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::ShellCommandFailed`] if `kill` fails,
+    /// e.g. the process doesn't exist or belongs to another user.
+    pub fn kill_pid(&self, pid: u32) -> AdbResult<()> {
+        let result = self.shell_run(["kill".to_string(), pid.to_string()])?;
+        if result.success() {
+            Ok(())
+        } else {
+            Err(shell_failure(result))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_build() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.force_stop("com.example.app")),
+            ["shell", "am", "force-stop", "com.example.app"]
+        );
+        assert_eq!(
+            args(adb.clear_app_data("com.example.app")),
+            ["shell", "pm", "clear", "com.example.app"]
+        );
+    }
+
+    #[test]
+    fn test_parse_pm_clear() {
+        assert!(parse_pm_clear("Success\n", "com.example.app").unwrap());
+        assert!(parse_pm_clear("Success\r\n", "com.example.app").unwrap());
+        assert!(!parse_pm_clear("Failed\n", "com.example.app").unwrap());
+        let unknown = "Exception occurred while executing 'clear':\n\
+                       java.lang.IllegalArgumentException: Unknown package: com.example.app\n";
+        assert!(matches!(
+            parse_pm_clear(unknown, "com.example.app"),
+            Err(AdbError::PackageNotFound(package)) if package == "com.example.app"
+        ));
+        assert!(matches!(
+            parse_pm_clear("", "com.example.app"),
+            Err(AdbError::ShellCommandFailed(_))
+        ));
+        // only `Unknown package:` means the package is missing
+        assert!(matches!(
+            parse_pm_clear("Error: file not found\n", "com.example.app"),
+            Err(AdbError::ShellCommandFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_meminfo() {
+        let summary = "\
+** MEMINFO in pid 1234 [com.example.app] **
+                   Pss  Private  Private  SwapPss      Rss     Heap     Heap     Heap
+                 Total    Dirty    Clean    Dirty    Total     Size    Alloc     Free
+                ------   ------   ------   ------   ------   ------   ------   ------
+  Native Heap     3468     3420        0       20     5256    10240     6144     4095
+        TOTAL    27424    12540     9236       55    82732    14400     9316     5083
+
+ App Summary
+                       Pss(KB)                        Rss(KB)
+                        ------                         ------
+           Java Heap:     4492                          15932
+         Native Heap:     3468                           5256
+                Code:     9236                          56176
+               Stack:      568                            576
+            Graphics:     1156                           1156
+       Private Other:     1464
+              System:     7040
+
+           TOTAL PSS:    27424            TOTAL RSS:    82732      TOTAL SWAP PSS:       55
+";
+        assert_eq!(
+            summary.parse::<MemInfo>().unwrap(),
+            MemInfo {
+                java_heap_kb: Some(4492),
+                native_heap_kb: Some(3468),
+                code_kb: Some(9236),
+                stack_kb: Some(568),
+                graphics_kb: Some(1156),
+                total_pss_kb: Some(27424),
+            }
+        );
+        let old = "\
+ App Summary
+           Java Heap:     4492
+         Native Heap:     3468
+               TOTAL:    27424       TOTAL SWAP (KB):        0
+";
+        assert_eq!(
+            old.parse::<MemInfo>().unwrap(),
+            MemInfo {
+                java_heap_kb: Some(4492),
+                native_heap_kb: Some(3468),
+                total_pss_kb: Some(27424),
+                ..MemInfo::default()
+            }
+        );
+        let table_only = "        TOTAL    27424    12540     9236\n";
+        assert_eq!(
+            table_only.parse::<MemInfo>().unwrap().total_pss_kb,
+            Some(27424)
+        );
+        assert!("No process found for: com.example.app"
+            .parse::<MemInfo>()
+            .unwrap_err()
+            .is_parse());
+    }
+
+    #[test]
+    fn test_parse_pids() {
+        assert_eq!(parse_pids("").unwrap(), Vec::<u32>::new());
+        assert_eq!(parse_pids("\n").unwrap(), Vec::<u32>::new());
+        assert_eq!(parse_pids("1234\n").unwrap(), [1234]);
+        assert_eq!(parse_pids("1234 5678 42\r\n").unwrap(), [1234, 5678, 42]);
+        assert!(parse_pids("1234 abc").is_err());
+    }
+
+    #[test]
+    fn test_shell_failure() {
+        let result = ShellResult {
+            exit_code: 1,
+            stdout: "out".to_string(),
+            stderr: String::new(),
+        };
+        assert!(matches!(
+            shell_failure(result.clone()),
+            AdbError::ShellCommandFailed(s) if s == "out"
+        ));
+        let result = ShellResult {
+            stderr: "No such process".to_string(),
+            ..result
+        };
+        assert!(matches!(
+            shell_failure(result),
+            AdbError::ShellCommandFailed(s) if s == "No such process"
+        ));
+    }
+}
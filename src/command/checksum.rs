@@ -0,0 +1,441 @@
+//! Verification of file transfers by comparing checksums (or sizes) on both sides,
+//! and the remote queries shared with [`AdbPull::skip_unchanged`](super::file_transfer::AdbPull::skip_unchanged).
+//!
+//! The verification itself requires the `verify` feature, which computes the SHA-256 checksums.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+#[cfg(feature = "verify")]
+use std::{ffi::OsString, fs::File, io};
+
+#[cfg(feature = "verify")]
+use sha2::{Digest, Sha256};
+
+use crate::command::{process, shell, AdbCommandBuilder};
+use crate::{AdbCommand, AdbError, AdbResult};
+
+/// A transferred file, with its paths on the host and on the device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Transfer {
+    /// The path on the host.
+    pub(crate) local: PathBuf,
+    /// The path on the device.
+    pub(crate) remote: String,
+}
+
+/// Joins the remote `path` with a relative path (`rel`), either of which may be empty.
+#[cfg(feature = "verify")]
+fn join_remote(path: &str, rel: &str) -> String {
+    match (path, rel) {
+        (path, "") => path.to_string(),
+        ("", rel) => rel.to_string(),
+        (path, rel) => format!("{}/{}", path.trim_end_matches('/'), rel),
+    }
+}
+
+/// The last component of the remote or local `path`.
+fn basename(path: &str) -> &str {
+    path.trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+}
+
+/// Collects the regular files in `dir` recursively, as paths relative to `dir` joined by `/`.
+#[cfg(feature = "verify")]
+fn walk(dir: &Path, prefix: &str, files: &mut Vec<(PathBuf, String)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let rel = join_remote(prefix, &entry.file_name().to_string_lossy());
+        if entry.file_type()?.is_dir() {
+            walk(&entry.path(), &rel, files)?;
+        } else {
+            files.push((entry.path(), rel));
+        }
+    }
+    Ok(())
+}
+
+/// Maps the `local` files/directories of `push` to the files on the device.
+///
+/// Like `cp`, the sources are copied into `remote` if it is a directory (`remote_is_dir`),
+/// otherwise `remote` is the copy of the only source.
+#[cfg(feature = "verify")]
+pub(crate) fn push_transfers(
+    local: &[OsString],
+    remote: &str,
+    remote_is_dir: bool,
+) -> io::Result<Vec<Transfer>> {
+    let mut transfers = Vec::new();
+    for source in local {
+        let source = Path::new(source);
+        let dest = if remote_is_dir {
+            join_remote(remote, basename(&source.to_string_lossy()))
+        } else {
+            remote.to_string()
+        };
+        if source.is_dir() {
+            let mut files = Vec::new();
+            walk(source, "", &mut files)?;
+            transfers.extend(files.into_iter().map(|(local, rel)| Transfer {
+                local,
+                remote: join_remote(&dest, &rel),
+            }));
+        } else {
+            transfers.push(Transfer {
+                local: source.to_path_buf(),
+                remote: dest,
+            });
+        }
+    }
+    Ok(transfers)
+}
+
+/// Maps the files on the device to the `local` destination of `pull`.
+///
+/// `remote` contains every source of `pull` with the regular files under it (as listed by `find`),
+/// like [`push_transfers`], the sources are copied into `local` if it is a directory (`local_is_dir`).
+pub(crate) fn pull_transfers(
+    remote: &[(String, Vec<String>)],
+    local: &Path,
+    local_is_dir: bool,
+) -> Vec<Transfer> {
+    let mut transfers = Vec::new();
+    for (source, files) in remote {
+        let dest = if local_is_dir {
+            local.join(basename(source))
+        } else {
+            local.to_path_buf()
+        };
+        for file in files {
+            let rel = file
+                .strip_prefix(source.trim_end_matches('/'))
+                .unwrap_or(file)
+                .trim_start_matches('/');
+            transfers.push(Transfer {
+                local: rel
+                    .split('/')
+                    .filter(|c| !c.is_empty())
+                    .fold(dest.clone(), |p, c| p.join(c)),
+                remote: file.clone(),
+            });
+        }
+    }
+    transfers
+}
+
+/// Computes the SHA-256 checksum of the local file as a lowercase hex string.
+#[cfg(feature = "verify")]
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Parses lines of `VALUE PATH` (e.g. the output of `sha256sum` or `stat -c '%s %n'`) into a map.
+fn parse_pairs(output: &str) -> HashMap<&str, &str> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .map(|(value, path)| (path.trim_start(), value))
+        .collect()
+}
+
+/// The line printed by [`remote_shell`] after the remote command, to tell it ran on the device.
+const DONE_MARKER: &str = "adbr:done";
+
+/// Executes a remote shell command on the quoted `paths`, returning its stdout.
+///
+/// The exit status of the remote command is ignored, since the output of the other paths
+/// is still valid if some of them are missing. Instead, a [`DONE_MARKER`] line is printed
+/// after the command, and a missing marker means adb itself failed (e.g. no device).
+///
+/// # Errors
+///
+/// Returns [`AdbError::NonZeroExit`] if adb failed,
+/// or [`AdbError::ShellCommandFailed`] if the marker is missing despite a successful exit status.
+fn remote_shell(acb: &AdbCommandBuilder, command: &str, paths: &[&str]) -> AdbResult<String> {
+    let paths = paths.iter().map(|p| shell::quote(p));
+    let command = std::iter::once(command.to_string())
+        .chain(paths)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let output = acb
+        .clone()
+        .shell()
+        .arg(format!("{}; echo {}", command, DONE_MARKER))
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match strip_done_marker(&stdout) {
+        Some(stdout) => Ok(stdout.to_string()),
+        None => {
+            process::check(&output)?;
+            Err(AdbError::ShellCommandFailed(stdout.into_owned()))
+        }
+    }
+}
+
+/// Strips the trailing [`DONE_MARKER`] line from the `stdout` of [`remote_shell`],
+/// or returns [`None`] if it's missing.
+fn strip_done_marker(stdout: &str) -> Option<&str> {
+    let stdout = stdout.trim_end();
+    let rest = stdout.strip_suffix(DONE_MARKER)?;
+    (rest.is_empty() || rest.ends_with('\n')).then_some(rest)
+}
+
+/// Whether the remote `path` is a directory, with `test -d`.
+pub(crate) fn remote_is_dir(acb: &AdbCommandBuilder, path: &str) -> AdbResult<bool> {
+    let command = format!("test -d {} && echo dir", shell::quote(path));
+    Ok(remote_shell(acb, &command, &[])?.trim() == "dir")
+}
+
+/// Lists the regular files under the remote `path` (or `path` itself if it's a file), with `find`.
+pub(crate) fn remote_files(acb: &AdbCommandBuilder, path: &str) -> AdbResult<Vec<String>> {
+    let command = format!("find {} -type f", shell::quote(path));
+    let stdout = remote_shell(acb, &command, &[])?;
+    Ok(stdout
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Gets the modification times of the remote files as seconds since the Unix epoch, with `stat`.
+///
+/// Missing files are absent from the result.
+fn remote_mtimes(acb: &AdbCommandBuilder, paths: &[&str]) -> AdbResult<HashMap<String, i64>> {
+    let stdout = remote_shell(acb, "stat -c '%Y %n'", paths)?;
+    Ok(parse_pairs(&stdout)
+        .into_iter()
+        .filter_map(|(path, mtime)| Some((path.to_string(), mtime.parse().ok()?)))
+        .collect())
+}
+
+/// Gets the modification time of the local file as seconds since the Unix epoch,
+/// or [`None`] if it's missing or has no modification time.
+fn local_mtime(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    match modified.duration_since(UNIX_EPOCH) {
+        Ok(d) => i64::try_from(d.as_secs()).ok(),
+        Err(e) => i64::try_from(e.duration().as_secs()).ok().map(|s| -s),
+    }
+}
+
+/// Whether the pulled file is up-to-date, i.e. the local copy exists and isn't older than the remote one.
+///
+/// A missing modification time on either side means the file must be pulled.
+fn is_unchanged(remote_mtime: Option<i64>, local_mtime: Option<i64>) -> bool {
+    matches!((remote_mtime, local_mtime), (Some(remote), Some(local)) if local >= remote)
+}
+
+/// Filters out the `transfers` of `pull` whose local copy is up-to-date, see [`is_unchanged`].
+pub(crate) fn changed(
+    acb: &AdbCommandBuilder,
+    transfers: Vec<Transfer>,
+) -> AdbResult<Vec<Transfer>> {
+    if transfers.is_empty() {
+        return Ok(transfers);
+    }
+    let paths = transfers
+        .iter()
+        .map(|t| t.remote.as_str())
+        .collect::<Vec<_>>();
+    let mtimes = remote_mtimes(acb, &paths)?;
+    Ok(transfers
+        .into_iter()
+        .filter(|t| !is_unchanged(mtimes.get(&t.remote).copied(), local_mtime(&t.local)))
+        .collect())
+}
+
+/// Verifies that every transferred file has the same content on both sides.
+///
+/// The SHA-256 checksums are compared if the device provides `sha256sum`,
+/// otherwise only the sizes are compared (with `stat`).
+///
+/// # Errors
+///
+/// Returns [`AdbError::VerificationFailed`] with the remote path of the first mismatched file.
+/// Returns [`AdbError::NonZeroExit`] if adb fails to query the device, without comparing anything.
+#[cfg(feature = "verify")]
+pub(crate) fn verify(acb: &AdbCommandBuilder, transfers: &[Transfer]) -> AdbResult<()> {
+    if transfers.is_empty() {
+        return Ok(());
+    }
+    let paths = transfers
+        .iter()
+        .map(|t| t.remote.as_str())
+        .collect::<Vec<_>>();
+    let checksums = remote_shell(acb, "sha256sum", &paths)?;
+    let checksums = parse_pairs(&checksums);
+    if !checksums.is_empty() {
+        for transfer in transfers {
+            let local = sha256_file(&transfer.local)?;
+            if checksums.get(transfer.remote.as_str()) != Some(&local.as_str()) {
+                return Err(AdbError::VerificationFailed(transfer.remote.clone()));
+            }
+        }
+        return Ok(());
+    }
+    // no `sha256sum` on the device
+    let sizes = remote_shell(acb, "stat -c '%s %n'", &paths)?;
+    let sizes = parse_pairs(&sizes);
+    for transfer in transfers {
+        let local = fs::metadata(&transfer.local)?.len();
+        let remote = sizes
+            .get(transfer.remote.as_str())
+            .and_then(|s| s.parse().ok());
+        if remote != Some(local) {
+            return Err(AdbError::VerificationFailed(transfer.remote.clone()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "verify")]
+    fn test_sha256_file() {
+        let path = std::env::temp_dir().join(format!("adbr-sha256-{}", std::process::id()));
+        fs::write(&path, b"abc").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_pairs() {
+        let checksums = parse_pairs(
+            "ba7816bf  /sdcard/a b.txt\n\
+             sha256sum: /sdcard/missing: No such file or directory\n\
+             e3b0c442  /sdcard/empty\n",
+        );
+        assert_eq!(checksums.get("/sdcard/a b.txt"), Some(&"ba7816bf"));
+        assert_eq!(checksums.get("/sdcard/empty"), Some(&"e3b0c442"));
+        assert_eq!(checksums.get("/sdcard/missing"), None);
+    }
+
+    #[test]
+    fn test_strip_done_marker() {
+        assert_eq!(strip_done_marker("adbr:done\n"), Some(""));
+        assert_eq!(strip_done_marker("dir\r\nadbr:done\r\n"), Some("dir\r\n"));
+        assert_eq!(
+            strip_done_marker("/sdcard/a\n/sdcard/b\nadbr:done\n"),
+            Some("/sdcard/a\n/sdcard/b\n")
+        );
+        // adb failed before the command ran
+        assert_eq!(strip_done_marker(""), None);
+        assert_eq!(strip_done_marker("dir\n"), None);
+        assert_eq!(strip_done_marker("xadbr:done\n"), None);
+    }
+
+    #[test]
+    #[cfg(all(unix, feature = "verify"))]
+    fn test_adb_failure() {
+        use crate::command::tests::fake_adb;
+        use crate::Adb;
+
+        let dir = fake_adb(
+            "no-device",
+            "echo 'error: no devices/emulators found' >&2\nexit 1",
+        );
+        let adb = Adb::default();
+        let acb = adb.command().env("PATH", &dir);
+        let transfers = [Transfer {
+            local: dir.join("adb"),
+            remote: "/sdcard/adb".to_string(),
+        }];
+        let is_no_device = |e: &AdbError| matches!(e, AdbError::NonZeroExit { stderr, .. } if stderr.contains("no devices"));
+        assert!(is_no_device(&verify(&acb, &transfers).unwrap_err()));
+        assert!(is_no_device(&remote_is_dir(&acb, "/sdcard").unwrap_err()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_unchanged() {
+        assert!(is_unchanged(Some(100), Some(100)));
+        assert!(is_unchanged(Some(100), Some(200)));
+        assert!(!is_unchanged(Some(200), Some(100)));
+        // missing local copy
+        assert!(!is_unchanged(Some(100), None));
+        // `stat` failed on the device
+        assert!(!is_unchanged(None, Some(100)));
+        assert!(!is_unchanged(None, None));
+
+        let mtimes = parse_pairs("1700000000 /sdcard/a b\nstat: '/sdcard/x': No such file\n");
+        assert_eq!(mtimes.get("/sdcard/a b"), Some(&"1700000000"));
+        assert!(local_mtime(Path::new("/nonexistent/adbr")).is_none());
+        assert!(local_mtime(&std::env::temp_dir()).is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "verify")]
+    fn test_push_transfers() {
+        let dir = std::env::temp_dir().join(format!("adbr-transfers-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub").join("b"), b"b").unwrap();
+        let file = dir.join("a");
+        fs::write(&file, b"a").unwrap();
+
+        let local = [file.clone().into_os_string()];
+        assert_eq!(
+            push_transfers(&local, "/sdcard/", true).unwrap(),
+            [Transfer {
+                local: file.clone(),
+                remote: "/sdcard/a".to_string()
+            }]
+        );
+        assert_eq!(
+            push_transfers(&local, "/sdcard/x", false).unwrap()[0].remote,
+            "/sdcard/x"
+        );
+        let local = [dir.join("sub").into_os_string()];
+        assert_eq!(
+            push_transfers(&local, "/sdcard", true).unwrap(),
+            [Transfer {
+                local: dir.join("sub").join("b"),
+                remote: "/sdcard/sub/b".to_string()
+            }]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pull_transfers() {
+        let remote = [
+            ("/sdcard/a".to_string(), vec!["/sdcard/a".to_string()]),
+            (
+                "/sdcard/dir/".to_string(),
+                vec!["/sdcard/dir/x/y".to_string()],
+            ),
+        ];
+        assert_eq!(
+            pull_transfers(&remote, Path::new("out"), true),
+            [
+                Transfer {
+                    local: Path::new("out").join("a"),
+                    remote: "/sdcard/a".to_string()
+                },
+                Transfer {
+                    local: Path::new("out").join("dir").join("x").join("y"),
+                    remote: "/sdcard/dir/x/y".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            pull_transfers(&remote[..1], Path::new("a.txt"), false)[0].local,
+            Path::new("a.txt")
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Content provider commands, run through the remote shell.
+//!
+//! - `shell content query --uri URI [--projection COLUMN...] [--where WHERE]`: Query a content provider.
+//! - `shell content insert --uri URI --bind BINDING...`: Insert a row into a content provider.
+//! - `shell content delete --uri URI [--where WHERE]`: Delete rows from a content provider.
+//!
+//! See `adb shell content` for more information.
+
+use std::fmt::Display;
+use std::process::Command;
+
+use crate::command::{process, shell, AdbCommandBuilder};
+use crate::{Adb, AdbCommand, AdbResult};
+
+/// The typed value of a `--bind COLUMN:TYPE:VALUE` argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentValue {
+    /// `b`: A boolean value.
+    Boolean(bool),
+    /// `s`: A string value.
+    String(String),
+    /// `i`: An integer value.
+    Integer(i32),
+    /// `l`: A long value.
+    Long(i64),
+    /// `f`: A float value.
+    Float(f32),
+    /// `d`: A double value.
+    Double(f64),
+    /// `n`: A null value.
+    Null,
+}
+
+impl Display for ContentValue {
+    /// Formats the value as `TYPE:VALUE`, e.g. `i:1` or `s:hello`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContentValue::Boolean(b) => write!(f, "b:{}", b),
+            ContentValue::String(s) => write!(f, "s:{}", s),
+            ContentValue::Integer(i) => write!(f, "i:{}", i),
+            ContentValue::Long(l) => write!(f, "l:{}", l),
+            ContentValue::Float(x) => write!(f, "f:{}", x),
+            ContentValue::Double(x) => write!(f, "d:{}", x),
+            ContentValue::Null => write!(f, "n:"),
+        }
+    }
+}
+
+/// Quotes `s` for the remote shell, unless it's a plain word (e.g. a content URI).
+fn quote_word(s: &str) -> String {
+    let plain = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    if plain {
+        s.to_string()
+    } else {
+        shell::quote(s)
+    }
+}
+
+/// `shell content`: Access content providers.
+/// - `query --uri URI`: Query a content provider.
+/// - `insert --uri URI`: Insert a row into a content provider.
+/// - `delete --uri URI`: Delete rows from a content provider.
+#[derive(Debug, Clone)]
+pub struct AdbContent<'a>(AdbCommandBuilder<'a>);
+
+impl<'a> AdbContent<'a> {
+    /// `query --uri URI`: Query a content provider.
+    pub fn query<S: AsRef<str>>(self, uri: S) -> AdbContentCommand<'a> {
+        AdbContentCommand::new(self.0, "query", uri.as_ref())
+    }
+
+    /// `insert --uri URI`: Insert a row into a content provider.
+    pub fn insert<S: AsRef<str>>(self, uri: S) -> AdbContentCommand<'a> {
+        AdbContentCommand::new(self.0, "insert", uri.as_ref())
+    }
+
+    /// `delete --uri URI`: Delete rows from a content provider.
+    pub fn delete<S: AsRef<str>>(self, uri: S) -> AdbContentCommand<'a> {
+        AdbContentCommand::new(self.0, "delete", uri.as_ref())
+    }
+}
+
+impl Adb {
+    /// `shell content`: Access content providers.
+    /// - `query --uri URI`: Query a content provider.
+    /// - `insert --uri URI`: Insert a row into a content provider.
+    /// - `delete --uri URI`: Delete rows from a content provider.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell content query --uri content://settings/system --projection name:value --where "name='volume_ring'"`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.content()
+    ///     .query("content://settings/system")
+    ///     .projection(["name", "value"])
+    ///     .where_clause("name='volume_ring'")
+    ///     .status()
+    ///     .expect("`adb shell content query` failed");
+    /// ```
+    ///
+    /// `adb shell content insert --uri content://settings/secure --bind name:s:new_setting --bind value:s:new_value`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # use adbr::command::content::ContentValue;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.content()
+    ///     .insert("content://settings/secure")
+    ///     .bind("name", ContentValue::String("new_setting".to_string()))
+    ///     .bind("value", ContentValue::String("new_value".to_string()))
+    ///     .status()
+    ///     .expect("`adb shell content insert` failed");
+    /// ```
+    pub fn content(&self) -> AdbContent<'_> {
+        AdbContent(self.command())
+    }
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `shell content`: Access content providers.
+    ///
+    /// See [`Adb::content`] for more information.
+    pub fn content(self) -> AdbContent<'a> {
+        AdbContent(self)
+    }
+}
+
+/// A subcommand of `shell content`, e.g. `query --uri URI`.
+///
+/// The arguments are emitted in the order they're added,
+/// and quoted for the remote shell if needed.
+#[derive(Debug, Clone)]
+pub struct AdbContentCommand<'a> {
+    acb: AdbCommandBuilder<'a>,
+    /// `query`, `insert` or `delete`.
+    operation: &'static str,
+    /// `URI`: The content URI.
+    uri: String,
+    /// The arguments after `--uri URI`.
+    args: Vec<String>,
+}
+
+impl<'a> AdbContentCommand<'a> {
+    fn new(acb: AdbCommandBuilder<'a>, operation: &'static str, uri: &str) -> Self {
+        Self {
+            acb,
+            operation,
+            uri: uri.to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    /// `--where WHERE`: Filter the rows with an SQL `WHERE` clause, e.g. `name='volume_ring'`.
+    pub fn where_clause<S: AsRef<str>>(mut self, clause: S) -> Self {
+        self.args.push("--where".to_string());
+        self.args.push(quote_word(clause.as_ref()));
+        self
+    }
+
+    /// `--projection COLUMN:COLUMN...`: The columns to return from `query`.
+    pub fn projection<S, I>(mut self, columns: I) -> Self
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = S>,
+    {
+        let columns = columns
+            .into_iter()
+            .map(|column| column.as_ref().to_string())
+            .collect::<Vec<_>>()
+            .join(":");
+        self.args.push("--projection".to_string());
+        self.args.push(quote_word(&columns));
+        self
+    }
+
+    /// `--bind COLUMN:TYPE:VALUE`: Bind a typed value to a column, e.g. for `insert`.
+    pub fn bind<S: AsRef<str>>(mut self, column: S, value: ContentValue) -> Self {
+        self.args.push("--bind".to_string());
+        self.args
+            .push(quote_word(&format!("{}:{}", column.as_ref(), value)));
+        self
+    }
+}
+
+impl<'a> AdbCommand for AdbContentCommand<'a> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("shell")
+            .arg("content")
+            .arg(self.operation)
+            .arg("--uri")
+            .arg(quote_word(&self.uri))
+            .args(self.args);
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.uri])?;
+        process::check_nul(&self.args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_build() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.content().query("content://settings/system")),
+            [
+                "shell",
+                "content",
+                "query",
+                "--uri",
+                "content://settings/system"
+            ]
+        );
+        assert_eq!(
+            args(
+                adb.content()
+                    .query("content://settings/system")
+                    .projection(["name", "value"])
+                    .where_clause("name='volume_ring'")
+            ),
+            [
+                "shell",
+                "content",
+                "query",
+                "--uri",
+                "content://settings/system",
+                "--projection",
+                "name:value",
+                "--where",
+                r"'name='\''volume_ring'\'''"
+            ]
+        );
+        assert_eq!(
+            args(
+                adb.content()
+                    .insert("content://settings/secure")
+                    .bind("name", ContentValue::String("a b".to_string()))
+                    .bind("value", ContentValue::Integer(1))
+                    .bind("extra", ContentValue::Null)
+            ),
+            [
+                "shell",
+                "content",
+                "insert",
+                "--uri",
+                "content://settings/secure",
+                "--bind",
+                "'name:s:a b'",
+                "--bind",
+                "value:i:1",
+                "--bind",
+                "extra:n:"
+            ]
+        );
+        assert_eq!(
+            args(adb.content().delete("content://sms").where_clause("_id=1")),
+            [
+                "shell",
+                "content",
+                "delete",
+                "--uri",
+                "content://sms",
+                "--where",
+                "_id=1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_content_value() {
+        assert_eq!(ContentValue::Boolean(true).to_string(), "b:true");
+        assert_eq!(ContentValue::Long(-1).to_string(), "l:-1");
+        assert_eq!(ContentValue::Float(1.5).to_string(), "f:1.5");
+        assert_eq!(ContentValue::Double(0.25).to_string(), "d:0.25");
+        assert_eq!(ContentValue::Null.to_string(), "n:");
+    }
+}
@@ -0,0 +1,116 @@
+//! Custom commands.
+//!
+//! - `SUBCOMMAND [ARG...]`: Run an arbitrary adb subcommand, e.g. one not supported by adbr yet.
+
+use std::ffi::{OsStr, OsString};
+use std::process::Command;
+
+use crate::command::{process, AdbCommandBuilder};
+use crate::{Adb, AdbCommand, AdbResult};
+
+/// `SUBCOMMAND [ARG...]`: Run an arbitrary adb subcommand.
+#[derive(Debug, Clone)]
+pub struct AdbCustom<'a> {
+    acb: AdbCommandBuilder<'a>,
+    /// `SUBCOMMAND`: The subcommand to run.
+    subcommand: OsString,
+    /// `ARG...`: The arguments of the subcommand.
+    args: Vec<OsString>,
+}
+
+impl<'a> AdbCustom<'a> {
+    /// Creates a new `AdbCustom` command without arguments.
+    fn new(acb: AdbCommandBuilder<'a>, subcommand: OsString) -> Self {
+        Self {
+            acb,
+            subcommand,
+            args: Vec::new(),
+        }
+    }
+
+    /// `ARG...`: The arguments of the subcommand.
+    pub fn arg<S: AsRef<OsStr>>(mut self, arg: S) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// `ARG...`: The arguments of the subcommand.
+    pub fn args<S, I>(mut self, args: I) -> Self
+    where
+        S: AsRef<OsStr>,
+        I: IntoIterator<Item = S>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+}
+
+impl<'a> AdbCommand for AdbCustom<'a> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg(self.subcommand).args(self.args);
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.subcommand])?;
+        process::check_nul(&self.args)
+    }
+}
+
+impl Adb {
+    /// `SUBCOMMAND [ARG...]`: Run an arbitrary adb subcommand.
+    ///
+    /// The subcommand and its arguments are emitted after the global options as is,
+    /// which is useful for subcommands not supported by adbr yet.
+    ///
+    /// # Examples
+    ///
+    /// `adb track-jdwp`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.custom("track-jdwp")
+    ///     .status()
+    ///     .expect("`adb track-jdwp` failed");
+    /// ```
+    pub fn custom<S: AsRef<OsStr>>(&self, subcommand: S) -> AdbCustom<'_> {
+        AdbCustom::new(self.command(), subcommand.as_ref().to_os_string())
+    }
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `SUBCOMMAND [ARG...]`: Run an arbitrary adb subcommand.
+    ///
+    /// See [`Adb::custom`] for more information.
+    pub fn custom<S: AsRef<OsStr>>(self, subcommand: S) -> AdbCustom<'a> {
+        AdbCustom::new(self, subcommand.as_ref().to_os_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_custom() {
+        let adb = Adb::default();
+        assert_eq!(args(adb.custom("track-jdwp")), ["track-jdwp"]);
+        assert_eq!(
+            args(
+                adb.s_serial("emulator-5554")
+                    .custom("exec-out")
+                    .arg("ls")
+                    .args(["-l", "/"])
+            ),
+            ["-s", "emulator-5554", "exec-out", "ls", "-l", "/"]
+        );
+    }
+}
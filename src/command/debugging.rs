@@ -1,18 +1,21 @@
 //! Debugging commands.
 //!
 //! - `bugreport [PATH]`: Write bugreport to given PATH (default=`bugreport.zip`);
-//!     if `PATH` is a directory, the bug report is saved in that directory.
-//!     devices that don't support zipped bug reports output to stdout.
+//!   if `PATH` is a directory, the bug report is saved in that directory.
+//!   devices that don't support zipped bug reports output to stdout.
 //! - `jdwp`: List pids of processes hosting a JDWP transport.
-//! - `logcat`: Show device log.
+//! - `logcat [-v FORMAT]... [-e REGEX] [-m N]`: Show device log.
 //!
 //! See [Debugging Commands](https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/master/docs/user/adb.1.md#debugging).
 
 use std::ffi::{OsStr, OsString};
-use std::process::Command;
+use std::fmt::Display;
+use std::process::{Command, ExitStatus};
+use std::str::FromStr;
 
-use crate::command::AdbCommandBuilder;
-use crate::{Adb, AdbCommand};
+use crate::command::{process, AdbCommandBuilder};
+use crate::error::ParseError;
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
 
 /// `bugreport [PATH]`: Write bugreport to given PATH (default=`bugreport.zip`).
 ///
@@ -51,6 +54,14 @@ impl<'a, S: AsRef<OsStr>> AdbCommand for AdbBugReport<'a, S> {
         }
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(&self.path)
+    }
 }
 
 impl Adb {
@@ -71,7 +82,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb bugreport /path/to/bugreport.zip` failed");
     /// ```
-    pub fn bug_report(&self) -> AdbBugReport<OsString> {
+    pub fn bug_report(&self) -> AdbBugReport<'_, OsString> {
         AdbBugReport::new(self.command())
     }
 }
@@ -95,6 +106,10 @@ impl<'a> AdbCommand for AdbJdwp<'a> {
         cmd.arg("jdwp");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -109,7 +124,7 @@ impl Adb {
     /// # let adb = Adb::new().unwrap();
     /// adb.jdwp().status().expect("`adb jdwp` failed");
     /// ```
-    pub fn jdwp(&self) -> AdbJdwp {
+    pub fn jdwp(&self) -> AdbJdwp<'_> {
         AdbJdwp(self.command())
     }
 }
@@ -123,40 +138,400 @@ impl<'a> AdbCommandBuilder<'a> {
     }
 }
 
-/// `logcat`: Show device log.
+/// The base format of `logcat -v`, see `logcat --help`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogcatFormat {
+    /// `brief`: Show priority, tag, and PID of the process issuing the message.
+    Brief,
+    /// `process`: Show PID only.
+    Process,
+    /// `tag`: Show the priority and tag only.
+    Tag,
+    /// `thread`: Show priority, PID, and TID of the thread issuing the message.
+    Thread,
+    /// `raw`: Show the raw log message with no other metadata fields.
+    Raw,
+    /// `time`: Show the date, invocation time, priority, tag, and PID of the process issuing the message.
+    Time,
+    /// `threadtime`: Show the date, invocation time, priority, tag, PID, and TID of the thread issuing the message.
+    ThreadTime,
+    /// `long`: Show all metadata fields and separate messages with blank lines.
+    Long,
+}
+
+impl AsRef<str> for LogcatFormat {
+    fn as_ref(&self) -> &str {
+        match self {
+            LogcatFormat::Brief => "brief",
+            LogcatFormat::Process => "process",
+            LogcatFormat::Tag => "tag",
+            LogcatFormat::Thread => "thread",
+            LogcatFormat::Raw => "raw",
+            LogcatFormat::Time => "time",
+            LogcatFormat::ThreadTime => "threadtime",
+            LogcatFormat::Long => "long",
+        }
+    }
+}
+
+impl Display for LogcatFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+impl FromStr for LogcatFormat {
+    type Err = AdbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "brief" => Ok(LogcatFormat::Brief),
+            "process" => Ok(LogcatFormat::Process),
+            "tag" => Ok(LogcatFormat::Tag),
+            "thread" => Ok(LogcatFormat::Thread),
+            "raw" => Ok(LogcatFormat::Raw),
+            "time" => Ok(LogcatFormat::Time),
+            "threadtime" => Ok(LogcatFormat::ThreadTime),
+            "long" => Ok(LogcatFormat::Long),
+            _ => {
+                Err(ParseError::with_description(s, "LogcatFormat", "Unknown logcat format").into())
+            }
+        }
+    }
+}
+
+/// A modifier of `logcat -v`, which stacks with the base [`LogcatFormat`], see `logcat --help`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogcatFormatModifier {
+    /// `color`: Show each priority with a different color.
+    Color,
+    /// `usec`: Show time down to microseconds.
+    Usec,
+    /// `UTC`: Show time as UTC.
+    Utc,
+    /// `epoch`: Show time as seconds since 1970-01-01 (Unix epoch).
+    Epoch,
+    /// `printable`: Ensure that any binary logging content is escaped.
+    Printable,
+}
+
+impl AsRef<str> for LogcatFormatModifier {
+    fn as_ref(&self) -> &str {
+        match self {
+            LogcatFormatModifier::Color => "color",
+            LogcatFormatModifier::Usec => "usec",
+            LogcatFormatModifier::Utc => "UTC",
+            LogcatFormatModifier::Epoch => "epoch",
+            LogcatFormatModifier::Printable => "printable",
+        }
+    }
+}
+
+impl Display for LogcatFormatModifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+impl FromStr for LogcatFormatModifier {
+    type Err = AdbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "color" => Ok(LogcatFormatModifier::Color),
+            "usec" => Ok(LogcatFormatModifier::Usec),
+            "UTC" => Ok(LogcatFormatModifier::Utc),
+            "epoch" => Ok(LogcatFormatModifier::Epoch),
+            "printable" => Ok(LogcatFormatModifier::Printable),
+            _ => Err(ParseError::with_description(
+                s,
+                "LogcatFormatModifier",
+                "Unknown logcat format modifier",
+            )
+            .into()),
+        }
+    }
+}
+
+/// `logcat [-v FORMAT]... [-e REGEX] [-m N]`: Show device log.
+/// - `-v FORMAT`: Sets the output format, and its modifiers.
+/// - `-e REGEX`: Only prints lines where the log message matches `REGEX`.
+/// - `-m N`: Quits after printing `N` lines.
 #[derive(Debug, Clone)]
-pub struct AdbLogcat<'a>(AdbCommandBuilder<'a>);
+pub struct AdbLogcat<'a> {
+    acb: AdbCommandBuilder<'a>,
+    /// `-v FORMAT`: The base output format.
+    format: Option<LogcatFormat>,
+    /// `-v MODIFIER`: The format modifiers, each emitted as a separate `-v`.
+    modifiers: Vec<LogcatFormatModifier>,
+    /// `-e REGEX`: The regular expression the log message must match.
+    regex: Option<String>,
+    /// `-m N`: The maximum number of lines to print.
+    max_count: Option<u32>,
+}
+
+impl<'a> AdbLogcat<'a> {
+    /// Creates a new `AdbLogcat` command with the default format.
+    fn new(acb: AdbCommandBuilder<'a>) -> Self {
+        Self {
+            acb,
+            format: None,
+            modifiers: Vec::new(),
+            regex: None,
+            max_count: None,
+        }
+    }
+
+    /// `-v FORMAT`: Sets the base output format.
+    ///
+    /// The previous format will be overwritten, the modifiers are kept.
+    pub fn format(mut self, format: LogcatFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// `-v MODIFIER`: Adds a format modifier, which stacks with the base format and other modifiers.
+    ///
+    /// Adding an existing modifier has no effect.
+    pub fn format_modifier(mut self, modifier: LogcatFormatModifier) -> Self {
+        if !self.modifiers.contains(&modifier) {
+            self.modifiers.push(modifier);
+        }
+        self
+    }
+
+    /// `-e REGEX`: Only prints lines where the log message matches `REGEX`,
+    /// which is evaluated by `logcat` on the device.
+    ///
+    /// The previous regex will be overwritten.
+    pub fn regex<S: Into<String>>(mut self, regex: S) -> Self {
+        self.regex = Some(regex.into());
+        self
+    }
+
+    /// `-m N`: Quits after printing `N` lines, combined with [`Self::regex`] it counts matches only.
+    pub fn max_count(mut self, n: u32) -> Self {
+        self.max_count = Some(n);
+        self
+    }
+
+    /// Executes the command like [`Self::for_each_line`],
+    /// calling `f` only on the lines matching `regex`.
+    ///
+    /// Unlike [`Self::regex`], the regex is evaluated on the host against the whole line,
+    /// including the fields added by the output format (e.g. the tag or the pid).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// use regex::Regex;
+    ///
+    /// let regex = Regex::new(r"^[EW]/ActivityManager").unwrap();
+    /// adb.logcat()
+    ///     .matching(regex, |line| println!("{}", line))
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn matching<F: FnMut(&str)>(self, regex: regex::Regex, f: F) -> AdbResult<ExitStatus> {
+        self.matching_by(move |line| regex.is_match(line), f)
+    }
+
+    /// Executes the command like [`Self::for_each_line`],
+    /// calling `f` only on the lines accepted by `predicate`.
+    ///
+    /// Like `matching` (with the `regex` feature), the predicate is evaluated on the host
+    /// against the whole line.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.logcat()
+    ///     .matching_by(|line| line.contains("ActivityManager"), |line| println!("{}", line))
+    ///     .unwrap();
+    /// ```
+    pub fn matching_by<P, F>(self, predicate: P, f: F) -> AdbResult<ExitStatus>
+    where
+        P: FnMut(&str) -> bool,
+        F: FnMut(&str),
+    {
+        self.for_each_line(filter_lines(predicate, f))
+    }
+}
+
+/// Wraps `f` to be called only on the lines accepted by `predicate`.
+fn filter_lines<P, F>(mut predicate: P, mut f: F) -> impl FnMut(&str)
+where
+    P: FnMut(&str) -> bool,
+    F: FnMut(&str),
+{
+    move |line| {
+        if predicate(line) {
+            f(line)
+        }
+    }
+}
 
 impl<'a> AdbCommand for AdbLogcat<'a> {
     fn build(self) -> Command {
-        let mut cmd = self.0.build();
+        let mut cmd = self.acb.build();
         cmd.arg("logcat");
+        if let Some(format) = self.format {
+            cmd.arg("-v").arg(format.as_ref());
+        }
+        for modifier in self.modifiers {
+            cmd.arg("-v").arg(modifier.as_ref());
+        }
+        if let Some(regex) = self.regex {
+            cmd.arg("-e").arg(regex);
+        }
+        if let Some(n) = self.max_count {
+            cmd.arg("-m").arg(n.to_string());
+        }
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(&self.regex)
+    }
 }
 
 impl Adb {
-    /// `logcat`: Show device log.
+    /// `logcat [-v FORMAT]... [-e REGEX] [-m N]`: Show device log.
+    /// - `-v FORMAT`: Sets the output format, and its modifiers.
+    /// - `-e REGEX`: Only prints lines where the log message matches `REGEX`.
+    /// - `-m N`: Quits after printing `N` lines.
     ///
     /// # Examples
     ///
-    /// `adb logcat`
+    /// `adb logcat -v threadtime -v color`
     ///
     /// ```no_run
     /// # use adbr::{Adb, AdbCommand};
+    /// use adbr::command::debugging::{LogcatFormat, LogcatFormatModifier};
     /// # let adb = Adb::new().unwrap();
-    /// adb.logcat().status().expect("`adb logcat` failed");
+    /// adb.logcat()
+    ///     .format(LogcatFormat::ThreadTime)   // optional
+    ///     .format_modifier(LogcatFormatModifier::Color)  // optional
+    ///     .status()
+    ///     .expect("`adb logcat -v threadtime -v color` failed");
     /// ```
-    pub fn logcat(&self) -> AdbLogcat {
-        AdbLogcat(self.command())
+    pub fn logcat(&self) -> AdbLogcat<'_> {
+        AdbLogcat::new(self.command())
     }
 }
 
 impl<'a> AdbCommandBuilder<'a> {
-    /// `logcat`: Show device log.
+    /// `logcat [-v FORMAT]... [-e REGEX] [-m N]`: Show device log.
     ///
     /// See [`Adb::logcat`] for more information.
     pub fn logcat(self) -> AdbLogcat<'a> {
-        AdbLogcat(self)
+        AdbLogcat::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_logcat_format() {
+        let formats = [
+            LogcatFormat::Brief,
+            LogcatFormat::Process,
+            LogcatFormat::Tag,
+            LogcatFormat::Thread,
+            LogcatFormat::Raw,
+            LogcatFormat::Time,
+            LogcatFormat::ThreadTime,
+            LogcatFormat::Long,
+        ];
+        for format in formats {
+            assert_eq!(format.to_string().parse::<LogcatFormat>().unwrap(), format);
+        }
+        let modifiers = [
+            LogcatFormatModifier::Color,
+            LogcatFormatModifier::Usec,
+            LogcatFormatModifier::Utc,
+            LogcatFormatModifier::Epoch,
+            LogcatFormatModifier::Printable,
+        ];
+        for modifier in modifiers {
+            assert_eq!(
+                modifier
+                    .to_string()
+                    .parse::<LogcatFormatModifier>()
+                    .unwrap(),
+                modifier
+            );
+        }
+        assert_eq!(LogcatFormatModifier::Utc.to_string(), "UTC");
+        assert!("utc".parse::<LogcatFormatModifier>().is_err());
+        assert!("color".parse::<LogcatFormat>().is_err());
+    }
+
+    #[test]
+    fn test_logcat_args() {
+        let adb = Adb::default();
+        assert_eq!(args(adb.logcat()), ["logcat"]);
+        assert_eq!(
+            args(
+                adb.logcat()
+                    .format_modifier(LogcatFormatModifier::Color)
+                    .format(LogcatFormat::Brief)
+                    .format(LogcatFormat::ThreadTime)
+                    .format_modifier(LogcatFormatModifier::Usec)
+                    .format_modifier(LogcatFormatModifier::Color)
+            ),
+            ["logcat", "-v", "threadtime", "-v", "color", "-v", "usec"]
+        );
+        assert_eq!(
+            args(
+                adb.logcat()
+                    .max_count(10)
+                    .regex("foo")
+                    .regex("Start proc \\d+")
+                    .format(LogcatFormat::Brief)
+            ),
+            ["logcat", "-v", "brief", "-e", "Start proc \\d+", "-m", "10"]
+        );
+    }
+
+    #[test]
+    fn test_filter_lines() {
+        let lines = [
+            "I/ActivityManager( 1234): Start proc 5678:com.example.app",
+            "D/dalvikvm( 5678): GC_CONCURRENT freed 1024K",
+            "W/ActivityManager( 1234): Timeout executing service",
+        ];
+        let mut matched = Vec::new();
+        let mut f = filter_lines(
+            |line| line.contains("ActivityManager"),
+            |line| matched.push(line.to_string()),
+        );
+        lines.into_iter().for_each(&mut f);
+        drop(f);
+        assert_eq!(matched, [lines[0], lines[2]]);
+
+        #[cfg(feature = "regex")]
+        {
+            let regex = regex::Regex::new(r"^[EW]/ActivityManager|freed \d+K").unwrap();
+            let mut matched = Vec::new();
+            let mut f = filter_lines(
+                |line| regex.is_match(line),
+                |line| matched.push(line.to_string()),
+            );
+            lines.into_iter().for_each(&mut f);
+            drop(f);
+            assert_eq!(matched, [lines[1], lines[2]]);
+        }
     }
 }
@@ -0,0 +1,295 @@
+//! Execution strategies of adb commands.
+//!
+//! A command is first prepared with [`AdbCommand::prepare`] into a [`PreparedCommand`],
+//! i.e. the program, arguments, environment variables and working directory,
+//! which is then consumed by an [`Executor`]:
+//!
+//! - [`Blocking`]: Executes the command with [`std::process`], blocking the current thread,
+//!   like [`AdbCommand::output`] and [`AdbCommand::status`].
+//! - `Tokio` (feature `tokio`): Executes the command with `tokio::process`, without blocking the runtime.
+//!
+//! The typed command builders are shared by all executors,
+//! see [`AdbCommand::output_with`] and [`AdbCommand::status_with`].
+//!
+//! [`AdbCommand::prepare`]: crate::AdbCommand::prepare
+//! [`AdbCommand::output`]: crate::AdbCommand::output
+//! [`AdbCommand::status`]: crate::AdbCommand::status
+//! [`AdbCommand::output_with`]: crate::AdbCommand::output_with
+//! [`AdbCommand::status_with`]: crate::AdbCommand::status_with
+
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output};
+use std::time::Duration;
+
+use crate::command::process;
+use crate::AdbResult;
+
+/// A command ready to be executed:
+/// the program, arguments, environment variables and working directory.
+///
+/// Created by [`AdbCommand::prepare`](crate::AdbCommand::prepare), and consumed by an [`Executor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedCommand {
+    /// The program to execute.
+    program: OsString,
+    /// The arguments passed to the program.
+    args: Vec<OsString>,
+    /// The explicitly set (`Some`) or removed (`None`) environment variables.
+    envs: Vec<(OsString, Option<OsString>)>,
+    /// The working directory, inherited from the parent if `None`.
+    current_dir: Option<PathBuf>,
+    /// The niceness increment of the process.
+    #[cfg(unix)]
+    nice: Option<i32>,
+    /// The time after which the process is killed, if the executor supports it.
+    timeout: Option<Duration>,
+}
+
+impl PreparedCommand {
+    /// Captures the program, arguments, environment variables and working directory of `cmd`.
+    ///
+    /// Anything else, e.g. the stdio or `env_clear`, can't be read back from a [`Command`] and is dropped.
+    pub(crate) fn from_command(cmd: &Command) -> Self {
+        Self {
+            program: cmd.get_program().to_os_string(),
+            args: cmd.get_args().map(OsStr::to_os_string).collect(),
+            envs: cmd
+                .get_envs()
+                .map(|(key, value)| (key.to_os_string(), value.map(OsStr::to_os_string)))
+                .collect(),
+            current_dir: cmd.get_current_dir().map(Path::to_path_buf),
+            #[cfg(unix)]
+            nice: None,
+            timeout: None,
+        }
+    }
+
+    /// Sets the niceness increment, which can't be read back from a [`Command`].
+    #[cfg(unix)]
+    pub(crate) fn with_nice(mut self, nice: Option<i32>) -> Self {
+        self.nice = nice;
+        self
+    }
+
+    /// Sets the time after which the process is killed, e.g. the [default timeout](crate::Adb::default_timeout).
+    pub(crate) fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the time after which the process is killed by [`Blocking`],
+    /// e.g. the [default timeout](crate::Adb::default_timeout).
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns the program to execute, i.e. `adb`.
+    pub fn program(&self) -> &OsStr {
+        &self.program
+    }
+
+    /// Returns the arguments passed to the program.
+    pub fn args(&self) -> &[OsString] {
+        &self.args
+    }
+
+    /// Returns the environment variables explicitly set (`Some`) or removed (`None`).
+    pub fn envs(&self) -> &[(OsString, Option<OsString>)] {
+        &self.envs
+    }
+
+    /// Returns the working directory, or `None` if it's inherited from the parent.
+    pub fn current_dir(&self) -> Option<&Path> {
+        self.current_dir.as_deref()
+    }
+
+    /// Converts into a [`Command`] with the default stdio of [`std::process`].
+    ///
+    /// The [timeout](Self::timeout) can't be carried by a [`Command`] and is dropped.
+    pub fn into_command(self) -> Command {
+        let mut cmd = Command::new(self.program);
+        cmd.args(self.args);
+        for (key, value) in self.envs {
+            match value {
+                Some(value) => cmd.env(key, value),
+                None => cmd.env_remove(key),
+            };
+        }
+        if let Some(dir) = self.current_dir {
+            cmd.current_dir(dir);
+        }
+        #[cfg(unix)]
+        if let Some(increment) = self.nice {
+            process::set_nice(&mut cmd, increment);
+        }
+        cmd
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::Blocking {}
+
+    #[cfg(feature = "tokio")]
+    impl Sealed for super::Tokio {}
+}
+
+/// A strategy to execute a [`PreparedCommand`].
+///
+/// This trait is sealed, see the [module documentation](self) for the available executors.
+pub trait Executor: sealed::Sealed {
+    /// The result of [`Self::output`], e.g. a future for async executors.
+    type Output;
+
+    /// The result of [`Self::status`], e.g. a future for async executors.
+    type Status;
+
+    /// Executes the command, waiting for it to finish and collecting all of its output.
+    ///
+    /// Stdin is set to null, stdout and stderr are captured, like [`Command::output`].
+    fn output(&self, cmd: PreparedCommand) -> Self::Output;
+
+    /// Executes the command, waiting for it to finish and collecting its status.
+    ///
+    /// Stdin, stdout and stderr are inherited from the parent, like [`Command::status`].
+    fn status(&self, cmd: PreparedCommand) -> Self::Status;
+}
+
+/// Executes commands with [`std::process`], blocking the current thread.
+///
+/// The child process is killed after the [timeout](PreparedCommand::timeout), if any,
+/// and the execution is traced with the `tracing` feature.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Blocking;
+
+impl Executor for Blocking {
+    type Output = AdbResult<Output>;
+    type Status = AdbResult<ExitStatus>;
+
+    fn output(&self, cmd: PreparedCommand) -> Self::Output {
+        let timeout = cmd.timeout;
+        process::traced(
+            cmd.into_command(),
+            |output| Some(output.status),
+            |mut cmd| match timeout {
+                Some(timeout) => process::output_timeout(cmd, timeout),
+                None => cmd.output().map_err(Into::into),
+            },
+        )
+    }
+
+    fn status(&self, cmd: PreparedCommand) -> Self::Status {
+        let timeout = cmd.timeout;
+        process::traced(
+            cmd.into_command(),
+            |status| Some(*status),
+            |mut cmd| match timeout {
+                Some(timeout) => process::status_timeout(cmd, timeout),
+                None => cmd.status().map_err(Into::into),
+            },
+        )
+    }
+}
+
+/// A boxed future returned by [`Tokio`].
+#[cfg(feature = "tokio")]
+pub type BoxFuture<T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send>>;
+
+/// Executes commands with `tokio::process`, without blocking the async runtime.
+///
+/// The [timeout](PreparedCommand::timeout) doesn't apply, drop the returned future instead
+/// (e.g. with `tokio::time::timeout`), which kills the child process.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tokio;
+
+#[cfg(feature = "tokio")]
+impl Executor for Tokio {
+    type Output = BoxFuture<AdbResult<Output>>;
+    type Status = BoxFuture<AdbResult<ExitStatus>>;
+
+    fn output(&self, cmd: PreparedCommand) -> Self::Output {
+        let mut cmd = tokio::process::Command::from(cmd.into_command());
+        cmd.kill_on_drop(true);
+        Box::pin(async move { Ok(cmd.output().await?) })
+    }
+
+    fn status(&self, cmd: PreparedCommand) -> Self::Status {
+        let mut cmd = tokio::process::Command::from(cmd.into_command());
+        cmd.kill_on_drop(true);
+        Box::pin(async move { Ok(cmd.status().await?) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepared_command() {
+        let mut cmd = Command::new("adb");
+        cmd.arg("devices")
+            .env("ANDROID_SERIAL", "emulator-5554")
+            .env_remove("ADB_TRACE")
+            .current_dir("/tmp");
+        let prepared = PreparedCommand::from_command(&cmd);
+        assert_eq!(prepared.program(), "adb");
+        assert_eq!(prepared.args(), ["devices"]);
+        assert_eq!(
+            prepared.envs(),
+            [
+                ("ADB_TRACE".into(), None),
+                ("ANDROID_SERIAL".into(), Some("emulator-5554".into()))
+            ]
+        );
+        assert_eq!(prepared.current_dir(), Some(Path::new("/tmp")));
+        assert_eq!(
+            PreparedCommand::from_command(&prepared.clone().into_command()),
+            prepared
+        );
+    }
+
+    #[cfg(unix)]
+    fn echo() -> PreparedCommand {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg("echo \"$ADBR_TEST\"")
+            .env("ADBR_TEST", "ok");
+        PreparedCommand::from_command(&cmd)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_blocking() {
+        let output = Blocking.output(echo()).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"ok\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_blocking_timeout() {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("sleep 10");
+        let prepared =
+            PreparedCommand::from_command(&cmd).with_timeout(Some(Duration::from_millis(100)));
+        assert!(matches!(
+            Blocking.output(prepared.clone()),
+            Err(crate::AdbError::Timeout(_))
+        ));
+        assert!(matches!(
+            Blocking.status(prepared),
+            Err(crate::AdbError::Timeout(_))
+        ));
+    }
+
+    #[cfg(all(unix, feature = "tokio"))]
+    #[tokio::test]
+    async fn test_tokio() {
+        let output = Tokio.output(echo()).await.unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"ok\n");
+    }
+}
@@ -5,10 +5,57 @@
 //!
 //! See [Feature Commands](https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/master/docs/user/adb.1.md#features).
 
+use std::collections::HashSet;
+use std::fmt::Display;
 use std::process::Command;
 
-use crate::command::AdbCommandBuilder;
-use crate::{Adb, AdbCommand};
+use crate::command::{process, AdbCommandBuilder};
+use crate::{Adb, AdbCommand, AdbResult};
+
+/// A feature reported by `host-features` or `features`, e.g. `shell_v2` or `libusb`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Feature(pub String);
+
+impl Feature {
+    /// Parses the output of `host-features` or `features`.
+    ///
+    /// Features are separated by newlines or commas, empty entries are ignored.
+    pub fn parse_list(s: &str) -> HashSet<Feature> {
+        s.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|f| !f.is_empty())
+            .map(Feature::from)
+            .collect()
+    }
+
+    /// Executes the command and parses its stdout as a feature list.
+    pub(crate) fn from_command<C: AdbCommand>(command: C) -> AdbResult<HashSet<Feature>> {
+        let stdout = process::stdout_string(command.output()?)?;
+        Ok(Feature::parse_list(&stdout))
+    }
+}
+
+impl From<&str> for Feature {
+    fn from(s: &str) -> Self {
+        Feature(s.to_string())
+    }
+}
+
+impl AsRef<str> for Feature {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Returns the features supported by adb server (`host`) but not by the device (`device`).
+fn missing_features(host: HashSet<Feature>, device: &HashSet<Feature>) -> HashSet<Feature> {
+    host.into_iter().filter(|f| !device.contains(f)).collect()
+}
 
 /// `host-features`: List features supported by adb server.
 #[derive(Debug, Clone)]
@@ -20,6 +67,10 @@ impl<'a> AdbCommand for AdbHostFeatures<'a> {
         cmd.arg("host-features");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -36,7 +87,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb host-features` failed");
     /// ```
-    pub fn host_features(&self) -> AdbHostFeatures {
+    pub fn host_features(&self) -> AdbHostFeatures<'_> {
         AdbHostFeatures(self.command())
     }
 }
@@ -60,6 +111,10 @@ impl<'a> AdbCommand for AdbFeatures<'a> {
         cmd.arg("features");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -76,9 +131,33 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb features` failed");
     /// ```
-    pub fn features(&self) -> AdbFeatures {
+    pub fn features(&self) -> AdbFeatures<'_> {
         AdbFeatures(self.command())
     }
+
+    /// Features supported by adb server but not by the device,
+    /// computed by executing both `host-features` and `features`.
+    ///
+    /// Useful to find out why a feature doesn't work with the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either command fails.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// for feature in adb.missing_device_features().unwrap() {
+    ///     println!("{}", feature);
+    /// }
+    /// ```
+    pub fn missing_device_features(&self) -> AdbResult<HashSet<Feature>> {
+        let host = Feature::from_command(self.host_features())?;
+        let device = Feature::from_command(self.features())?;
+        Ok(missing_features(host, &device))
+    }
 }
 
 impl<'a> AdbCommandBuilder<'a> {
@@ -89,3 +168,37 @@ impl<'a> AdbCommandBuilder<'a> {
         AdbFeatures(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(list: &[&str]) -> HashSet<Feature> {
+        list.iter().copied().map(Feature::from).collect()
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            Feature::parse_list("shell_v2\ncmd\r\nstat_v2\n\n"),
+            features(&["shell_v2", "cmd", "stat_v2"])
+        );
+        assert_eq!(
+            Feature::parse_list("shell_v2,cmd,,libusb"),
+            features(&["shell_v2", "cmd", "libusb"])
+        );
+        assert!(Feature::parse_list("").is_empty());
+    }
+
+    #[test]
+    fn test_missing_features() {
+        let host = features(&["shell_v2", "cmd", "libusb", "push_sync"]);
+        let device = features(&["shell_v2", "cmd", "abb"]);
+        assert_eq!(
+            missing_features(host.clone(), &device),
+            features(&["libusb", "push_sync"])
+        );
+        assert!(missing_features(device.clone(), &device).is_empty());
+        assert_eq!(missing_features(host.clone(), &HashSet::new()), host);
+    }
+}
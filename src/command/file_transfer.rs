@@ -3,18 +3,23 @@
 //! - `push [--sync] [-z ALGORITHM] [-Z] LOCAL... REMOTE`: Copy local files/directories to device.
 //! - `pull [-a] [-z ALGORITHM] [-Z] REMOTE... LOCAL`: Copy files/dirs from device
 //! - `sync [-l] [-z ALGORITHM] [-Z] [all|data|odm|oem|product|system|system_ext|vendor]`:
-//!     Sync a local build from `$ANDROID_PRODUCT_OUT` to the device (default `all`)
+//!   Sync a local build from `$ANDROID_PRODUCT_OUT` to the device (default `all`)
 //!
 //! See [File Transfer Commands](https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/master/docs/user/adb.1.md#file-transfer)
 
-use std::ffi::OsStr;
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
 use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::process::Command;
 use std::str::FromStr;
 
-use crate::command::AdbCommandBuilder;
+use crate::command::features::Feature;
+use crate::command::{checksum, process, AdbCommandBuilder};
 use crate::error::ParseError;
-use crate::{Adb, AdbCommand, AdbError};
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
 
 /// Compression algorithm for file transfer commands.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -26,6 +31,19 @@ pub enum AdbCompressionAlgorithm {
     Zstd,
 }
 
+impl AdbCompressionAlgorithm {
+    /// Returns all the compression algorithms, in declaration order.
+    pub fn all() -> &'static [AdbCompressionAlgorithm] {
+        &[
+            AdbCompressionAlgorithm::Any,
+            AdbCompressionAlgorithm::None,
+            AdbCompressionAlgorithm::Brotli,
+            AdbCompressionAlgorithm::Lz4,
+            AdbCompressionAlgorithm::Zstd,
+        ]
+    }
+}
+
 impl AsRef<OsStr> for AdbCompressionAlgorithm {
     fn as_ref(&self) -> &OsStr {
         match self {
@@ -69,18 +87,61 @@ impl FromStr for AdbCompressionAlgorithm {
     }
 }
 
+impl_try_from_os_str!(AdbCompressionAlgorithm, AdbSyncTarget);
+impl_value_enum!(AdbCompressionAlgorithm, AdbSyncTarget);
+
+/// The device features of the compression algorithms, from the best to the worst.
+const COMPRESSION_FEATURES: [(&str, AdbCompressionAlgorithm); 3] = [
+    ("sendrecv_v2_zstd", AdbCompressionAlgorithm::Zstd),
+    ("sendrecv_v2_lz4", AdbCompressionAlgorithm::Lz4),
+    ("sendrecv_v2_brotli", AdbCompressionAlgorithm::Brotli),
+];
+
+/// Picks the best compression algorithm supported by both the adb server (`host`) and the `device`,
+/// falling back to [`AdbCompressionAlgorithm::Any`] with `sendrecv_v2`, or no compression without it.
+fn best_compression(
+    host: &HashSet<Feature>,
+    device: &HashSet<Feature>,
+) -> Option<AdbCompressionAlgorithm> {
+    let supported = |feature: &str| {
+        let feature = Feature::from(feature);
+        host.contains(&feature) && device.contains(&feature)
+    };
+    COMPRESSION_FEATURES
+        .iter()
+        .find(|(feature, _)| supported(feature))
+        .map(|(_, algorithm)| *algorithm)
+        .or_else(|| supported("sendrecv_v2").then_some(AdbCompressionAlgorithm::Any))
+}
+
+/// Negotiates the compression algorithm of the adb server and the device selected by `acb`
+/// with `host-features` and `features`.
+///
+/// The result is cached in the [`Adb`] instance only if the device is selected with `-s SERIAL`,
+/// otherwise (e.g. `ANDROID_SERIAL`, `-d`, a single attached device) the device may change
+/// between commands with the same global options.
+fn negotiate_compression(acb: &AdbCommandBuilder) -> AdbResult<Option<AdbCompressionAlgorithm>> {
+    let negotiate = || {
+        let host = Feature::from_command(acb.clone().host_features())?;
+        let device = Feature::from_command(acb.clone().features())?;
+        Ok(best_compression(&host, &device))
+    };
+    match acb.serial() {
+        Some(_) => acb
+            .adb
+            .compression
+            .get_or_try_insert(&acb.to_string(), negotiate),
+        None => negotiate(),
+    }
+}
+
 /// `push [--sync] [-z ALGORITHM] [-Z] LOCAL... REMOTE`: Copy local files/directories to device.
 /// - `--sync`: Only push files that are newer on the host than the device.
 /// - `-n`: Dry run, push files to device without storing to the filesystem.
 /// - `-z`: enable compression with a specified algorithm (any/none/brotli/lz4/zstd).
 /// - `-Z`: Disable compression.
 #[derive(Debug, Clone)]
-pub struct AdbPush<'a, S1, S2, I>
-where
-    S1: AsRef<OsStr>,
-    S2: AsRef<OsStr>,
-    I: IntoIterator<Item = S1>,
-{
+pub struct AdbPush<'a, S: AsRef<OsStr>> {
     acb: AdbCommandBuilder<'a>,
     /// `--sync`: Only push files that are newer on the host than the device.
     sync: bool,
@@ -90,20 +151,18 @@ where
     /// - `-Z`: Disable compression. (if [`None`])
     z: Option<AdbCompressionAlgorithm>,
     /// Local files/directories to copy.
-    local: I,
+    local: Vec<OsString>,
     /// Remote destination.
-    remote: S2,
+    remote: S,
+    /// Verify the pushed files after the transfer in [`AdbPush::run`].
+    #[cfg(feature = "verify")]
+    verify: bool,
 }
 
-impl<'a, S1, S2, I> AdbPush<'a, S1, S2, I>
-where
-    S1: AsRef<OsStr>,
-    S2: AsRef<OsStr>,
-    I: IntoIterator<Item = S1>,
-{
+impl<'a, S: AsRef<OsStr>> AdbPush<'a, S> {
     /// Creates a new `AdbPush` instance,
     /// `sync`, `n` (dry run), `z` (compression) is disabled.
-    fn new(acb: AdbCommandBuilder<'a>, local: I, remote: S2) -> Self {
+    fn new(acb: AdbCommandBuilder<'a>, local: Vec<OsString>, remote: S) -> Self {
         Self {
             acb,
             sync: false,
@@ -111,6 +170,8 @@ where
             z: None,
             local,
             remote,
+            #[cfg(feature = "verify")]
+            verify: false,
         }
     }
 
@@ -143,28 +204,49 @@ where
         self
     }
 
+    /// `-z ALGORITHM` or `-Z`: Use the best compression algorithm supported by the device.
+    ///
+    /// The algorithm is picked from the `features` of the device that the `host-features`
+    /// of the adb server also support (`zstd`, then `lz4`, then `brotli`),
+    /// falling back to `any` or no compression on old devices and adb versions.
+    /// The choice is cached in the [`Adb`] instance per device selected with `-s SERIAL`,
+    /// so both commands are executed once per device.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `features`, e.g. if no device is connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.push(["a"], "b").z_auto().unwrap().run().unwrap();
+    /// ```
+    pub fn z_auto(mut self) -> AdbResult<Self> {
+        self.z = negotiate_compression(&self.acb)?;
+        Ok(self)
+    }
+
     /// `LOCAL...`: Local files/directories to copy.
     ///
     /// The previous local files/directories will be overwritten.
-    pub fn local<S, I1>(self, local: I1) -> AdbPush<'a, S, S2, I1>
+    pub fn local<S1, I>(mut self, local: I) -> Self
     where
-        S: AsRef<OsStr>,
-        I1: IntoIterator<Item = S>,
+        S1: AsRef<OsStr>,
+        I: IntoIterator<Item = S1>,
     {
-        AdbPush {
-            acb: self.acb,
-            sync: self.sync,
-            n: self.n,
-            z: self.z,
-            local,
-            remote: self.remote,
-        }
+        self.local = local
+            .into_iter()
+            .map(|s| s.as_ref().to_os_string())
+            .collect();
+        self
     }
 
     /// `REMOTE`: Remote destination.
     ///
     /// The previous remote destination will be overwritten.
-    pub fn remote<S: AsRef<OsStr>>(self, remote: S) -> AdbPush<'a, S1, S, I> {
+    pub fn remote<S1: AsRef<OsStr>>(self, remote: S1) -> AdbPush<'a, S1> {
         AdbPush {
             acb: self.acb,
             sync: self.sync,
@@ -172,16 +254,44 @@ where
             z: self.z,
             local: self.local,
             remote,
+            #[cfg(feature = "verify")]
+            verify: self.verify,
+        }
+    }
+
+    /// Verifies the pushed files after the transfer in [`AdbPush::run`].
+    ///
+    /// The SHA-256 checksum of every local file is compared with `sha256sum` on the device,
+    /// or only the sizes are compared if the device doesn't provide `sha256sum`.
+    #[cfg(feature = "verify")]
+    pub fn verify(mut self) -> Self {
+        self.verify = true;
+        self
+    }
+
+    /// Executes the command like [`AdbCommand::checked_status`],
+    /// then verifies the pushed files if `AdbPush::verify` is set (requires the `verify` feature).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::VerificationFailed`] if any pushed file differs on the device.
+    pub fn run(self) -> AdbResult<()> {
+        #[cfg(feature = "verify")]
+        if self.verify {
+            let remote = self.remote.as_ref().to_string_lossy().into_owned();
+            let remote_is_dir = self.local.len() > 1
+                || remote.ends_with('/')
+                || checksum::remote_is_dir(&self.acb, &remote)?;
+            let transfers = checksum::push_transfers(&self.local, &remote, remote_is_dir)?;
+            let acb = self.acb.clone();
+            self.checked_status()?;
+            return checksum::verify(&acb, &transfers);
         }
+        self.checked_status()
     }
 }
 
-impl<'a, S1, S2, I> AdbCommand for AdbPush<'a, S1, S2, I>
-where
-    S1: AsRef<OsStr>,
-    S2: AsRef<OsStr>,
-    I: IntoIterator<Item = S1>,
-{
+impl<'a, S: AsRef<OsStr>> AdbCommand for AdbPush<'a, S> {
     fn build(self) -> Command {
         let mut cmd = self.acb.build();
         cmd.arg("push");
@@ -196,6 +306,15 @@ where
         cmd.args(self.local).arg(self.remote);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(&self.local)?;
+        process::check_nul([&self.remote])
+    }
 }
 
 impl Adb {
@@ -218,13 +337,13 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb push -z zstd /path/to/local /path/to/remote` failed");
     /// ```
-    pub fn push<S1, S2, I>(&self, local: I, remote: S2) -> AdbPush<S1, S2, I>
+    pub fn push<S1, S2, I>(&self, local: I, remote: S2) -> AdbPush<'_, S2>
     where
         S1: AsRef<OsStr>,
         S2: AsRef<OsStr>,
         I: IntoIterator<Item = S1>,
     {
-        AdbPush::new(self.command(), local, remote)
+        self.command().push(local, remote)
     }
 }
 
@@ -232,13 +351,20 @@ impl<'a> AdbCommandBuilder<'a> {
     /// `push [--sync] [-z ALGORITHM] [-Z] LOCAL... REMOTE`: Copy local files/directories to device.
     ///
     /// See [`Adb::push`] for more information.
-    pub fn push<S1, S2, I>(self, local: I, remote: S2) -> AdbPush<'a, S1, S2, I>
+    pub fn push<S1, S2, I>(self, local: I, remote: S2) -> AdbPush<'a, S2>
     where
         S1: AsRef<OsStr>,
         S2: AsRef<OsStr>,
         I: IntoIterator<Item = S1>,
     {
-        AdbPush::new(self, local, remote)
+        AdbPush::new(
+            self,
+            local
+                .into_iter()
+                .map(|s| s.as_ref().to_os_string())
+                .collect(),
+            remote,
+        )
     }
 }
 
@@ -247,12 +373,7 @@ impl<'a> AdbCommandBuilder<'a> {
 /// - `-z`: enable compression with a specified algorithm (any/none/brotli/lz4/zstd).
 /// - `-Z`: disable compression.
 #[derive(Debug, Clone)]
-pub struct AdbPull<'a, S1, S2, I>
-where
-    S1: AsRef<OsStr>,
-    S2: AsRef<OsStr>,
-    I: IntoIterator<Item = S1>,
-{
+pub struct AdbPull<'a, S: AsRef<OsStr>> {
     acb: AdbCommandBuilder<'a>,
     /// `-a`: Preserve file timestamps and permissions.
     a: bool,
@@ -260,26 +381,29 @@ where
     /// - `-Z`: Disable compression. (if [`None`])
     z: Option<AdbCompressionAlgorithm>,
     /// Remote files/directories to copy.
-    remote: I,
+    remote: Vec<OsString>,
     /// Local destination.
-    local: S2,
+    local: S,
+    /// Verify the pulled files after the transfer in [`AdbPull::run`].
+    #[cfg(feature = "verify")]
+    verify: bool,
+    /// Skip the files whose local copy is up-to-date in [`AdbPull::run`].
+    skip_unchanged: bool,
 }
 
-impl<'a, S1, S2, I> AdbPull<'a, S1, S2, I>
-where
-    S1: AsRef<OsStr>,
-    S2: AsRef<OsStr>,
-    I: IntoIterator<Item = S1>,
-{
+impl<'a, S: AsRef<OsStr>> AdbPull<'a, S> {
     /// Creates a new `AdbPull` instance,
     /// `a` (preserve file timestamp and mode), `z` (compression) is disabled.
-    fn new(acb: AdbCommandBuilder<'a>, remote: I, local: S2) -> Self {
+    fn new(acb: AdbCommandBuilder<'a>, remote: Vec<OsString>, local: S) -> Self {
         Self {
             acb,
             a: false,
             z: None,
             remote,
             local,
+            #[cfg(feature = "verify")]
+            verify: false,
+            skip_unchanged: false,
         }
     }
 
@@ -306,43 +430,143 @@ where
         self
     }
 
+    /// `-z ALGORITHM` or `-Z`: Use the best compression algorithm supported by the device.
+    ///
+    /// The algorithm is picked from the `features` of the device that the `host-features`
+    /// of the adb server also support (`zstd`, then `lz4`, then `brotli`),
+    /// falling back to `any` or no compression on old devices and adb versions.
+    /// The choice is cached in the [`Adb`] instance per device selected with `-s SERIAL`,
+    /// so both commands are executed once per device.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `features`, e.g. if no device is connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.pull(["a"], "b").z_auto().unwrap().run().unwrap();
+    /// ```
+    pub fn z_auto(mut self) -> AdbResult<Self> {
+        self.z = negotiate_compression(&self.acb)?;
+        Ok(self)
+    }
+
     /// `REMOTE...`: Remote files/directories to copy.
     ///
     /// The previous remote files/directories will be overwritten.
-    pub fn remote<S, I1>(self, remote: I1) -> AdbPull<'a, S, S2, I1>
+    pub fn remote<S1, I>(mut self, remote: I) -> Self
     where
-        S: AsRef<OsStr>,
-        I1: IntoIterator<Item = S>,
+        S1: AsRef<OsStr>,
+        I: IntoIterator<Item = S1>,
     {
-        AdbPull {
-            acb: self.acb,
-            a: self.a,
-            z: self.z,
-            remote,
-            local: self.local,
-        }
+        self.remote = remote
+            .into_iter()
+            .map(|s| s.as_ref().to_os_string())
+            .collect();
+        self
     }
 
     /// `LOCAL`: Local destination.
     ///
     /// The previous local destination will be overwritten.
-    pub fn local<S: AsRef<OsStr>>(self, local: S) -> AdbPull<'a, S1, S, I> {
+    pub fn local<S1: AsRef<OsStr>>(self, local: S1) -> AdbPull<'a, S1> {
         AdbPull {
             acb: self.acb,
             a: self.a,
             z: self.z,
             remote: self.remote,
             local,
+            #[cfg(feature = "verify")]
+            verify: self.verify,
+            skip_unchanged: self.skip_unchanged,
         }
     }
+
+    /// Skips the remote files whose local copy is up-to-date in [`AdbPull::run`],
+    /// like `push --sync` but for `pull`.
+    ///
+    /// Before pulling, the modification time of every remote file (by `stat` on the device)
+    /// is compared with its local copy, which is skipped if it's not older than the remote file.
+    /// The remaining files are then pulled one by one.
+    pub fn skip_unchanged(mut self) -> Self {
+        self.skip_unchanged = true;
+        self
+    }
+
+    /// Verifies the pulled files after the transfer in [`AdbPull::run`].
+    ///
+    /// The SHA-256 checksum of every local file is compared with `sha256sum` on the device,
+    /// or only the sizes are compared if the device doesn't provide `sha256sum`.
+    #[cfg(feature = "verify")]
+    pub fn verify(mut self) -> Self {
+        self.verify = true;
+        self
+    }
+
+    /// Executes the command like [`AdbCommand::checked_status`],
+    /// skipping up-to-date files if [`AdbPull::skip_unchanged`] is set,
+    /// then verifies the pulled files if `AdbPull::verify` is set (requires the `verify` feature).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::VerificationFailed`] if any pulled file differs from the device.
+    pub fn run(self) -> AdbResult<()> {
+        #[cfg(feature = "verify")]
+        let verify = self.verify;
+        #[cfg(not(feature = "verify"))]
+        let verify = false;
+        if !verify && !self.skip_unchanged {
+            return self.checked_status();
+        }
+        let remote = self
+            .remote
+            .iter()
+            .map(|r| {
+                let r = r.to_string_lossy().into_owned();
+                checksum::remote_files(&self.acb, &r).map(|files| (r, files))
+            })
+            .collect::<AdbResult<Vec<_>>>()?;
+        let local = Path::new(self.local.as_ref()).to_path_buf();
+        let local_is_dir = remote.len() > 1 || local.is_dir();
+        let transfers = checksum::pull_transfers(&remote, &local, local_is_dir);
+        let acb = self.acb.clone();
+        // the transfers are only needed afterwards to verify them
+        #[cfg_attr(not(feature = "verify"), allow(unused_variables))]
+        let transfers = if self.skip_unchanged {
+            let transfers = checksum::changed(&acb, transfers)?;
+            for transfer in &transfers {
+                if let Some(parent) = transfer.local.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                AdbPull {
+                    acb: acb.clone(),
+                    a: self.a,
+                    z: self.z,
+                    remote: vec![transfer.remote.clone().into()],
+                    local: &transfer.local,
+                    #[cfg(feature = "verify")]
+                    verify: false,
+                    skip_unchanged: false,
+                }
+                .checked_status()?;
+            }
+            transfers
+        } else {
+            self.checked_status()?;
+            transfers
+        };
+        #[cfg(feature = "verify")]
+        if verify {
+            checksum::verify(&acb, &transfers)?;
+        }
+        Ok(())
+    }
 }
 
-impl<'a, S1, S2, I> AdbCommand for AdbPull<'a, S1, S2, I>
-where
-    S1: AsRef<OsStr>,
-    S2: AsRef<OsStr>,
-    I: IntoIterator<Item = S1>,
-{
+impl<'a, S: AsRef<OsStr>> AdbCommand for AdbPull<'a, S> {
     fn build(self) -> Command {
         let mut cmd = self.acb.build();
         cmd.arg("pull");
@@ -357,9 +581,116 @@ where
         cmd.args(self.remote).arg(self.local);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(&self.remote)?;
+        process::check_nul([&self.local])
+    }
+}
+
+/// The summary printed by `pull`, e.g. `/sdcard/dir/: 3 files pulled, 0 skipped. 1.2 MB/s (4096 bytes in 0.003s)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PullSummary {
+    /// The number of pulled files.
+    pub pulled: u32,
+    /// The number of skipped files, e.g. special files.
+    pub skipped: u32,
+    /// The number of transferred bytes, if reported.
+    pub bytes: Option<u64>,
+}
+
+impl FromStr for PullSummary {
+    type Err = AdbError;
+
+    /// Parses the summary line of `pull`, ignoring the other lines.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseError::with_description(s, "PullSummary", "Missing pull summary");
+        let line = s
+            .lines()
+            .rev()
+            .find(|line| line.contains(" pulled"))
+            .ok_or_else(err)?;
+        // the number right before `word`, e.g. `3` in `3 files pulled`
+        let number_before = |word: &str| -> Option<&str> {
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            let i = tokens
+                .iter()
+                .position(|t| t.trim_end_matches([',', '.']) == word)?;
+            tokens.get(i.checked_sub(1)?).copied()
+        };
+        let count = |word: &str| -> AdbResult<u32> {
+            match number_before(word) {
+                Some(n) => n
+                    .trim_start_matches('(')
+                    .parse()
+                    .map_err(|e| ParseError::with_source(s, "u32", e).into()),
+                None => Ok(0),
+            }
+        };
+        // the file count may precede `file` or `files`
+        let pulled = match number_before("files") {
+            Some(_) => count("files")?,
+            None => count("file")?,
+        };
+        let bytes = match number_before("bytes") {
+            Some(n) => Some(
+                n.trim_start_matches('(')
+                    .parse()
+                    .map_err(|e| ParseError::with_source(s, "u64", e))?,
+            ),
+            None => None,
+        };
+        Ok(PullSummary {
+            pulled,
+            skipped: count("skipped")?,
+            bytes,
+        })
+    }
 }
 
 impl Adb {
+    /// `pull REMOTE_DIR LOCAL_DIR`: Copy a directory from device,
+    /// creating `local_dir` (and its parents) first.
+    ///
+    /// Like `adb pull`, the directory is copied into `local_dir`, i.e. to `LOCAL_DIR/NAME`.
+    /// Returns the summary printed by adb.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Io`] with [`io::ErrorKind::InvalidInput`] without pulling
+    /// if `remote_dir` is not a directory on the device (checked with `test -d`).
+    /// Returns [`AdbError::NonZeroExit`] if adb fails to run the check, e.g. without a device.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let summary = adb.pull_dir("/sdcard/DCIM", "backup/phone").unwrap();
+    /// println!("{} files pulled", summary.pulled);
+    /// ```
+    pub fn pull_dir<P: AsRef<Path>>(
+        &self,
+        remote_dir: &str,
+        local_dir: P,
+    ) -> AdbResult<PullSummary> {
+        let acb = self.command();
+        if !checksum::remote_is_dir(&acb, remote_dir)? {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("`{}` is not a directory on the device", remote_dir),
+            )
+            .into());
+        }
+        let local_dir = local_dir.as_ref();
+        fs::create_dir_all(local_dir)?;
+        process::stdout_string(acb.pull([remote_dir], local_dir).output()?)?.parse()
+    }
+
     /// `pull [-a] [-z ALGORITHM] [-Z] REMOTE... LOCAL`: Copy files/dirs from device
     /// - `-a`: preserve file timestamp and mode.
     /// - `-z`: enable compression with a specified algorithm (any/none/brotli/lz4/zstd).
@@ -378,13 +709,13 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb pull -z zstd /path/to/remote /path/to/local` failed");
     /// ```
-    pub fn pull<S1, S2, I>(&self, remote: I, local: S2) -> AdbPull<S1, S2, I>
+    pub fn pull<S1, S2, I>(&self, remote: I, local: S2) -> AdbPull<'_, S2>
     where
         S1: AsRef<OsStr>,
         S2: AsRef<OsStr>,
         I: IntoIterator<Item = S1>,
     {
-        AdbPull::new(self.command(), remote, local)
+        self.command().pull(remote, local)
     }
 }
 
@@ -392,13 +723,20 @@ impl<'a> AdbCommandBuilder<'a> {
     /// `pull [-a] [-z ALGORITHM] [-Z] REMOTE... LOCAL`: Copy files/dirs from device
     ///
     /// See [`Adb::pull`] for more information.
-    pub fn pull<S1, S2, I>(self, remote: I, local: S2) -> AdbPull<'a, S1, S2, I>
+    pub fn pull<S1, S2, I>(self, remote: I, local: S2) -> AdbPull<'a, S2>
     where
         S1: AsRef<OsStr>,
         S2: AsRef<OsStr>,
         I: IntoIterator<Item = S1>,
     {
-        AdbPull::new(self, remote, local)
+        AdbPull::new(
+            self,
+            remote
+                .into_iter()
+                .map(|s| s.as_ref().to_os_string())
+                .collect(),
+            local,
+        )
     }
 }
 
@@ -416,6 +754,22 @@ pub enum AdbSyncTarget {
     Vendor,
 }
 
+impl AdbSyncTarget {
+    /// Returns all the sync targets, in declaration order.
+    pub fn all() -> &'static [AdbSyncTarget] {
+        &[
+            AdbSyncTarget::All,
+            AdbSyncTarget::Data,
+            AdbSyncTarget::Odm,
+            AdbSyncTarget::Oem,
+            AdbSyncTarget::Product,
+            AdbSyncTarget::System,
+            AdbSyncTarget::SystemExt,
+            AdbSyncTarget::Vendor,
+        ]
+    }
+}
+
 impl AsRef<OsStr> for AdbSyncTarget {
     fn as_ref(&self) -> &OsStr {
         match self {
@@ -559,6 +913,10 @@ impl<'a> AdbCommand for AdbSync<'a> {
         }
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
 }
 
 impl Adb {
@@ -583,7 +941,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb sync -z zstd` failed");
     /// ```
-    pub fn sync(&self) -> AdbSync {
+    pub fn sync(&self) -> AdbSync<'_> {
         AdbSync::new(self.command())
     }
 }
@@ -597,3 +955,144 @@ impl<'a> AdbCommandBuilder<'a> {
         AdbSync::new(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_all() {
+        for &algorithm in AdbCompressionAlgorithm::all() {
+            assert_eq!(
+                algorithm
+                    .to_string()
+                    .parse::<AdbCompressionAlgorithm>()
+                    .unwrap(),
+                algorithm
+            );
+        }
+        for &target in AdbSyncTarget::all() {
+            assert_eq!(target.to_string().parse::<AdbSyncTarget>().unwrap(), target);
+        }
+    }
+
+    #[test]
+    fn test_try_from_os_str() {
+        assert_eq!(
+            AdbCompressionAlgorithm::try_from(OsStr::new("zstd")).unwrap(),
+            AdbCompressionAlgorithm::Zstd
+        );
+        assert!(AdbCompressionAlgorithm::try_from(OsStr::new("gzip"))
+            .unwrap_err()
+            .is_parse());
+        assert_eq!(
+            AdbSyncTarget::try_from(OsStr::new("data")).unwrap(),
+            AdbSyncTarget::Data
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let invalid = OsStr::from_bytes(b"dat\xffa");
+            assert!(AdbSyncTarget::try_from(invalid).unwrap_err().is_parse());
+        }
+    }
+
+    #[test]
+    fn test_best_compression() {
+        let features = |s: &str| Feature::parse_list(s);
+        let all =
+            features("shell_v2,sendrecv_v2,sendrecv_v2_brotli,sendrecv_v2_lz4,sendrecv_v2_zstd");
+        assert_eq!(
+            best_compression(&all, &all),
+            Some(AdbCompressionAlgorithm::Zstd)
+        );
+        assert_eq!(
+            best_compression(
+                &all,
+                &features("sendrecv_v2,sendrecv_v2_brotli,sendrecv_v2_lz4")
+            ),
+            Some(AdbCompressionAlgorithm::Lz4)
+        );
+        // an old adb server without zstd
+        assert_eq!(
+            best_compression(
+                &features("sendrecv_v2,sendrecv_v2_brotli,sendrecv_v2_lz4"),
+                &all
+            ),
+            Some(AdbCompressionAlgorithm::Lz4)
+        );
+        assert_eq!(
+            best_compression(&all, &features("sendrecv_v2")),
+            Some(AdbCompressionAlgorithm::Any)
+        );
+        assert_eq!(best_compression(&features("shell_v2"), &all), None);
+        assert_eq!(best_compression(&all, &features("shell_v2,cmd")), None);
+    }
+
+    #[test]
+    fn test_negotiate_compression_cache() {
+        let adb = Adb::default();
+        adb.compression
+            .get_or_try_insert(&adb.s_serial("a").to_string(), || {
+                Ok(Some(AdbCompressionAlgorithm::Lz4))
+            })
+            .unwrap();
+        assert_eq!(
+            args(adb.s_serial("a").push(["x"], "/sdcard").z_auto().unwrap()),
+            ["-s", "a", "push", "-z", "lz4", "x", "/sdcard"]
+        );
+    }
+
+    #[test]
+    fn test_pull_summary() {
+        assert_eq!(
+            "/sdcard/dir/: 3 files pulled, 1 skipped. 1.2 MB/s (4096 bytes in 0.003s)"
+                .parse::<PullSummary>()
+                .unwrap(),
+            PullSummary {
+                pulled: 3,
+                skipped: 1,
+                bytes: Some(4096)
+            }
+        );
+        assert_eq!(
+            "/sdcard/a.txt: 1 file pulled, 0 skipped.\n"
+                .parse::<PullSummary>()
+                .unwrap(),
+            PullSummary {
+                pulled: 1,
+                skipped: 0,
+                bytes: None
+            }
+        );
+        assert!("".parse::<PullSummary>().is_err());
+        assert!("/sdcard/dir/: x files pulled"
+            .parse::<PullSummary>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_push_pull_clone() {
+        let adb = Adb::default();
+        let push = adb
+            .push(vec!["a".to_string(), "b".to_string()], "/sdcard")
+            .sync();
+        assert_eq!(args(push.clone()), args(push));
+        assert_eq!(
+            args(adb.push(["a"], "/sdcard").local(["b", "c"])),
+            ["push", "-Z", "b", "c", "/sdcard"]
+        );
+
+        let pull = adb.pull(["/sdcard/a", "/sdcard/b"], ".").a();
+        assert_eq!(args(pull.clone()), args(pull));
+        assert_eq!(
+            args(
+                adb.pull(["/sdcard/a"], ".")
+                    .remote(["/sdcard/b"])
+                    .local("/tmp")
+            ),
+            ["pull", "-Z", "/sdcard/b", "/tmp"]
+        );
+    }
+}
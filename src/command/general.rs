@@ -6,10 +6,140 @@
 //!
 //! See [General Commands](https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/master/docs/user/adb.1.md#general-commands).
 
+use std::fmt::Display;
 use std::process::Command;
+use std::str::FromStr;
 
-use crate::command::AdbCommandBuilder;
-use crate::{Adb, AdbCommand};
+use crate::command::{process, AdbCommandBuilder, DeviceState};
+use crate::error::ParseError;
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
+
+/// A device listed by `devices [-l]`.
+///
+/// The optional fields are only reported with `-l`.
+///
+/// # Examples
+///
+/// ```
+/// # use adbr::command::{AdbDevice, DeviceState};
+/// let line = "emulator-5554          device product:sdk_gphone64_x86_64 model:sdk_gphone64_x86_64 device:emu64xa transport_id:1";
+/// let device = line.parse::<AdbDevice>().unwrap();
+/// assert_eq!(device.serial, "emulator-5554");
+/// assert!(device.is_online());
+/// assert_eq!(device.transport_id, Some(1));
+/// assert_eq!(device.to_string(), line);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdbDevice {
+    /// The serial of the device, e.g. `emulator-5554` or `192.168.0.2:5555`.
+    pub serial: String,
+    /// The connection state of the device.
+    pub state: DeviceState,
+    /// `usb:PATH`: The USB device path, e.g. `1-1.2`.
+    pub usb: Option<String>,
+    /// `product:PRODUCT`: The product name.
+    pub product: Option<String>,
+    /// `model:MODEL`: The model name.
+    pub model: Option<String>,
+    /// `device:DEVICE`: The device name.
+    pub device: Option<String>,
+    /// `transport_id:ID`: The transport id, which can be used with `-t ID`.
+    pub transport_id: Option<u32>,
+}
+
+impl AdbDevice {
+    /// Whether the device is online, i.e. in the [`DeviceState::Device`] state.
+    pub fn is_online(&self) -> bool {
+        self.state == DeviceState::Device
+    }
+
+    /// The `KEY:VALUE` fields of the long output, in the order adb prints them.
+    fn fields(&self) -> impl Iterator<Item = (&'static str, String)> + '_ {
+        [
+            ("usb", self.usb.clone()),
+            ("product", self.product.clone()),
+            ("model", self.model.clone()),
+            ("device", self.device.clone()),
+            ("transport_id", self.transport_id.map(|id| id.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+    }
+}
+
+impl Display for AdbDevice {
+    /// Formats the device like a line of `devices`, or `devices -l` if any optional field is set.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut fields = self.fields().peekable();
+        if fields.peek().is_none() {
+            return write!(f, "{}\t{}", self.serial, self.state);
+        }
+        write!(f, "{:<22} {}", self.serial, self.state)?;
+        for (key, value) in fields {
+            write!(f, " {}:{}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AdbDevice {
+    type Err = AdbError;
+
+    /// Parses a line of `devices [-l]`, e.g. `emulator-5554\tdevice`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace().collect::<Vec<_>>();
+        if tokens.len() < 2 {
+            return Err(
+                ParseError::with_description(s, "AdbDevice", "Missing serial or state").into(),
+            );
+        }
+        let serial = tokens.remove(0).to_string();
+        let mut device = AdbDevice {
+            serial,
+            state: DeviceState::Offline,
+            usb: None,
+            product: None,
+            model: None,
+            device: None,
+            transport_id: None,
+        };
+        // the fields are at the end, since the state (e.g. `no permissions (...)`) may contain spaces
+        while tokens.len() > 1 {
+            let Some((key, value)) = tokens[tokens.len() - 1].split_once(':') else {
+                break;
+            };
+            match key {
+                "usb" => device.usb = Some(value.to_string()),
+                "product" => device.product = Some(value.to_string()),
+                "model" => device.model = Some(value.to_string()),
+                "device" => device.device = Some(value.to_string()),
+                "transport_id" => {
+                    device.transport_id = Some(
+                        value
+                            .parse()
+                            .map_err(|e| ParseError::with_source(s, "transport_id (u32)", e))?,
+                    )
+                }
+                _ => break,
+            }
+            tokens.pop();
+        }
+        device.state = tokens.join(" ").parse()?;
+        Ok(device)
+    }
+}
+
+/// Parses the output of `devices [-l]`, skipping the header and the messages of the adb server.
+fn parse_devices(output: &str) -> AdbResult<Vec<AdbDevice>> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            !line.is_empty() && !line.starts_with("List of devices") && !line.starts_with('*')
+        })
+        .map(str::parse)
+        .collect()
+}
 
 /// `devices [-l]`: List connected devices.
 /// - `-l`: Use long output.
@@ -30,6 +160,23 @@ impl<'a> Devices<'a> {
         self.l = true;
         self
     }
+
+    /// Executes the command and parses the listed devices.
+    ///
+    /// Set [`Self::l`] to get the optional fields of [`AdbDevice`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// for device in adb.devices().l().run().unwrap() {
+    ///     println!("{}", device);
+    /// }
+    /// ```
+    pub fn run(self) -> AdbResult<Vec<AdbDevice>> {
+        parse_devices(&process::stdout_string(self.output()?)?)
+    }
 }
 
 impl<'a> AdbCommand for Devices<'a> {
@@ -41,6 +188,10 @@ impl<'a> AdbCommand for Devices<'a> {
         }
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
 }
 
 impl Adb {
@@ -59,7 +210,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb devices -l` failed");
     /// ```
-    pub fn devices(&self) -> Devices {
+    pub fn devices(&self) -> Devices<'_> {
         Devices::new(self.command())
     }
 }
@@ -84,6 +235,10 @@ impl<'a> AdbCommand for Help<'a> {
         cmd.arg("help");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -98,7 +253,7 @@ impl Adb {
     /// # let adb = Adb::new().unwrap();
     /// adb.help().status().expect("`adb help` failed");
     /// ```
-    pub fn help(&self) -> Help {
+    pub fn help(&self) -> Help<'_> {
         Help(self.command())
     }
 }
@@ -122,6 +277,10 @@ impl<'a> AdbCommand for Version<'a> {
         cmd.arg("version");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -136,7 +295,7 @@ impl Adb {
     /// # let adb = Adb::new().unwrap();
     /// adb.version().status().expect("`adb version` failed");
     /// ```
-    pub fn version(&self) -> Version {
+    pub fn version(&self) -> Version<'_> {
         Version(self.command())
     }
 }
@@ -149,3 +308,59 @@ impl<'a> AdbCommandBuilder<'a> {
         Version(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_devices() {
+        let output = "* daemon not running; starting now at tcp:5037\n\
+                      * daemon started successfully\n\
+                      List of devices attached\n\
+                      emulator-5554\tdevice\n\
+                      0123456789ABCDEF\tunauthorized\n\n";
+        let devices = parse_devices(output).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].serial, "emulator-5554");
+        assert!(devices[0].is_online());
+        assert_eq!(devices[1].state, DeviceState::Unauthorized);
+        assert!(!devices[1].is_online());
+        assert_eq!(devices[1].transport_id, None);
+        assert_eq!(devices[0].to_string(), "emulator-5554\tdevice");
+    }
+
+    #[test]
+    fn test_parse_devices_long() {
+        let output = "List of devices attached\n\
+                      0123456789ABCDEF       device usb:1-1.2 product:redfin model:Pixel_5 device:redfin transport_id:3\n\
+                      0123456789ABCDEG       no permissions (missing udev rules? user is in the plugdev group); see [http://developer.android.com/tools/device.html] usb:1-1.3 transport_id:4\n";
+        let devices = parse_devices(output).unwrap();
+        assert_eq!(
+            devices[0],
+            AdbDevice {
+                serial: "0123456789ABCDEF".to_string(),
+                state: DeviceState::Device,
+                usb: Some("1-1.2".to_string()),
+                product: Some("redfin".to_string()),
+                model: Some("Pixel_5".to_string()),
+                device: Some("redfin".to_string()),
+                transport_id: Some(3),
+            }
+        );
+        assert_eq!(devices[0].to_string(), output.lines().nth(1).unwrap());
+        assert_eq!(
+            devices[0].to_string().parse::<AdbDevice>().unwrap(),
+            devices[0]
+        );
+        assert_eq!(devices[1].state, DeviceState::NoPermissions);
+        assert_eq!(devices[1].usb.as_deref(), Some("1-1.3"));
+        assert_eq!(devices[1].transport_id, Some(4));
+
+        assert!("emulator-5554".parse::<AdbDevice>().is_err());
+        assert!("emulator-5554 online".parse::<AdbDevice>().is_err());
+        assert!("emulator-5554 device transport_id:x"
+            .parse::<AdbDevice>()
+            .is_err());
+    }
+}
@@ -9,7 +9,7 @@
 //! - [`AdbGlobalOption::Port`] `-P *PORT` Smart socket PORT of adb server (default=`5037`).
 //! - [`AdbGlobalOption::Listen`] `-L SOCKET` Listen on given socket for adb server (default=`tcp:localhost:5037`).
 //! - [`AdbGlobalOption::OneDevice`] `--one-device SERIAL | USB` Server will only connect to one USB device,
-//!    specified by a SERIAL number or USB device address (only with ‘start-server’ or ‘server nodaemon’).
+//!   specified by a SERIAL number or USB device address (only with ‘start-server’ or ‘server nodaemon’).
 //! - [`AdbGlobalOption::ExitOnWriteError`] `--exit-on-write-error` Exit if stdout is closed.
 //!
 //! See [GLOBAL OPTIONS](https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/master/docs/user/adb.1.md#global-options)
@@ -20,9 +20,12 @@ use std::str::FromStr;
 
 use crate::command::AdbCommandBuilder;
 use crate::error::{AdbError, ParseError};
-use crate::socket::Tcp;
+use crate::socket::{AdbSocketFamily, Tcp};
 use crate::{Adb, AdbResult};
 
+/// The default smart socket port of the adb server, used when no `-P` option is given.
+pub const DEFAULT_ADB_SERVER_PORT: u16 = 5037;
+
 /// The global options of the `adb` command.
 ///
 /// # Examples
@@ -30,7 +33,7 @@ use crate::{Adb, AdbResult};
 /// ```
 /// # use std::net::{IpAddr, Ipv4Addr};
 /// # use adbr::AdbGlobalOption;
-/// # use adbr::socket::Tcp;
+/// # use adbr::socket::{AdbSocketFamily, LocalAbstract, Tcp};
 /// assert_eq!("-a".parse::<AdbGlobalOption>().unwrap(), AdbGlobalOption::ListenAll);
 /// assert_eq!(
 ///     "-s emulator-123".parse::<AdbGlobalOption>().unwrap(),
@@ -38,13 +41,19 @@ use crate::{Adb, AdbResult};
 /// );
 /// assert_eq!(
 ///     "-L tcp:127.0.0.1:8080".parse::<AdbGlobalOption>().unwrap(),
-///     AdbGlobalOption::Listen(Tcp{
+///     AdbGlobalOption::Listen(AdbSocketFamily::Tcp(Tcp{
 ///         ip: Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))),
 ///         port: Some(8080)
-///     })
+///     }))
+/// );
+/// assert_eq!(
+///     "-L localabstract:adb".parse::<AdbGlobalOption>().unwrap(),
+///     AdbGlobalOption::Listen(AdbSocketFamily::LocalAbstract(LocalAbstract("adb".to_string())))
 /// );
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+///
+/// The options are ordered by their variants, which is the order they're emitted in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum AdbGlobalOption {
     /// `-a`: Listen on all network interfaces, not just localhost.
     ListenAll,
@@ -53,6 +62,9 @@ pub enum AdbGlobalOption {
     /// `-e`: Use TCP/IP device (error if multiple TCP/IP devices available).
     TcpIp,
     /// `-s SERIAL`: Use device with given SERIAL (overrides $ANDROID_SERIAL).
+    ///
+    /// If `ANDROID_SERIAL` is also set in the [`AdbEnvs`](crate::AdbEnvs), `-s` silently wins.
+    /// See [`AdbCommandBuilder::check_serial_conflict`] to catch a leftover, different serial.
     Serial(String),
     /// `-t ID`: Use device with given transport id.
     TransportId(String),
@@ -61,7 +73,10 @@ pub enum AdbGlobalOption {
     /// `-P *PORT`: Smart socket PORT of adb server. Default is `5037`.
     Port(u16),
     /// `-L SOCKET`: Listen on given socket for adb server. Default is `tcp:localhost:5037`.
-    Listen(Tcp),
+    ///
+    /// Besides `tcp`, the server may listen on e.g. a `localabstract` Unix domain socket,
+    /// see [`AdbSocketFamily::is_listenable`].
+    Listen(AdbSocketFamily),
     /// `--one-device SERIAL | USB`:
     /// Server will only connect to one USB device, specified by a SERIAL number or USB device address
     /// (only with `start-server` or `server nodaemon`).
@@ -86,13 +101,13 @@ impl AdbGlobalOption {
     /// ```
     /// # use std::net::{IpAddr, Ipv4Addr};
     /// # use adbr::AdbGlobalOption;
-    /// # use adbr::socket::Tcp;
+    /// # use adbr::socket::{AdbSocketFamily, Tcp};
     /// assert_eq!(
     ///     AdbGlobalOption::from_host("-L tcp:localhost:8080").unwrap(),
-    ///     AdbGlobalOption::Listen(Tcp{
+    ///     AdbGlobalOption::Listen(AdbSocketFamily::Tcp(Tcp{
     ///         ip: Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))),
     ///         port: Some(8080)
-    ///     })
+    ///     }))
     /// );
     /// ```
     pub fn from_host(s: &str) -> AdbResult<Self> {
@@ -135,19 +150,31 @@ impl AdbGlobalOption {
                 .parse()
                 .map(Self::Port)
                 .map_err(|e| ParseError::with_source(val, "port (u16)", e).into()),
-            "-L" => {
-                if RESOLVE {
-                    Ok(Self::Listen(Tcp::from_host(val)?))
-                } else {
-                    val.parse().map(Self::Listen)
-                }
-            }
+            "-L" => parse_listen::<RESOLVE>(val).map(Self::Listen),
             "--one-device" => Ok(Self::OneDevice(val.to_string())),
             _ => Err(ParseError::with_description(opt, "GlobalOption", "unknown option").into()),
         }
     }
 }
 
+/// Parses the socket of `-L`, resolving the hostname of a `tcp` socket if `RESOLVE` is `true`.
+///
+/// # Errors
+///
+/// Returns [`AdbError::Parse`] if the socket can't be listened on, e.g. `jdwp:PID`.
+fn parse_listen<const RESOLVE: bool>(val: &str) -> AdbResult<AdbSocketFamily> {
+    let socket = if RESOLVE && val.starts_with("tcp:") {
+        AdbSocketFamily::Tcp(Tcp::from_host(val)?)
+    } else {
+        val.parse()?
+    };
+    if socket.is_listenable() {
+        Ok(socket)
+    } else {
+        Err(ParseError::with_description(val, "AdbSocketFamily", "not a listen socket").into())
+    }
+}
+
 impl FromStr for AdbGlobalOption {
     type Err = AdbError;
 
@@ -156,6 +183,39 @@ impl FromStr for AdbGlobalOption {
     }
 }
 
+impl AdbGlobalOption {
+    /// The command line arguments of the option, e.g. `["-s", "emulator-5554"]`.
+    ///
+    /// Unlike [`Display`], the flag and its value are separate arguments,
+    /// as adb expects them in the argv.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::AdbGlobalOption;
+    /// assert_eq!(AdbGlobalOption::Usb.args(), ["-d"]);
+    /// assert_eq!(
+    ///     AdbGlobalOption::Serial("emulator-5554".to_string()).args(),
+    ///     ["-s", "emulator-5554"]
+    /// );
+    /// ```
+    pub fn args(&self) -> Vec<String> {
+        let (flag, value) = match self {
+            Self::ListenAll => ("-a", None),
+            Self::Usb => ("-d", None),
+            Self::TcpIp => ("-e", None),
+            Self::Serial(serial) => ("-s", Some(serial.clone())),
+            Self::TransportId(id) => ("-t", Some(id.clone())),
+            Self::Host(ip) => ("-H", Some(ip.to_string())),
+            Self::Port(port) => ("-P", Some(port.to_string())),
+            Self::Listen(addr) => ("-L", Some(addr.to_string())),
+            Self::OneDevice(device) => ("--one-device", Some(device.clone())),
+            Self::ExitOnWriteError => ("--exit-on-write-error", None),
+        };
+        std::iter::once(flag.to_string()).chain(value).collect()
+    }
+}
+
 impl Display for AdbGlobalOption {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -175,33 +235,33 @@ impl Display for AdbGlobalOption {
 
 impl Adb {
     /// `-a`: Listen on all network interfaces, not just localhost.
-    pub fn a_listen_all(&self) -> AdbCommandBuilder {
+    pub fn a_listen_all(&self) -> AdbCommandBuilder<'_> {
         self.command().a_listen_all()
     }
 
     /// `-d`: Use USB device (error if multiple devices connected).
-    pub fn d_usb(&self) -> AdbCommandBuilder {
+    pub fn d_usb(&self) -> AdbCommandBuilder<'_> {
         self.command().d_usb()
     }
 
     /// `-e`: Use TCP/IP device (error if multiple TCP/IP devices available).
-    pub fn e_tcp_ip(&self) -> AdbCommandBuilder {
+    pub fn e_tcp_ip(&self) -> AdbCommandBuilder<'_> {
         self.command().e_tcp_ip()
     }
 
     /// `-s SERIAL`: Use device with given SERIAL (overrides $ANDROID_SERIAL).
-    pub fn s_serial<S: ToString>(&self, serial: S) -> AdbCommandBuilder {
+    pub fn s_serial<S: ToString>(&self, serial: S) -> AdbCommandBuilder<'_> {
         self.command().s_serial(serial)
     }
 
     /// `-t ID`: Use device with given transport id.
-    pub fn t_transport_id<S: ToString>(&self, id: S) -> AdbCommandBuilder {
+    pub fn t_transport_id<S: ToString>(&self, id: S) -> AdbCommandBuilder<'_> {
         self.command().t_transport_id(id)
     }
 
     /// `-H`: Name of adb server host. Default is `localhost`.
     #[allow(non_snake_case)]
-    pub fn H_host<A: Into<IpAddr>>(&self, host: A) -> AdbCommandBuilder {
+    pub fn H_host<A: Into<IpAddr>>(&self, host: A) -> AdbCommandBuilder<'_> {
         self.command().H_host(host)
     }
 
@@ -209,39 +269,70 @@ impl Adb {
     ///
     /// This will resolve the hostname to an IP address. See [`Tcp::from_host`] for more information.
     #[allow(non_snake_case)]
-    pub fn H_host_resolved(&self, host: &str) -> AdbResult<AdbCommandBuilder> {
+    pub fn H_host_resolved(&self, host: &str) -> AdbResult<AdbCommandBuilder<'_>> {
         self.command().H_host_resolved(host)
     }
 
     /// `-P *PORT`: Smart socket PORT of adb server. Default is `5037`.
     #[allow(non_snake_case)]
-    pub fn P_port(&self, port: u16) -> AdbCommandBuilder {
+    pub fn P_port(&self, port: u16) -> AdbCommandBuilder<'_> {
         self.command().P_port(port)
     }
 
+    /// `-H` and `-P`: Name and smart socket PORT of adb server.
+    ///
+    /// Shortcut for [`Self::H_host`] followed by [`AdbCommandBuilder::P_port`].
+    ///
+    /// # Examples
+    ///
+    /// `adb -H 192.168.0.2 -P 5037 devices`
+    ///
+    /// ```no_run
+    /// # use std::net::Ipv4Addr;
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.server(Ipv4Addr::new(192, 168, 0, 2), 5037)
+    ///     .devices()
+    ///     .status()
+    ///     .expect("`adb -H 192.168.0.2 -P 5037 devices` failed");
+    /// ```
+    pub fn server<A: Into<IpAddr>>(&self, host: A, port: u16) -> AdbCommandBuilder<'_> {
+        self.command().server(host, port)
+    }
+
+    /// `-H` and `-P`: Name and smart socket PORT of adb server.
+    ///
+    /// This will resolve the hostname to an IP address. See [`Tcp::from_host`] for more information.
+    pub fn server_resolved(&self, host: &str, port: u16) -> AdbResult<AdbCommandBuilder<'_>> {
+        self.command().server_resolved(host, port)
+    }
+
     /// `-L SOCKET`: Listen on given socket for adb server. Default is `tcp:localhost:5037`.
+    ///
+    /// Accepts a [`Tcp`] or any other [`AdbSocketFamily`], e.g. `localabstract:adb`.
     #[allow(non_snake_case)]
-    pub fn L_listen(&self, addr: Tcp) -> AdbCommandBuilder {
+    pub fn L_listen<A: Into<AdbSocketFamily>>(&self, addr: A) -> AdbCommandBuilder<'_> {
         self.command().L_listen(addr)
     }
 
     /// `-L SOCKET`: Listen on given socket for adb server. Default is `tcp:localhost:5037`.
     ///
-    /// This will resolve the hostname to an IP address. See [`Tcp::from_host`] for more information.
+    /// This will resolve the hostname of a `tcp` socket to an IP address.
+    /// See [`Tcp::from_host`] for more information.
     #[allow(non_snake_case)]
-    pub fn L_listen_resolved(&self, addr: &str) -> AdbResult<AdbCommandBuilder> {
+    pub fn L_listen_resolved(&self, addr: &str) -> AdbResult<AdbCommandBuilder<'_>> {
         self.command().L_listen_resolved(addr)
     }
 
     /// `--one-device SERIAL | USB`:
     /// Server will only connect to one USB device, specified by a SERIAL number or USB device address
     /// (only with `start-server` or `server nodaemon`).
-    pub fn one_device<S: ToString>(&self, device: S) -> AdbCommandBuilder {
+    pub fn one_device<S: ToString>(&self, device: S) -> AdbCommandBuilder<'_> {
         self.command().one_device(device)
     }
 
     /// `--exit-on-write-error`: Exit if stdout is closed.
-    pub fn exit_on_write_error(&self) -> AdbCommandBuilder {
+    pub fn exit_on_write_error(&self) -> AdbCommandBuilder<'_> {
         self.command().exit_on_write_error()
     }
 }
@@ -271,6 +362,76 @@ impl<'a> AdbCommandBuilder<'a> {
         })
     }
 
+    /// The serial of the `-s SERIAL` option, if any.
+    pub(crate) fn serial(&self) -> Option<&str> {
+        self.global_options.iter().find_map(|opt| match opt {
+            AdbGlobalOption::Serial(serial) => Some(serial.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Checks that the `-s SERIAL` option, if any, doesn't conflict with
+    /// `ANDROID_SERIAL` in the [`AdbEnvs`](crate::AdbEnvs) of the command,
+    /// i.e. the ones of the instance overlaid with the [overrides](Self::with_envs).
+    ///
+    /// adb lets `-s` override `ANDROID_SERIAL`, so a leftover env serial is ignored without notice.
+    /// This check is opt-in, the commands never perform it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Parse`] if both serials are set and differ.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::Adb;
+    /// let mut adb = Adb::default();
+    /// adb.envs_mut().set_android_serial("emulator-5554".to_string());
+    /// assert!(adb.s_serial("emulator-5554").check_serial_conflict().is_ok());
+    /// assert!(adb.s_serial("emulator-5556").check_serial_conflict().is_err());
+    /// ```
+    pub fn check_serial_conflict(&self) -> AdbResult<()> {
+        let envs = self.envs();
+        let Some(env_serial) = envs.android_serial() else {
+            return Ok(());
+        };
+        match self.serial() {
+            Some(serial) if serial != env_serial => Err(ParseError::with_description(
+                format!("-s {} (ANDROID_SERIAL={})", serial, env_serial),
+                "AdbGlobalOption::Serial",
+                "Conflicts with ANDROID_SERIAL",
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the sockets of the global options (i.e. `-L SOCKET`) are [valid](AdbSocketFamily::is_valid)
+    /// and [listenable](AdbSocketFamily::is_listenable).
+    ///
+    /// Called by [`AdbCommand::try_build`](crate::AdbCommand::try_build),
+    /// so that an empty `-L` argument or e.g. `-L jdwp:PID` is never emitted.
+    pub(crate) fn check_sockets(&self) -> AdbResult<()> {
+        for opt in &self.global_options {
+            if let AdbGlobalOption::Listen(socket) = opt {
+                let description = if !socket.is_valid() {
+                    "Invalid socket, e.g. `tcp` without address and port"
+                } else if !socket.is_listenable() {
+                    "not a listen socket"
+                } else {
+                    continue;
+                };
+                return Err(ParseError::with_description(
+                    format!("{:?}", socket),
+                    "AdbGlobalOption::Listen",
+                    description,
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
     /// `-t ID`: Use device with given transport id.
     ///
     /// If a `-t ID` option already exists, it will be replaced.
@@ -313,26 +474,56 @@ impl<'a> AdbCommandBuilder<'a> {
         })
     }
 
+    /// Removes the `-P` option, so that adb uses the default port [`DEFAULT_ADB_SERVER_PORT`].
+    ///
+    /// Unlike `P_port(DEFAULT_ADB_SERVER_PORT)`, no option is emitted at all.
+    pub fn default_port(self) -> Self {
+        self.remove_global_option(|opt| matches!(opt, AdbGlobalOption::Port(_)))
+    }
+
+    /// `-H` and `-P`: Name and smart socket PORT of adb server.
+    ///
+    /// Existing `-H` and `-P` options will be replaced.
+    /// If you want to resolve the hostname, use [`Self::server_resolved`] instead.
+    pub fn server<A: Into<IpAddr>>(self, host: A, port: u16) -> Self {
+        self.H_host(host).P_port(port)
+    }
+
+    /// `-H` and `-P`: Name and smart socket PORT of adb server.
+    ///
+    /// This will resolve the hostname to an IP address. See [`Tcp::from_host`] for more information.
+    pub fn server_resolved(self, host: &str, port: u16) -> AdbResult<Self> {
+        Ok(self.H_host_resolved(host)?.P_port(port))
+    }
+
     /// `-L SOCKET`: Listen on given socket for adb server. Default is `tcp:localhost:5037`.
     ///
+    /// Accepts a [`Tcp`] or any other [`AdbSocketFamily`], e.g. `localabstract:adb`.
+    /// [`AdbCommand::try_build`](crate::AdbCommand::try_build) fails if the socket can't be listened on,
+    /// see [`AdbSocketFamily::is_listenable`].
+    ///
     /// If you want to resolve the hostname, use [`Self::L_listen_resolved`] instead.
     #[allow(non_snake_case)]
-    pub fn L_listen(self, addr: Tcp) -> Self {
-        self.add_global_option(AdbGlobalOption::Listen(addr), |opt| {
+    pub fn L_listen<A: Into<AdbSocketFamily>>(self, addr: A) -> Self {
+        self.add_global_option(AdbGlobalOption::Listen(addr.into()), |opt| {
             matches!(opt, AdbGlobalOption::Listen(_))
         })
     }
 
     /// `-L SOCKET`: Listen on given socket for adb server. Default is `tcp:localhost:5037`.
     ///
-    /// This will resolve the hostname to an IP address. See [`Tcp::from_host`] for more information.
+    /// This will resolve the hostname of a `tcp` socket to an IP address.
+    /// See [`Tcp::from_host`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Parse`] if `addr` is not a socket that can be listened on.
     #[allow(non_snake_case)]
     pub fn L_listen_resolved(self, addr: &str) -> AdbResult<Self> {
-        Ok(
-            self.add_global_option(AdbGlobalOption::Listen(Tcp::from_host(addr)?), |opt| {
-                matches!(opt, AdbGlobalOption::Listen(_))
-            }),
-        )
+        Ok(self.add_global_option(
+            AdbGlobalOption::Listen(parse_listen::<true>(addr)?),
+            |opt| matches!(opt, AdbGlobalOption::Listen(_)),
+        ))
     }
 
     /// `--one-device SERIAL | USB`:
@@ -348,13 +539,93 @@ impl<'a> AdbCommandBuilder<'a> {
     pub fn exit_on_write_error(self) -> Self {
         self.add_global_option_unchecked(AdbGlobalOption::ExitOnWriteError)
     }
+
+    /// Removes all global options, including the ones preset by the [`Adb`] instance
+    /// (e.g. `-H` and `-P` of a [remote server](Adb::remote)).
+    ///
+    /// The environment variables (e.g. `ANDROID_SERIAL`) are kept,
+    /// use [`Adb::envs_mut`] to change them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::net::Ipv4Addr;
+    /// # use adbr::Adb;
+    /// let adb = Adb::remote(Ipv4Addr::new(192, 168, 0, 2), 5037).unwrap();
+    /// assert_eq!(adb.builder().global_options().count(), 2);
+    /// assert_eq!(adb.builder().without_global_options().global_options().count(), 0);
+    /// ```
+    pub fn without_global_options(self) -> Self {
+        self.remove_global_option(|_| true)
+    }
+
+    /// Returns the global options of the command, in the order they're emitted.
+    pub fn global_options(&self) -> impl Iterator<Item = &AdbGlobalOption> {
+        self.global_options.iter()
+    }
+}
+
+/// Generates valid options only, e.g. `-L` with a listenable socket,
+/// so that `Display` and `FromStr` round-trip.
+#[cfg(any(test, feature = "arbitrary"))]
+impl<'a> arbitrary::Arbitrary<'a> for AdbGlobalOption {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        use crate::socket::arbitrary_name;
+
+        Ok(match u.choose_index(10)? {
+            0 => Self::ListenAll,
+            1 => Self::Usb,
+            2 => Self::TcpIp,
+            3 => Self::Serial(arbitrary_name(u)?),
+            4 => Self::TransportId(u32::arbitrary(u)?.to_string()),
+            5 => Self::Host(IpAddr::arbitrary(u)?),
+            6 => Self::Port(u16::arbitrary(u)?),
+            7 => match AdbSocketFamily::arbitrary(u)? {
+                socket if socket.is_listenable() => Self::Listen(socket),
+                _ => Self::Listen(AdbSocketFamily::Tcp(Tcp::arbitrary(u)?)),
+            },
+            8 => Self::OneDevice(arbitrary_name(u)?),
+            _ => Self::ExitOnWriteError,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::socket::{Dev, DevRaw, Jdwp, LocalAbstract};
     use std::net::Ipv4Addr;
 
+    #[test]
+    fn test_round_trip() {
+        use arbitrary::Arbitrary;
+
+        crate::socket::tests::with_unstructured(|u| {
+            let opt = AdbGlobalOption::arbitrary(u).unwrap();
+            let s = opt.to_string();
+            assert_eq!(s.parse::<AdbGlobalOption>().unwrap(), opt, "{}", s);
+            assert_eq!(opt.args().join(" "), s);
+        });
+    }
+
+    #[test]
+    fn test_check_serial_conflict() {
+        let mut adb = Adb::default();
+        assert!(adb.s_serial("a").check_serial_conflict().is_ok());
+        adb.envs_mut().set_android_serial("a".to_string());
+        assert!(adb.command().check_serial_conflict().is_ok());
+        assert!(adb.d_usb().check_serial_conflict().is_ok());
+        assert!(adb.s_serial("a").check_serial_conflict().is_ok());
+        let err = adb.s_serial("b").check_serial_conflict().unwrap_err();
+        assert!(err.is_parse());
+
+        let mut overrides = crate::AdbEnvs::default();
+        overrides.set_android_serial("b".to_string());
+        let acb = || adb.with_envs(&overrides);
+        assert!(acb().s_serial("b").check_serial_conflict().is_ok());
+        assert!(acb().s_serial("a").check_serial_conflict().is_err());
+    }
+
     fn test_loop<T: AsRef<str>>(arr: &[(T, AdbGlobalOption)]) {
         for (s, expected) in arr {
             assert_eq!(
@@ -429,17 +700,17 @@ mod tests {
             ),
             (
                 "-L tcp:127.0.0.1",
-                AdbGlobalOption::Listen(Tcp {
+                AdbGlobalOption::Listen(AdbSocketFamily::Tcp(Tcp {
                     ip: Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))),
                     port: None,
-                }),
+                })),
             ),
             (
                 "-L tcp:127.0.0.1:1234",
-                AdbGlobalOption::Listen(Tcp {
+                AdbGlobalOption::Listen(AdbSocketFamily::Tcp(Tcp {
                     ip: Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))),
                     port: Some(1234),
-                }),
+                })),
             ),
         ];
         for (s, expected) in values {
@@ -447,6 +718,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_listen_socket() {
+        let listen = "-L localabstract:adb".parse::<AdbGlobalOption>().unwrap();
+        assert_eq!(
+            listen,
+            AdbGlobalOption::Listen(AdbSocketFamily::LocalAbstract(LocalAbstract(
+                "adb".to_string()
+            )))
+        );
+        assert_eq!(listen.to_string(), "-L localabstract:adb");
+        assert_eq!(
+            AdbGlobalOption::from_host("-L localfilesystem:/tmp/adb.sock")
+                .unwrap()
+                .to_string(),
+            "-L localfilesystem:/tmp/adb.sock"
+        );
+        assert!("-L jdwp:1234".parse::<AdbGlobalOption>().is_err());
+        assert!("-L dev:/dev/ttyS0".parse::<AdbGlobalOption>().is_err());
+
+        let adb = Adb::default();
+        assert_eq!(
+            adb.L_listen(Tcp::with_port(5038)).to_string(),
+            "adb -L tcp:5038"
+        );
+        assert_eq!(
+            adb.command()
+                .L_listen_resolved("localabstract:adb")
+                .unwrap()
+                .to_string(),
+            "adb -L localabstract:adb"
+        );
+        assert!(adb.command().L_listen_resolved("jdwp:1").is_err());
+    }
+
     #[test]
     fn test_from_str_net_host() {
         let values = [
@@ -456,15 +761,83 @@ mod tests {
             ),
             (
                 "-L tcp:localhost",
-                AdbGlobalOption::Listen(Tcp::with_ipv4(Ipv4Addr::new(127, 0, 0, 1))),
+                AdbGlobalOption::Listen(Tcp::with_ipv4(Ipv4Addr::new(127, 0, 0, 1)).into()),
             ),
             (
                 "-L tcp:localhost:1234",
-                AdbGlobalOption::Listen(Tcp::new(Ipv4Addr::new(127, 0, 0, 1).into(), 1234)),
+                AdbGlobalOption::Listen(Tcp::new(Ipv4Addr::new(127, 0, 0, 1).into(), 1234).into()),
             ),
         ];
         for (s, expected) in values {
             assert_eq!(AdbGlobalOption::from_host(s).unwrap(), expected);
         }
     }
+
+    #[test]
+    fn test_invalid_listen_socket() {
+        use crate::AdbCommand;
+
+        let adb = Adb::default();
+        let invalid = Tcp {
+            ip: None,
+            port: None,
+        };
+        let e = adb.L_listen(invalid).devices().try_build().unwrap_err();
+        assert!(e.is_parse());
+        assert!(adb
+            .L_listen(Tcp::with_port(5038))
+            .devices()
+            .try_build()
+            .is_ok());
+        for socket in [
+            AdbSocketFamily::Jdwp(Jdwp(1)),
+            AdbSocketFamily::Dev(Dev("/dev/ttyS0".to_string())),
+            AdbSocketFamily::DevRaw(DevRaw("/dev/ttyS0".to_string())),
+        ] {
+            let e = adb.L_listen(socket).devices().try_build().unwrap_err();
+            assert!(e.is_parse());
+        }
+    }
+
+    #[test]
+    fn test_without_global_options() {
+        let adb = Adb::default();
+        let acb = adb
+            .s_serial("emulator-5554")
+            .d_usb()
+            .without_global_options();
+        assert_eq!(acb.global_options().count(), 0);
+        assert_eq!(acb.to_string(), "adb");
+        let acb = acb.P_port(5038);
+        assert_eq!(
+            acb.global_options().collect::<Vec<_>>(),
+            [&AdbGlobalOption::Port(5038)]
+        );
+    }
+
+    #[test]
+    fn test_server() {
+        let adb = Adb::default();
+        let acb = adb.P_port(1234).server(Ipv4Addr::LOCALHOST, 5038);
+        assert_eq!(acb.global_options.len(), 2);
+        assert!(acb
+            .global_options
+            .contains(&AdbGlobalOption::Host(Ipv4Addr::LOCALHOST.into())));
+        assert!(acb.global_options.contains(&AdbGlobalOption::Port(5038)));
+        assert_eq!(
+            adb.server_resolved("127.0.0.1", 5038).unwrap(),
+            adb.server(Ipv4Addr::LOCALHOST, 5038)
+        );
+    }
+
+    #[test]
+    fn test_default_port() {
+        let adb = Adb::default();
+        let acb = adb.server(Ipv4Addr::LOCALHOST, 5038).default_port();
+        assert_eq!(acb.global_options.len(), 1);
+        assert!(acb
+            .global_options
+            .contains(&AdbGlobalOption::Host(Ipv4Addr::LOCALHOST.into())));
+        assert_eq!(adb.command().default_port(), adb.command());
+    }
 }
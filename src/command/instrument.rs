@@ -0,0 +1,544 @@
+//! Instrumentation and stress test commands.
+//!
+//! - `shell am instrument [OPTIONS] COMPONENT`: Start an instrumentation, see [`Adb::instrument`].
+//! - `shell monkey [OPTIONS] COUNT`: Send pseudo-random events, see [`Adb::monkey`].
+//!
+//! Both can run for a long time, use `for_each_line` (e.g. [`AdbInstrument::for_each_line`])
+//! to follow their progress.
+
+use std::collections::BTreeMap;
+use std::process::{Command, ExitStatus};
+use std::str::FromStr;
+
+use crate::command::{process, shell, AdbCommandBuilder};
+use crate::error::ParseError;
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
+
+/// A status block reported by the instrumentation, e.g. the start or the end of a test.
+///
+/// Printed as `INSTRUMENTATION_STATUS: KEY=VALUE` lines,
+/// followed by an `INSTRUMENTATION_STATUS_CODE: CODE` line.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InstrumentationStatus {
+    /// The status code, e.g. `1` for a started test, `0` for a passed test,
+    /// `-1` for an error, `-2` for a failure and `-3` for an ignored test with `AndroidJUnitRunner`.
+    pub code: i32,
+    /// The reported values, e.g. `class`, `test` or `stack`.
+    pub values: BTreeMap<String, String>,
+}
+
+impl InstrumentationStatus {
+    /// Returns the value of `key`, if reported.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Returns the `class` of the test, if reported.
+    pub fn class(&self) -> Option<&str> {
+        self.get("class")
+    }
+
+    /// Returns the `test` method name, if reported.
+    pub fn test(&self) -> Option<&str> {
+        self.get("test")
+    }
+
+    /// Returns `true` if the code reports an error (`-1`) or a failure (`-2`).
+    pub fn is_failure(&self) -> bool {
+        matches!(self.code, -1 | -2)
+    }
+}
+
+/// The result of an instrumentation, parsed from the output of `am instrument -r`.
+///
+/// Multi-line values (e.g. `stack` or `stream`) keep their line breaks.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InstrumentationResult {
+    /// The status blocks, in the order they were reported.
+    pub statuses: Vec<InstrumentationStatus>,
+    /// The values of the `INSTRUMENTATION_RESULT: KEY=VALUE` lines, e.g. `stream` or `shortMsg`.
+    pub results: BTreeMap<String, String>,
+    /// The code of the `INSTRUMENTATION_CODE: CODE` line,
+    /// `None` if the instrumentation didn't finish, e.g. it crashed or wasn't found.
+    pub code: Option<i32>,
+}
+
+impl InstrumentationResult {
+    /// Returns the status blocks reporting an error or a failure.
+    pub fn failures(&self) -> impl Iterator<Item = &InstrumentationStatus> {
+        self.statuses.iter().filter(|status| status.is_failure())
+    }
+
+    /// Returns `true` if the instrumentation finished with `Activity.RESULT_OK` (`-1`)
+    /// and no status block reported an error or a failure.
+    pub fn is_success(&self) -> bool {
+        self.code == Some(-1) && self.failures().next().is_none()
+    }
+}
+
+/// The section of the value being parsed by [`InstrumentationResult::from_str`].
+enum Section {
+    Status,
+    Result,
+}
+
+impl FromStr for InstrumentationResult {
+    type Err = AdbError;
+
+    /// Parses the output of `am instrument -r`.
+    ///
+    /// Lines not starting with `INSTRUMENTATION_` continue the previous value,
+    /// and are ignored if there's no previous value.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn parse_code(value: &str) -> AdbResult<i32> {
+            value
+                .trim()
+                .parse()
+                .map_err(|e| ParseError::with_source(value, "InstrumentationResult", e).into())
+        }
+
+        let mut result = InstrumentationResult::default();
+        let mut values = BTreeMap::new();
+        let mut current: Option<(Section, String, String)> = None;
+        for line in s.lines() {
+            if line.starts_with("INSTRUMENTATION_") {
+                match current.take() {
+                    Some((Section::Status, key, value)) => values.insert(key, value),
+                    Some((Section::Result, key, value)) => result.results.insert(key, value),
+                    None => None,
+                };
+            }
+            if let Some(code) = line.strip_prefix("INSTRUMENTATION_STATUS_CODE:") {
+                result.statuses.push(InstrumentationStatus {
+                    code: parse_code(code)?,
+                    values: std::mem::take(&mut values),
+                });
+            } else if let Some(code) = line.strip_prefix("INSTRUMENTATION_CODE:") {
+                result.code = Some(parse_code(code)?);
+            } else if let Some(pair) = line.strip_prefix("INSTRUMENTATION_STATUS:") {
+                if let Some((key, value)) = pair.trim_start().split_once('=') {
+                    current = Some((Section::Status, key.to_string(), value.to_string()));
+                }
+            } else if let Some(pair) = line.strip_prefix("INSTRUMENTATION_RESULT:") {
+                if let Some((key, value)) = pair.trim_start().split_once('=') {
+                    current = Some((Section::Result, key.to_string(), value.to_string()));
+                }
+            } else if let Some((_, _, value)) = current.as_mut() {
+                value.push('\n');
+                value.push_str(line);
+            }
+        }
+        if let Some((Section::Result, key, value)) = current {
+            result.results.insert(key, value);
+        }
+        Ok(result)
+    }
+}
+
+/// `shell am instrument [OPTIONS] COMPONENT`: Start an instrumentation.
+#[derive(Debug, Clone)]
+pub struct AdbInstrument<'a> {
+    acb: AdbCommandBuilder<'a>,
+    /// `-w`: Wait for the instrumentation to finish.
+    wait: bool,
+    /// `-r`: Print raw results.
+    raw: bool,
+    /// `-e KEY VALUE`: The arguments passed to the instrumentation, in order.
+    extras: Vec<(String, String)>,
+    /// `--no-window-animation`: Turn off window animations while running.
+    no_window_animation: bool,
+    /// `COMPONENT`: The instrumentation, i.e. `PACKAGE/RUNNER`.
+    component: String,
+}
+
+impl<'a> AdbInstrument<'a> {
+    /// `-w`: Wait for the instrumentation to finish before returning.
+    pub fn wait(mut self) -> Self {
+        self.wait = true;
+        self
+    }
+
+    /// `-r`: Print raw results, required by [`InstrumentationResult`].
+    pub fn raw(mut self) -> Self {
+        self.raw = true;
+        self
+    }
+
+    /// `-e KEY VALUE`: Pass an argument to the instrumentation, e.g. `class com.example.FooTest`.
+    ///
+    /// Can be called multiple times, the arguments are passed in order.
+    pub fn extra<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.extras.push((key.into(), value.into()));
+        self
+    }
+
+    /// `--no-window-animation`: Turn off window animations while running.
+    pub fn no_window_animation(mut self) -> Self {
+        self.no_window_animation = true;
+        self
+    }
+
+    /// Executes the command with `-w` and `-r`, returning the parsed result.
+    ///
+    /// A finished instrumentation with failed tests is still `Ok`,
+    /// check [`InstrumentationResult::is_success`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NonZeroExit`] if `adb` fails,
+    /// or [`AdbError::Parse`] if a status code can't be parsed.
+    pub fn run(self) -> AdbResult<InstrumentationResult> {
+        process::stdout_string(self.wait().raw().output()?)?.parse()
+    }
+
+    /// Executes the command with `-w` like [`AdbCommand::for_each_line`],
+    /// calling `f` on each line of the output as soon as it's printed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.instrument("com.example.test/androidx.test.runner.AndroidJUnitRunner")
+    ///     .raw()
+    ///     .for_each_line(|line| {
+    ///         if let Some(test) = line.strip_prefix("INSTRUMENTATION_STATUS: test=") {
+    ///             println!("running {}", test);
+    ///         }
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn for_each_line<F: FnMut(&str)>(self, f: F) -> AdbResult<ExitStatus> {
+        AdbCommand::for_each_line(self.wait(), f)
+    }
+}
+
+impl<'a> AdbCommand for AdbInstrument<'a> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("shell").arg("am").arg("instrument");
+        if self.wait {
+            cmd.arg("-w");
+        }
+        if self.raw {
+            cmd.arg("-r");
+        }
+        for (key, value) in &self.extras {
+            cmd.arg("-e")
+                .arg(shell::quote(key))
+                .arg(shell::quote(value));
+        }
+        if self.no_window_animation {
+            cmd.arg("--no-window-animation");
+        }
+        cmd.arg(self.component);
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(self.extras.iter().flat_map(|(key, value)| [key, value]))?;
+        process::check_nul([&self.component])
+    }
+}
+
+impl Adb {
+    /// `shell am instrument [OPTIONS] COMPONENT`: Start an instrumentation.
+    ///
+    /// `COMPONENT` is the instrumentation, i.e. `PACKAGE/RUNNER`.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell am instrument -w -r -e class com.example.FooTest com.example.test/androidx.test.runner.AndroidJUnitRunner`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let result = adb
+    ///     .instrument("com.example.test/androidx.test.runner.AndroidJUnitRunner")
+    ///     .extra("class", "com.example.FooTest")
+    ///     .run()
+    ///     .unwrap();
+    /// for failure in result.failures() {
+    ///     println!("{:?}#{:?} failed", failure.class(), failure.test());
+    /// }
+    /// ```
+    pub fn instrument<S: Into<String>>(&self, component: S) -> AdbInstrument<'_> {
+        self.command().instrument(component)
+    }
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `shell am instrument [OPTIONS] COMPONENT`: Start an instrumentation.
+    ///
+    /// See [`Adb::instrument`] for more information.
+    pub fn instrument<S: Into<String>>(self, component: S) -> AdbInstrument<'a> {
+        AdbInstrument {
+            acb: self,
+            wait: false,
+            raw: false,
+            extras: Vec::new(),
+            no_window_animation: false,
+            component: component.into(),
+        }
+    }
+}
+
+/// `shell monkey [OPTIONS] COUNT`: Send `COUNT` pseudo-random events to the device.
+#[derive(Debug, Clone)]
+pub struct AdbMonkey<'a> {
+    acb: AdbCommandBuilder<'a>,
+    /// `-p PACKAGE`: The packages allowed to be visited, all if empty.
+    packages: Vec<String>,
+    /// `-s SEED`: The seed of the pseudo-random generator.
+    seed: Option<u64>,
+    /// `--throttle MILLIS`: The delay between events.
+    throttle: Option<u32>,
+    /// `-v`: The verbosity level, i.e. the number of `-v`.
+    verbosity: u8,
+    /// `COUNT`: The number of events.
+    count: u32,
+}
+
+impl<'a> AdbMonkey<'a> {
+    /// `-p PACKAGE`: Only allow visiting `package`.
+    ///
+    /// Can be called multiple times to allow multiple packages.
+    pub fn package<S: Into<String>>(mut self, package: S) -> Self {
+        self.packages.push(package.into());
+        self
+    }
+
+    /// `-s SEED`: The seed of the pseudo-random generator, to repeat a sequence of events.
+    ///
+    /// The previous seed will be overwritten.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// `--throttle MILLIS`: Insert a delay between events.
+    ///
+    /// The previous delay will be overwritten.
+    pub fn throttle(mut self, millis: u32) -> Self {
+        self.throttle = Some(millis);
+        self
+    }
+
+    /// `-v`: Increase the verbosity, up to 3 levels.
+    pub fn v(mut self) -> Self {
+        self.verbosity = (self.verbosity + 1).min(3);
+        self
+    }
+}
+
+impl<'a> AdbCommand for AdbMonkey<'a> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("shell").arg("monkey");
+        for package in &self.packages {
+            cmd.arg("-p").arg(shell::quote(package));
+        }
+        if let Some(seed) = self.seed {
+            cmd.arg("-s").arg(seed.to_string());
+        }
+        if let Some(throttle) = self.throttle {
+            cmd.arg("--throttle").arg(throttle.to_string());
+        }
+        for _ in 0..self.verbosity {
+            cmd.arg("-v");
+        }
+        cmd.arg(self.count.to_string());
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(&self.packages)
+    }
+}
+
+impl Adb {
+    /// `shell monkey [OPTIONS] COUNT`: Send `count` pseudo-random events to the device.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell monkey -p com.example.app -s 42 --throttle 100 -v 500`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.monkey(500)
+    ///     .package("com.example.app")
+    ///     .seed(42)
+    ///     .throttle(100)
+    ///     .v()
+    ///     .checked_status()
+    ///     .expect("`adb shell monkey -p com.example.app -s 42 --throttle 100 -v 500` failed");
+    /// ```
+    pub fn monkey(&self, count: u32) -> AdbMonkey<'_> {
+        self.command().monkey(count)
+    }
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `shell monkey [OPTIONS] COUNT`: Send `count` pseudo-random events to the device.
+    ///
+    /// See [`Adb::monkey`] for more information.
+    pub fn monkey(self, count: u32) -> AdbMonkey<'a> {
+        AdbMonkey {
+            acb: self,
+            packages: Vec::new(),
+            seed: None,
+            throttle: None,
+            verbosity: 0,
+            count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_build() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.instrument("com.example.test/.Runner")),
+            ["shell", "am", "instrument", "com.example.test/.Runner"]
+        );
+        assert_eq!(
+            args(
+                adb.instrument("com.example.test/.Runner")
+                    .no_window_animation()
+                    .extra("class", "com.example.FooTest")
+                    .extra("debug", "it's on")
+                    .raw()
+                    .wait()
+            ),
+            [
+                "shell",
+                "am",
+                "instrument",
+                "-w",
+                "-r",
+                "-e",
+                "'class'",
+                "'com.example.FooTest'",
+                "-e",
+                "'debug'",
+                r"'it'\''s on'",
+                "--no-window-animation",
+                "com.example.test/.Runner"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_monkey() {
+        let adb = Adb::default();
+        assert_eq!(args(adb.monkey(100)), ["shell", "monkey", "100"]);
+        assert_eq!(
+            args(
+                adb.monkey(500)
+                    .v()
+                    .throttle(100)
+                    .package("com.example.app")
+                    .seed(42)
+                    .package("com.example.other")
+                    .v()
+                    .v()
+                    .v()
+            ),
+            [
+                "shell",
+                "monkey",
+                "-p",
+                "'com.example.app'",
+                "-p",
+                "'com.example.other'",
+                "-s",
+                "42",
+                "--throttle",
+                "100",
+                "-v",
+                "-v",
+                "-v",
+                "500"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_instrumentation_result() {
+        let output = "\
+INSTRUMENTATION_STATUS: class=com.example.FooTest
+INSTRUMENTATION_STATUS: current=1
+INSTRUMENTATION_STATUS: numtests=2
+INSTRUMENTATION_STATUS: stream=
+com.example.FooTest:
+INSTRUMENTATION_STATUS: test=testA
+INSTRUMENTATION_STATUS_CODE: 1
+INSTRUMENTATION_STATUS: class=com.example.FooTest
+INSTRUMENTATION_STATUS: test=testA
+INSTRUMENTATION_STATUS_CODE: 0
+INSTRUMENTATION_STATUS: class=com.example.FooTest
+INSTRUMENTATION_STATUS: stack=java.lang.AssertionError
+\tat com.example.FooTest.testB(FooTest.java:12)
+INSTRUMENTATION_STATUS: test=testB
+INSTRUMENTATION_STATUS_CODE: -2
+INSTRUMENTATION_RESULT: stream=
+
+Time: 0.5
+
+FAILURES!!!
+Tests run: 2,  Failures: 1
+
+INSTRUMENTATION_CODE: -1
+";
+        let result = output.parse::<InstrumentationResult>().unwrap();
+        assert_eq!(result.statuses.len(), 3);
+        assert_eq!(result.statuses[0].code, 1);
+        assert_eq!(result.statuses[0].get("numtests"), Some("2"));
+        assert_eq!(
+            result.statuses[0].get("stream"),
+            Some("\ncom.example.FooTest:")
+        );
+        assert_eq!(result.statuses[1].test(), Some("testA"));
+        let failures = result.failures().collect::<Vec<_>>();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].class(), Some("com.example.FooTest"));
+        assert_eq!(failures[0].test(), Some("testB"));
+        assert_eq!(
+            failures[0].get("stack"),
+            Some("java.lang.AssertionError\n\tat com.example.FooTest.testB(FooTest.java:12)")
+        );
+        assert!(result.results["stream"].contains("Failures: 1"));
+        assert_eq!(result.code, Some(-1));
+        assert!(!result.is_success());
+
+        let passed = "INSTRUMENTATION_RESULT: stream=OK (1 test)\nINSTRUMENTATION_CODE: -1\n";
+        let result = passed.parse::<InstrumentationResult>().unwrap();
+        assert_eq!(result.results["stream"], "OK (1 test)");
+        assert!(result.is_success());
+
+        let crashed = "INSTRUMENTATION_RESULT: shortMsg=Process crashed.\n";
+        let result = crashed.parse::<InstrumentationResult>().unwrap();
+        assert_eq!(result.results["shortMsg"], "Process crashed.");
+        assert_eq!(result.code, None);
+        assert!(!result.is_success());
+
+        assert!("INSTRUMENTATION_CODE: x"
+            .parse::<InstrumentationResult>()
+            .unwrap_err()
+            .is_parse());
+    }
+}
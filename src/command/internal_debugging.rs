@@ -23,6 +23,10 @@ impl<'a> AdbCommand for AdbStartServer<'a> {
         cmd.arg("start-server");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -39,7 +43,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb start-server` failed");
     /// ```
-    pub fn start_server(&self) -> AdbStartServer {
+    pub fn start_server(&self) -> AdbStartServer<'_> {
         AdbStartServer(self.command())
     }
 }
@@ -63,6 +67,10 @@ impl<'a> AdbCommand for AdbKillServer<'a> {
         cmd.arg("kill-server");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -79,7 +87,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb kill-server` failed");
     /// ```
-    pub fn kill_server(&self) -> AdbKillServer {
+    pub fn kill_server(&self) -> AdbKillServer<'_> {
         AdbKillServer(self.command())
     }
 }
@@ -141,6 +149,10 @@ impl<'a> AdbCommand for AdbReconnect<'a> {
         cmd.arg("reconnect");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -157,7 +169,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb reconnect` failed");
     /// ```
-    pub fn reconnect(&self) -> AdbReconnect {
+    pub fn reconnect(&self) -> AdbReconnect<'_> {
         AdbReconnect(self.command())
     }
 }
@@ -181,6 +193,10 @@ impl<'a> AdbCommand for AdbReconnectDevice<'a> {
         cmd.arg("reconnect").arg("device");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 /// `reconnect offline`: Reset offline/unauthorized devices to force reconnect.
@@ -193,4 +209,8 @@ impl<'a> AdbCommand for AdbReconnectOffline<'a> {
         cmd.arg("reconnect").arg("offline");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
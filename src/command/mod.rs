@@ -1,28 +1,110 @@
 //! The module for adb commands and command builders.
 
+/// Implements `TryFrom<&OsStr>` for types implementing `FromStr<Err = AdbError>`,
+/// failing with [`AdbError::Parse`] if the value isn't valid UTF-8.
+macro_rules! impl_try_from_os_str {
+    ($($ty:ident),* $(,)?) => {
+        $(
+            impl TryFrom<&::std::ffi::OsStr> for $ty {
+                type Error = $crate::AdbError;
+
+                fn try_from(value: &::std::ffi::OsStr) -> Result<Self, Self::Error> {
+                    match value.to_str() {
+                        Some(s) => s.parse(),
+                        None => Err($crate::error::ParseError::with_description(
+                            value.to_string_lossy(),
+                            stringify!($ty),
+                            "Invalid UTF-8",
+                        )
+                        .into()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+/// Implements `clap::ValueEnum` with the `clap` feature for the option enums,
+/// listing the variants with `all()` and naming them with [`Display`].
+macro_rules! impl_value_enum {
+    ($($ty:ident),* $(,)?) => {
+        $(
+            #[cfg(feature = "clap")]
+            impl ::clap::ValueEnum for $ty {
+                fn value_variants<'a>() -> &'a [Self] {
+                    Self::all()
+                }
+
+                fn to_possible_value(&self) -> Option<::clap::builder::PossibleValue> {
+                    Some(::clap::builder::PossibleValue::new(self.to_string()))
+                }
+            }
+        )*
+    };
+}
+
 pub mod app_installation;
+pub mod app_management;
+pub mod content;
+pub mod custom;
 pub mod debugging;
+pub mod executor;
 pub mod features;
 pub mod file_transfer;
 pub mod general;
 pub mod global_option;
+pub mod instrument;
 pub mod internal_debugging;
 pub mod networking;
+pub mod properties;
+pub mod screen;
 pub mod scripting;
 pub mod security;
 pub mod shell;
+pub mod svc;
 pub mod usb;
 
-use std::collections::HashSet;
-use std::process::{Child, Command, ExitStatus, Output};
+mod checksum;
+mod process;
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::io::Write;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::time::{Duration, Instant};
 
-use crate::{Adb, AdbResult};
+use crate::{Adb, AdbEnvs, AdbResult};
 use global_option::AdbGlobalOption;
 
-pub use file_transfer::{AdbCompressionAlgorithm, AdbSyncTarget};
-pub use scripting::{AdbRebootTarget, AdbWaitForState, AdbWaitForTransport};
+pub use app_installation::InstallLocation;
+pub use app_management::MemInfo;
+pub use debugging::{LogcatFormat, LogcatFormatModifier};
+pub use file_transfer::{AdbCompressionAlgorithm, AdbSyncTarget, PullSummary};
+pub use general::AdbDevice;
+pub use instrument::{InstrumentationResult, InstrumentationStatus};
+pub use networking::ForwardEntry;
+pub use process::TailHandle;
+#[allow(deprecated)]
+pub use scripting::AdbWaitForState;
+pub use scripting::{AdbRebootTarget, AdbRootStatus, AdbWaitForTransport, DeviceState};
+pub use shell::{AdbSuFlavor, PtyMode, ShellResult};
+pub use svc::StayOn;
+
+/// The prefixes of the messages printed to stderr by adb when it (re)starts the adb server,
+/// which are removed by [`AdbCommand::clean_output`].
+pub const DAEMON_NOISE: &[&str] = &[
+    "* daemon not running; starting now at",
+    "* daemon started successfully",
+    "adb server version",
+    "adb server is out of date.  killing...",
+];
 
 /// A trait that builds and executes adb commands.
+///
+/// With the `tracing` feature enabled, every execution shortcut runs in a `debug` span
+/// recording the argv of the command, its exit status and the elapsed time.
 pub trait AdbCommand: Sized {
     /// Builds the adb command with working directory, global options and command-specific options.
     ///
@@ -30,13 +112,177 @@ pub trait AdbCommand: Sized {
     /// instead of this method, unless you need to modify the command further.
     fn build(self) -> Command;
 
+    /// The [`AdbCommandBuilder`] of the command,
+    /// which gives access to the [`Adb`] instance and the global options.
+    ///
+    /// Returns [`None`] by default, in which case the settings of the [`Adb`] instance
+    /// (e.g. the [default timeout](Adb::default_timeout)) don't apply.
+    /// Commands built on an [`AdbCommandBuilder`] override it to honor them.
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        None
+    }
+
+    /// Checks the command-specific arguments before building, called by [`Self::try_build`].
+    ///
+    /// Accepts everything by default, commands override it to reject arguments
+    /// that adb would misinterpret, e.g. an empty socket,
+    /// or that can't be passed to a process, i.e. with an interior NUL byte.
+    fn check_args(&self) -> AdbResult<()> {
+        Ok(())
+    }
+
+    /// Builds the adb command like [`Self::build`], then validates its arguments.
+    ///
+    /// All execution shortcuts build the command with this method.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NulArgument`](crate::AdbError::NulArgument)
+    /// if a global option, a [prefix argument](AdbCommandBuilder::args_prefix)
+    /// or an argument of the command contains an interior NUL byte,
+    /// or [`AdbError::Parse`](crate::AdbError::Parse) if a socket of the global options
+    /// or the command is invalid (e.g. a [`Tcp`](crate::socket::Tcp) without address and port),
+    /// see [`Self::check_args`].
+    fn try_build(self) -> AdbResult<Command> {
+        if let Some(acb) = self.command_builder() {
+            acb.check_sockets()?;
+            acb.check_nul()?;
+        }
+        self.check_args()?;
+        Ok(self.build())
+    }
+
+    /// Prepares the command for an [`Executor`](executor::Executor),
+    /// capturing its program, arguments, environment variables, working directory
+    /// and [default timeout](Adb::default_timeout).
+    /// Anything else set by [`Self::build`], e.g. the stdio or `env_clear`, is dropped.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::try_build`].
+    fn prepare(self) -> AdbResult<executor::PreparedCommand> {
+        #[cfg(unix)]
+        let nice = self.command_builder().and_then(|acb| acb.nice);
+        let timeout = default_timeout(&self);
+        let prepared =
+            executor::PreparedCommand::from_command(&self.try_build()?).with_timeout(timeout);
+        #[cfg(unix)]
+        let prepared = prepared.with_nice(nice);
+        Ok(prepared)
+    }
+
+    /// Like [`Self::output`], but executes the command with the given `executor`.
+    ///
+    /// The [default timeout](Adb::default_timeout) applies to [`executor::Blocking`] only,
+    /// the futures of async executors are cancelled by dropping them, which kills the child process.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error without executing the command if it can't be prepared,
+    /// see [`Self::prepare`]. Errors of the execution are reported by the executor.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// use adbr::command::executor::Blocking;
+    ///
+    /// let output = adb.devices().output_with(&Blocking).unwrap().unwrap();
+    /// ```
+    ///
+    /// With the `tokio` feature:
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "tokio")]
+    /// # async fn example() -> adbr::AdbResult<()> {
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new()?;
+    /// use adbr::command::executor::Tokio;
+    ///
+    /// let output = adb.devices().output_with(&Tokio)?.await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn output_with<E: executor::Executor>(self, executor: &E) -> AdbResult<E::Output> {
+        Ok(executor.output(self.prepare()?))
+    }
+
+    /// Like [`Self::status`], but executes the command with the given `executor`.
+    ///
+    /// See [`Self::output_with`] for more information.
+    fn status_with<E: executor::Executor>(self, executor: &E) -> AdbResult<E::Status> {
+        Ok(executor.status(self.prepare()?))
+    }
+
     /// Executes the command as a child process, returning a handle to it.
     ///
     /// By default, stdin, stdout and stderr are inherited from the parent.
     ///
     /// Shortcut for [`Command::spawn`].
+    ///
+    /// The [default timeout](Adb::default_timeout) doesn't apply,
+    /// since the child process is returned to the caller without waiting for it.
     fn spawn(self) -> AdbResult<Child> {
-        self.build().spawn().map_err(Into::into)
+        process::traced(
+            self.try_build()?,
+            |_| None,
+            |mut cmd| cmd.spawn().map_err(Into::into),
+        )
+    }
+
+    /// Executes the command as a background child process, returning a handle to it.
+    ///
+    /// Unlike [`Self::spawn`], stdin, stdout and stderr are set to null,
+    /// so a long-running command (e.g. `logcat`, `track-devices`) doesn't interleave
+    /// its output with the parent's. To keep the output, use instead:
+    ///
+    /// - [`Self::spawn_tail`] to keep the most recent output for diagnostics,
+    /// - [`Self::pipe_to`] to stream stdout into a writer (blocking until the child exits),
+    /// - [`Self::spawn`] after configuring the pipes of [`Self::build`] yourself.
+    ///
+    /// The child process keeps running if the handle is dropped,
+    /// call [`Child::kill`] to stop it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let mut logcat = adb.logcat().spawn_detached().unwrap();
+    /// // ... run the test ...
+    /// logcat.kill().unwrap();
+    /// ```
+    fn spawn_detached(self) -> AdbResult<Child> {
+        process::traced(self.try_build()?, |_| None, process::spawn_detached)
+    }
+
+    /// Executes the command as a child process, keeping only the last `cap` bytes
+    /// of its stdout and stderr, e.g. for post-mortem diagnostics of a long-running command.
+    ///
+    /// Both streams are drained by background threads into bounded buffers,
+    /// which can be read with [`TailHandle::stdout_tail`] and [`TailHandle::stderr_tail`]
+    /// while the process runs. Stdin is inherited from the parent.
+    ///
+    /// The [default timeout](Adb::default_timeout) doesn't apply,
+    /// since the child process is returned to the caller without waiting for it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let mut logcat = adb.logcat().spawn_tail(64 * 1024).unwrap();
+    /// // ... run the test ...
+    /// logcat.kill().unwrap();
+    /// println!("{}", String::from_utf8_lossy(&logcat.stdout_tail()));
+    /// ```
+    fn spawn_tail(self, cap: usize) -> AdbResult<TailHandle> {
+        process::traced(
+            self.try_build()?,
+            |_| None,
+            |cmd| process::spawn_tail(cmd, cap),
+        )
     }
 
     /// Executes the command as a child process,
@@ -46,19 +292,317 @@ pub trait AdbCommand: Sized {
     /// Stdin is not inherited from the parent and any attempt by the child process
     /// to read from the stdin stream will result in the stream immediately closing.
     ///
-    /// Shortcut for [`Command::output`].
+    /// Shortcut for [`Command::output`] on the [built](Self::try_build) command,
+    /// or [`Self::output_timeout`] if the [default timeout](Adb::default_timeout) is set.
+    /// Unlike [`Self::output_with`], everything set by [`Self::build`] (e.g. `env_clear`) is kept.
     fn output(self) -> AdbResult<Output> {
-        self.build().output().map_err(Into::into)
+        match default_timeout(&self) {
+            Some(timeout) => self.output_timeout(timeout),
+            None => process::traced(
+                self.try_build()?,
+                |output| Some(output.status),
+                |mut cmd| cmd.output().map_err(Into::into),
+            ),
+        }
+    }
+
+    /// Like [`Self::output`], but the messages printed by adb when it starts the adb server
+    /// (see [`DAEMON_NOISE`]) are removed from the captured stderr.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let output = adb.shell().arg("ls /nonexistent").clean_output().unwrap();
+    /// assert!(!String::from_utf8_lossy(&output.stderr).contains("daemon"));
+    /// ```
+    fn clean_output(self) -> AdbResult<Output> {
+        self.clean_output_with(DAEMON_NOISE)
+    }
+
+    /// Like [`Self::output`], but the lines starting with any of `prefixes`
+    /// (ignoring leading whitespace) are removed from the captured stderr.
+    ///
+    /// Extend [`DAEMON_NOISE`] to filter more messages:
+    ///
+    /// ```no_run
+    /// # use adbr::command::DAEMON_NOISE;
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let mut noise = DAEMON_NOISE.to_vec();
+    /// noise.push("WARNING: linker:");
+    /// let output = adb.shell().arg("ls").clean_output_with(&noise).unwrap();
+    /// ```
+    fn clean_output_with<S: AsRef<str>>(self, prefixes: &[S]) -> AdbResult<Output> {
+        let mut output = self.output()?;
+        output.stderr = process::strip_lines(&output.stderr, prefixes);
+        Ok(output)
+    }
+
+    /// Like [`Self::output`], also returning the wall-clock duration of the execution.
+    ///
+    /// The duration is measured around [`Self::output`], so it includes spawning adb
+    /// (the `tracing` feature records the same duration as `elapsed`).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let (output, elapsed) = adb.shell_cmd(["pm", "list", "packages"]).timed_output().unwrap();
+    /// println!("`pm list packages` took {:?}", elapsed);
+    /// ```
+    fn timed_output(self) -> AdbResult<(Output, Duration)> {
+        let start = Instant::now();
+        let output = self.output()?;
+        Ok((output, start.elapsed()))
+    }
+
+    /// Like [`Self::status`], also returning the wall-clock duration of the execution.
+    ///
+    /// See [`Self::timed_output`] for more information.
+    fn timed_status(self) -> AdbResult<(ExitStatus, Duration)> {
+        let start = Instant::now();
+        let status = self.status()?;
+        Ok((status, start.elapsed()))
+    }
+
+    /// Like [`Self::output`], but the command is retried on transient failures,
+    /// e.g. `protocol fault` or `device offline` over a flaky USB connection.
+    ///
+    /// The command is executed at most `attempts` times (at least once),
+    /// sleeping `backoff` before the first retry and doubling it before each subsequent one.
+    /// A failure is retried only if it's [transient](crate::AdbError::is_transient),
+    /// deterministic failures (e.g. `INSTALL_FAILED_VERSION_DOWNGRADE`) are returned immediately.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NonZeroExit`](crate::AdbError::NonZeroExit) with the captured stderr
+    /// if the command exits with a non-zero status, or any other error of [`Self::output`],
+    /// from the last attempt.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.install("app.apk")
+    ///     .run_with_retry(3, Duration::from_millis(500))
+    ///     .expect("`adb install app.apk` failed");
+    /// ```
+    fn run_with_retry(self, attempts: u32, backoff: Duration) -> AdbResult<Output>
+    where
+        Self: Clone,
+    {
+        let mut backoff = backoff;
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .clone()
+                .output()
+                .and_then(|output| process::check(&output).map(|_| output));
+            match result {
+                Err(e) if e.is_transient() && attempt < attempts => {
+                    std::thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
     }
 
     /// Executes a command as a child process, waiting for it to finish and collecting its status.
     ///
     /// By default, stdin, stdout and stderr are inherited from the parent.
     ///
-    /// Shortcut for [`Command::status`].
+    /// Shortcut for [`Command::status`] on the [built](Self::try_build) command,
+    /// or [`Self::status_timeout`] if the [default timeout](Adb::default_timeout) is set.
+    /// Unlike [`Self::status_with`], everything set by [`Self::build`] (e.g. `env_clear`) is kept.
     fn status(self) -> AdbResult<ExitStatus> {
-        self.build().status().map_err(Into::into)
+        match default_timeout(&self) {
+            Some(timeout) => self.status_timeout(timeout),
+            None => process::traced(
+                self.try_build()?,
+                |status| Some(*status),
+                |mut cmd| cmd.status().map_err(Into::into),
+            ),
+        }
+    }
+
+    /// Like [`Self::status`], but stderr is captured instead of inherited from the parent,
+    /// so that the error message is available to the caller.
+    ///
+    /// Stdin and stdout are inherited from the parent.
+    /// The [default timeout](Adb::default_timeout) applies.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NonZeroExit`](crate::AdbError::NonZeroExit) with the captured stderr
+    /// if the command exits with a non-zero status.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand, AdbError};
+    /// # let adb = Adb::new().unwrap();
+    /// match adb.root().checked_status() {
+    ///     Ok(()) => println!("adbd is restarting as root"),
+    ///     Err(AdbError::NonZeroExit { stderr, .. }) => println!("`adb root` failed: {}", stderr),
+    ///     Err(e) => println!("{}", e),
+    /// }
+    /// ```
+    fn checked_status(self) -> AdbResult<()> {
+        let timeout = default_timeout(&self);
+        let mut cmd = self.try_build()?;
+        cmd.stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::piped());
+        process::traced(
+            cmd,
+            |_| None,
+            |cmd| process::check(&process::wait_with_output(cmd, timeout)?),
+        )
+    }
+
+    /// Executes the command as a child process, streaming its stdout into `writer`,
+    /// waiting for it to finish and collecting its status.
+    ///
+    /// Unlike [`Self::output`], stdout isn't buffered in memory,
+    /// so large data (e.g. `exec-out tar`, `exec-out screencap -p`) can be written to a file directly.
+    ///
+    /// Stdin and stderr are inherited from the parent,
+    /// so error messages of adb are still printed to the terminal.
+    /// The [default timeout](Adb::default_timeout) doesn't apply,
+    /// since a transfer may take arbitrarily long.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command can't be spawned or writing to `writer` fails,
+    /// in the latter case the child process is killed.
+    ///
+    /// # Examples
+    ///
+    /// `adb exec-out screencap -p > screen.png`
+    ///
+    /// ```no_run
+    /// # use std::fs::File;
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let file = File::create("screen.png").unwrap();
+    /// adb.custom("exec-out")
+    ///     .args(["screencap", "-p"])
+    ///     .pipe_to(file)
+    ///     .expect("`adb exec-out screencap -p` failed");
+    /// ```
+    fn pipe_to<W: Write>(self, mut writer: W) -> AdbResult<ExitStatus> {
+        process::traced(
+            self.try_build()?,
+            |status| Some(*status),
+            |cmd| process::pipe_to(cmd, &mut writer),
+        )
+    }
+
+    /// Executes the command, calling `f` on each line of its stdout as soon as it's read.
+    ///
+    /// Unlike [`Self::output`], the output isn't buffered as a whole,
+    /// which suits long-running commands (e.g. `logcat`) with unbounded output.
+    /// Lines are converted lossily, with the line endings stripped.
+    ///
+    /// Returns the exit status of adb once stdout is closed, stderr is inherited.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let status = adb
+    ///     .shell()
+    ///     .arg("logcat -d")
+    ///     .for_each_line(|line| println!("{}", line))
+    ///     .unwrap();
+    /// assert!(status.success());
+    /// ```
+    fn for_each_line<F: FnMut(&str)>(self, f: F) -> AdbResult<ExitStatus> {
+        process::traced(
+            self.try_build()?,
+            |status| Some(*status),
+            |cmd| process::for_each_line(cmd, f),
+        )
+    }
+
+    /// Like [`Self::output`], but the child process is killed if it doesn't finish within `timeout`.
+    ///
+    /// `timeout` overrides the [default timeout](Adb::default_timeout).
+    ///
+    /// Stdin is set to null, stdout and stderr are captured.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Timeout`](crate::AdbError::Timeout) if the child process is killed.
+    fn output_timeout(self, timeout: Duration) -> AdbResult<Output> {
+        process::traced(
+            self.try_build()?,
+            |output| Some(output.status),
+            |cmd| process::output_timeout(cmd, timeout),
+        )
     }
+
+    /// Like [`Self::status`], but the child process is killed if it doesn't finish within `timeout`.
+    ///
+    /// `timeout` overrides the [default timeout](Adb::default_timeout).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Timeout`](crate::AdbError::Timeout) if the child process is killed.
+    fn status_timeout(self, timeout: Duration) -> AdbResult<ExitStatus> {
+        process::traced(
+            self.try_build()?,
+            |status| Some(*status),
+            |cmd| process::status_timeout(cmd, timeout),
+        )
+    }
+
+    /// Like [`Self::output_timeout`], but the child process is killed at the given `deadline`,
+    /// so that a sequence of commands can share one overall time budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Timeout`](crate::AdbError::Timeout) if the child process is killed,
+    /// or without executing the command if `deadline` has already passed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::time::{Duration, Instant};
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let deadline = Instant::now() + Duration::from_secs(60);
+    /// adb.root().output_deadline(deadline).unwrap();
+    /// adb.remount().output_deadline(deadline).unwrap();
+    /// ```
+    fn output_deadline(self, deadline: Instant) -> AdbResult<Output> {
+        self.output_timeout(process::remaining(deadline)?)
+    }
+
+    /// Like [`Self::status_timeout`], but the child process is killed at the given `deadline`,
+    /// so that a sequence of commands can share one overall time budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Timeout`](crate::AdbError::Timeout) if the child process is killed,
+    /// or without executing the command if `deadline` has already passed.
+    fn status_deadline(self, deadline: Instant) -> AdbResult<ExitStatus> {
+        self.status_timeout(process::remaining(deadline)?)
+    }
+}
+
+/// The [default timeout](Adb::default_timeout) of `cmd`, if it has an [`AdbCommandBuilder`].
+fn default_timeout<C: AdbCommand>(cmd: &C) -> Option<Duration> {
+    cmd.command_builder()
+        .and_then(|acb| acb.adb.default_timeout)
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -69,8 +613,17 @@ pub trait AdbCommand: Sized {
 pub struct AdbCommandBuilder<'a> {
     /// The environment used in this command.
     adb: &'a Adb,
-    /// The global options of the command.
-    global_options: HashSet<AdbGlobalOption>,
+    /// The global options of the command, ordered to make the command line deterministic.
+    global_options: BTreeSet<AdbGlobalOption>,
+    /// The arguments emitted between the global options and the subcommand.
+    prefix: Vec<OsString>,
+    /// The adb environment variables overriding the ones of the [`Adb`] instance.
+    env_overrides: AdbEnvs,
+    /// The other environment variables, set after the adb environment variables.
+    extra_envs: Vec<(OsString, OsString)>,
+    /// The niceness increment of the adb process.
+    #[cfg(unix)]
+    nice: Option<i32>,
 }
 
 impl<'a> AdbCommandBuilder<'a> {
@@ -78,18 +631,32 @@ impl<'a> AdbCommandBuilder<'a> {
     pub(crate) fn new(adb: &'a Adb) -> Self {
         Self {
             adb,
-            global_options: HashSet::new(),
+            global_options: BTreeSet::new(),
+            prefix: Vec::new(),
+            env_overrides: AdbEnvs::default(),
+            extra_envs: Vec::new(),
+            #[cfg(unix)]
+            nice: None,
         }
     }
 
     /// Removes all existing global options that match the given predicate (`matches`),
     /// then adds the given global option (`opt`).
-    pub(crate) fn add_global_option<F>(mut self, opt: AdbGlobalOption, mut matches: F) -> Self
+    pub(crate) fn add_global_option<F>(self, opt: AdbGlobalOption, matches: F) -> Self
+    where
+        F: FnMut(&AdbGlobalOption) -> bool,
+    {
+        self.remove_global_option(matches)
+            .add_global_option_unchecked(opt)
+    }
+
+    /// Removes all existing global options that match the given predicate (`matches`).
+    pub(crate) fn remove_global_option<F>(mut self, mut matches: F) -> Self
     where
         F: FnMut(&AdbGlobalOption) -> bool,
     {
         self.global_options.retain(|opt| !matches(opt));
-        self.add_global_option_unchecked(opt)
+        self
     }
 
     /// Adds the given global option (`opt`) without checking for duplicates.
@@ -105,12 +672,665 @@ impl<'a> AdbCommandBuilder<'a> {
 
     /// Builds the adb command with working directory, environment variables and global options.
     fn build(self) -> Command {
-        let mut cmd = Command::new("adb");
+        let program = if self.extra_envs.iter().any(|(key, _)| key == "PATH") {
+            // the OS resolves the program in the `PATH` of the child,
+            // not in the `PATH` of this process cached by `Adb::program`
+            OsString::from("adb")
+        } else {
+            self.adb.program()
+        };
+        let mut cmd = Command::new(program);
         if let Some(working_directory) = &self.adb.working_directory {
             cmd.current_dir(working_directory);
         }
-        self.adb.envs.apply(&mut cmd);
-        cmd.args(self.global_options.iter().map(|opt| opt.to_string()));
+        self.envs().apply(&mut cmd);
+        cmd.envs(self.extra_envs);
+        cmd.args(self.global_options.iter().flat_map(AdbGlobalOption::args));
+        cmd.args(self.prefix);
+        #[cfg(unix)]
+        if let Some(increment) = self.nice {
+            process::set_nice(&mut cmd, increment);
+        }
         cmd
     }
+
+    /// Checks that the global options and the prefix arguments don't contain an interior NUL byte.
+    ///
+    /// Called by [`AdbCommand::try_build`], before the arguments are handed to [`Command`],
+    /// which would only fail at spawn with an opaque message.
+    fn check_nul(&self) -> AdbResult<()> {
+        process::check_nul(self.global_options.iter().flat_map(AdbGlobalOption::args))?;
+        process::check_nul(&self.prefix)
+    }
+
+    /// The adb environment variables of the command,
+    /// i.e. the ones of the [`Adb`] instance overlaid with the [overrides](Self::with_envs).
+    pub(crate) fn envs(&self) -> Cow<'_, AdbEnvs> {
+        if self.env_overrides.is_empty() {
+            Cow::Borrowed(&self.adb.envs)
+        } else {
+            Cow::Owned(self.adb.envs.clone().merged(&self.env_overrides))
+        }
+    }
+
+    /// Appends `args` to the arguments emitted right before the subcommand,
+    /// e.g. for an adb wrapper expecting `adb --profile work devices`.
+    ///
+    /// The command line is ordered as follows:
+    ///
+    /// 1. The program, i.e. `adb`.
+    /// 2. The global options, in their [canonical order](AdbGlobalOption),
+    ///    regardless of the order they're added in.
+    /// 3. The prefix arguments, in the order they're added in.
+    /// 4. The subcommand and its arguments.
+    ///
+    /// The prefix arguments are passed as is, they're neither validated nor deduplicated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::{Adb, AdbCommand};
+    /// let adb = Adb::default();
+    /// let cmd = adb.builder().args_prefix(["--profile", "work"]).d_usb().devices().build();
+    /// assert_eq!(
+    ///     cmd.get_args().collect::<Vec<_>>(),
+    ///     ["-d", "--profile", "work", "devices"]
+    /// );
+    /// ```
+    pub fn args_prefix<S, I>(mut self, args: I) -> Self
+    where
+        S: Into<OsString>,
+        I: IntoIterator<Item = S>,
+    {
+        self.prefix.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Overrides the adb environment variables of the [`Adb`] instance for this command only,
+    /// e.g. to debug a single command with `ADB_TRACE`.
+    ///
+    /// The environment of the command is layered as follows:
+    ///
+    /// 1. The adb environment variables of the [`Adb`] instance, see [`Adb::envs`].
+    /// 2. The values set in `envs`, overlaid like [`AdbEnvs::merge`],
+    ///    i.e. the values not set in `envs` are kept from the instance.
+    /// 3. The variables set with [`Self::env`].
+    ///
+    /// Calling it multiple times merges `envs` into the previous overrides.
+    ///
+    /// Overrides can only set variables, a variable set in the instance (e.g. `ANDROID_SERIAL`)
+    /// can't be unset for a single command, since the values not set in `envs` are kept.
+    /// Use a separate [`Adb`] instance without the variable instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::{Adb, AdbCommand, AdbEnvs};
+    /// use adbr::envs::AdbTraceEnum;
+    ///
+    /// let mut adb = Adb::default();
+    /// adb.envs_mut().set_android_serial("emulator-5554".to_string());
+    /// let mut trace = AdbEnvs::default();
+    /// trace.set_adb_trace(vec![AdbTraceEnum::All]);
+    ///
+    /// let cmd = adb.builder().with_envs(&trace).devices().build();
+    /// let envs = cmd.get_envs().collect::<Vec<_>>();
+    /// assert!(envs.contains(&("ADB_TRACE".as_ref(), Some("all".as_ref()))));
+    /// assert!(envs.contains(&("ANDROID_SERIAL".as_ref(), Some("emulator-5554".as_ref()))));
+    /// assert!(adb.envs().adb_trace().is_none());
+    /// ```
+    pub fn with_envs(mut self, envs: &AdbEnvs) -> Self {
+        self.env_overrides.merge(envs);
+        self
+    }
+
+    /// Sets the environment variable `key` to `value` for this command only,
+    /// after the adb environment variables (see [`Self::with_envs`] for the layering order).
+    ///
+    /// Any variable can be set, including the ones not modeled by [`AdbEnvs`].
+    pub fn env<K: Into<OsString>, V: Into<OsString>>(mut self, key: K, value: V) -> Self {
+        self.extra_envs.push((key.into(), value.into()));
+        self
+    }
+
+    /// Adds `increment` to the niceness of the adb process, like `nice -n INCREMENT adb ...`.
+    ///
+    /// A positive `increment` lowers the priority of the adb process,
+    /// so that background work (e.g. file transfers) doesn't starve other processes.
+    /// The niceness is changed in the child process right before executing adb,
+    /// a failure to change it (e.g. a negative `increment` without privileges) is ignored.
+    ///
+    /// The previous increment will be overwritten.
+    #[cfg(unix)]
+    pub fn nice(mut self, increment: i32) -> Self {
+        self.nice = Some(increment);
+        self
+    }
+}
+
+impl<'a> Display for AdbCommandBuilder<'a> {
+    /// Formats the program and the global options like a command line, e.g. `adb -d -P 5038`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("adb")?;
+        for opt in &self.global_options {
+            write!(f, " {}", opt)?;
+        }
+        for arg in &self.prefix {
+            write!(f, " {}", arg.to_string_lossy())?;
+        }
+        Ok(())
+    }
+}
+
+impl Adb {
+    /// Overrides the adb environment variables of this instance for a single command.
+    ///
+    /// See [`AdbCommandBuilder::with_envs`] for more information.
+    pub fn with_envs(&self, envs: &AdbEnvs) -> AdbCommandBuilder<'_> {
+        self.command().with_envs(envs)
+    }
+
+    /// Sets the environment variable `key` to `value` for a single command.
+    ///
+    /// See [`AdbCommandBuilder::env`] for more information.
+    pub fn env<K: Into<OsString>, V: Into<OsString>>(
+        &self,
+        key: K,
+        value: V,
+    ) -> AdbCommandBuilder<'_> {
+        self.command().env(key, value)
+    }
+
+    /// Adds `increment` to the niceness of the adb process, like `nice -n INCREMENT adb ...`.
+    ///
+    /// See [`AdbCommandBuilder::nice`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// `nice -n 10 adb push /path/to/local /path/to/remote`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.nice(10)
+    ///     .push(&["/path/to/local"], "/path/to/remote")
+    ///     .status()
+    ///     .expect("`nice -n 10 adb push /path/to/local /path/to/remote` failed");
+    /// ```
+    #[cfg(unix)]
+    pub fn nice(&self, increment: i32) -> AdbCommandBuilder<'_> {
+        self.command().nice(increment)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::AdbError;
+
+    /// The arguments of the built command (program excluded), converted lossily.
+    pub(crate) fn args(cmd: impl AdbCommand) -> Vec<String> {
+        cmd.build()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    /// An [`Output`] with the exit `code` of a real process, and the given stdout and stderr.
+    pub(crate) fn output(code: i32, stdout: &str, stderr: &str) -> Output {
+        #[cfg(unix)]
+        let status = {
+            use std::os::unix::process::ExitStatusExt;
+            ExitStatus::from_raw(code << 8)
+        };
+        #[cfg(windows)]
+        let status = {
+            use std::os::windows::process::ExitStatusExt;
+            ExitStatus::from_raw(code as u32)
+        };
+        Output {
+            status,
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    /// Creates an executable `adb` running `script` in a new temporary directory named after `name`,
+    /// returning the directory, to be set as the `PATH` of a command (see [`AdbCommandBuilder::env`]).
+    #[cfg(unix)]
+    pub(crate) fn fake_adb(name: &str, script: &str) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("adbr-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let adb = dir.join("adb");
+        std::fs::write(&adb, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&adb, std::fs::Permissions::from_mode(0o755)).unwrap();
+        dir
+    }
+
+    /// A command executing `script` with `sh -c` instead of adb.
+    ///
+    /// The [`AdbCommandBuilder`] (if any) only carries the settings of the [`Adb`] instance,
+    /// e.g. the default timeout, it isn't part of the command line.
+    #[derive(Debug, Clone)]
+    pub(crate) struct Sh<'a> {
+        acb: Option<AdbCommandBuilder<'a>>,
+        script: String,
+    }
+
+    impl<'a> Sh<'a> {
+        /// A command executing `script` with the settings of `acb`.
+        pub(crate) fn new<S: Into<String>>(acb: AdbCommandBuilder<'a>, script: S) -> Self {
+            Self {
+                acb: Some(acb),
+                script: script.into(),
+            }
+        }
+
+        /// A command executing `script` without an [`AdbCommandBuilder`].
+        pub(crate) fn bare<S: Into<String>>(script: S) -> Self {
+            Self {
+                acb: None,
+                script: script.into(),
+            }
+        }
+    }
+
+    impl AdbCommand for Sh<'_> {
+        fn build(self) -> Command {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(self.script);
+            cmd
+        }
+
+        fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+            self.acb.as_ref()
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_custom_build() {
+        /// A command clearing the environment in its own `build`.
+        struct Cleared(&'static str);
+
+        impl AdbCommand for Cleared {
+            fn build(self) -> Command {
+                let mut cmd = Command::new("/bin/sh");
+                cmd.arg("-c").arg(self.0).env_clear();
+                cmd
+            }
+        }
+
+        // set by cargo for the tests
+        assert!(std::env::var_os("CARGO_PKG_NAME").is_some());
+        let output = Cleared("echo \"${CARGO_PKG_NAME:-cleared}\"")
+            .output()
+            .unwrap();
+        assert_eq!(output.stdout, b"cleared\n");
+        assert!(Cleared("test -z \"$CARGO_PKG_NAME\"")
+            .status()
+            .unwrap()
+            .success());
+    }
+
+    #[test]
+    fn test_args_prefix() {
+        let adb = Adb::default();
+        let acb = adb
+            .builder()
+            .args_prefix(["--profile"])
+            .s_serial("emulator-5554")
+            .args_prefix([OsString::from("work")]);
+        assert_eq!(acb.to_string(), "adb -s emulator-5554 --profile work");
+        assert_eq!(
+            args(acb.shell().arg("id")),
+            ["-s", "emulator-5554", "--profile", "work", "shell", "id"]
+        );
+    }
+
+    #[test]
+    fn test_prepare() {
+        let adb = Adb::default();
+        let prepared = adb.command().d_usb().devices().prepare().unwrap();
+        assert_eq!(
+            std::path::Path::new(prepared.program()).file_stem(),
+            Some(std::ffi::OsStr::new("adb"))
+        );
+        assert_eq!(prepared.args(), ["-d", "devices"]);
+        assert!(prepared.envs().iter().all(|(_, value)| value.is_none()));
+        assert_eq!(
+            prepared.into_command().get_args().collect::<Vec<_>>(),
+            adb.command()
+                .d_usb()
+                .devices()
+                .build()
+                .get_args()
+                .collect::<Vec<_>>()
+        );
+        assert!(adb.shell().arg("a\0b").prepare().is_err());
+    }
+
+    #[test]
+    fn test_strip_lines() {
+        let stderr = b"* daemon not running; starting now at tcp:5037\n\
+            * daemon started successfully\n\
+            ls: /nonexistent: No such file or directory\n";
+        assert_eq!(
+            process::strip_lines(stderr, DAEMON_NOISE),
+            b"ls: /nonexistent: No such file or directory\n"
+        );
+        assert_eq!(
+            process::strip_lines(b"  * daemon started successfully\r\nerror", DAEMON_NOISE),
+            b"error"
+        );
+        assert_eq!(process::strip_lines(b"a\nb\nc", &["b"]), b"a\nc");
+        assert_eq!(process::strip_lines::<&str>(b"a\n", &[]), b"a\n");
+        assert!(process::strip_lines(b"", DAEMON_NOISE).is_empty());
+    }
+
+    #[test]
+    fn test_try_build_rejects_nul() {
+        let adb = Adb::default();
+        assert!(adb.shell().arg("echo").arg("hello").try_build().is_ok());
+        fn nul<T>(result: AdbResult<T>) -> OsString {
+            match result {
+                Err(AdbError::NulArgument(arg)) => arg,
+                result => panic!("unexpected result: {:?}", result.map(|_| ())),
+            }
+        }
+        assert_eq!(
+            nul(adb.shell().arg("echo").arg("hel\0lo").try_build()),
+            "hel\0lo"
+        );
+        assert_eq!(nul(adb.emu("kill\0").try_build()), "kill\0");
+        assert_eq!(nul(adb.install("app\0.apk").status()), "app\0.apk");
+        assert_eq!(nul(adb.push(["a\0"], "/sdcard").try_build()), "a\0");
+        assert_eq!(nul(adb.custom("x").arg("\0").try_build()), "\0");
+        assert_eq!(
+            nul(adb.builder().s_serial("emu\0").devices().try_build()),
+            "emu\0"
+        );
+        assert_eq!(
+            nul(adb.builder().args_prefix(["\0"]).devices().try_build()),
+            "\0"
+        );
+        assert_eq!(nul(adb.forward().remove("tcp:1\0").try_build()), "tcp:1\0");
+        assert_eq!(
+            AdbError::NulArgument("a\0".into()).to_string(),
+            "Argument \"a\\0\" contains an interior NUL byte"
+        );
+    }
+
+    #[test]
+    fn test_builder_template() {
+        let adb = Adb::default();
+        let template = adb.builder().d_usb();
+        assert_eq!(args(template.clone().root()), ["-d", "root"]);
+        assert_eq!(
+            args(template.clone().shell().arg("id")),
+            ["-d", "shell", "id"]
+        );
+        assert_eq!(args(template.unroot()), ["-d", "unroot"]);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        use crate::envs::AdbTraceEnum;
+
+        let envs = |cmd: Command| {
+            let mut envs = cmd
+                .get_envs()
+                .map(|(key, value)| {
+                    (
+                        key.to_string_lossy().into_owned(),
+                        value.map(|value| value.to_string_lossy().into_owned()),
+                    )
+                })
+                .collect::<Vec<_>>();
+            envs.sort();
+            envs
+        };
+        let mut adb = Adb::default();
+        adb.envs_mut().set_android_serial("a".to_string());
+        adb.envs_mut().set_adb_libusb(true);
+        let mut overrides = AdbEnvs::default();
+        overrides.set_adb_trace(vec![AdbTraceEnum::Adb]);
+        overrides.set_android_serial("b".to_string());
+
+        let overridden = envs(
+            adb.builder()
+                .with_envs(&overrides)
+                .env("ADB_TRACE", "all")
+                .env("ADBR_TEST", "1")
+                .build(),
+        );
+        let get = |name: &str| {
+            overridden
+                .iter()
+                .find(|(key, _)| key == name)
+                .and_then(|(_, value)| value.as_deref())
+        };
+        assert_eq!(get("ANDROID_SERIAL"), Some("b"));
+        assert_eq!(get("ADB_LIBUSB"), Some("1"));
+        assert_eq!(get("ADB_TRACE"), Some("all"));
+        assert_eq!(get("ADBR_TEST"), Some("1"));
+        assert_eq!(adb.envs().android_serial(), Some("a"));
+        // the overrides don't leak into other commands
+        let mut planned = adb
+            .envs()
+            .planned_env()
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect::<Vec<_>>();
+        planned.sort();
+        assert_eq!(envs(adb.builder().build()), planned);
+    }
+
+    #[cfg(feature = "clap")]
+    #[test]
+    fn test_value_enum() {
+        use clap::ValueEnum;
+
+        fn check<T: ValueEnum + Display + PartialEq + std::fmt::Debug>() {
+            for variant in T::value_variants() {
+                let name = variant.to_string();
+                assert_eq!(&T::from_str(&name, false).unwrap(), variant);
+                assert_eq!(
+                    variant.to_possible_value().unwrap().get_name(),
+                    name.as_str()
+                );
+            }
+            assert!(T::from_str("unknown", false).is_err());
+        }
+
+        check::<AdbCompressionAlgorithm>();
+        check::<DeviceState>();
+        check::<AdbWaitForTransport>();
+        check::<AdbRebootTarget>();
+        check::<AdbSyncTarget>();
+    }
+
+    #[test]
+    fn test_display() {
+        let adb = Adb::default();
+        assert_eq!(adb.builder().to_string(), "adb");
+        let acb = adb
+            .builder()
+            .exit_on_write_error()
+            .P_port(5038)
+            .s_serial("emulator-5554")
+            .d_usb();
+        assert_eq!(
+            acb.to_string(),
+            "adb -d -s emulator-5554 -P 5038 --exit-on-write-error"
+        );
+        let args = acb
+            .build()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            args,
+            [
+                "-d",
+                "-s",
+                "emulator-5554",
+                "-P",
+                "5038",
+                "--exit-on-write-error"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remote_server() {
+        use std::net::Ipv4Addr;
+
+        let mut adb = Adb::default();
+        assert!(adb.builder().global_options.is_empty());
+        adb.set_remote_server(Some((Ipv4Addr::LOCALHOST.into(), 5038)));
+        let acb = adb.builder();
+        assert_eq!(acb.global_options.len(), 2);
+        assert!(acb
+            .global_options
+            .contains(&AdbGlobalOption::Host(Ipv4Addr::LOCALHOST.into())));
+        assert!(acb.global_options.contains(&AdbGlobalOption::Port(5038)));
+        // the preset can still be overridden per command
+        let acb = adb.P_port(5039);
+        assert!(acb.global_options.contains(&AdbGlobalOption::Port(5039)));
+        assert!(!acb.global_options.contains(&AdbGlobalOption::Port(5038)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_default_timeout() {
+        use std::time::Instant;
+
+        let mut adb = Adb::default();
+        adb.with_default_timeout(Some(Duration::from_millis(100)));
+        let hang = || Sh::new(adb.command(), "sleep 10");
+        let start = Instant::now();
+        assert!(matches!(hang().status(), Err(AdbError::Timeout(_))));
+        assert!(matches!(hang().output(), Err(AdbError::Timeout(_))));
+        assert!(matches!(hang().checked_status(), Err(AdbError::Timeout(_))));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // the default timeout doesn't apply without an `AdbCommandBuilder`
+        assert!(Sh::bare("sleep 0.2").output().unwrap().status.success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timed() {
+        let adb = Adb::default();
+        let nap = || Sh::new(adb.command(), "sleep 0.05");
+        let (output, elapsed) = nap().timed_output().unwrap();
+        assert!(output.status.success());
+        assert!(elapsed >= Duration::from_millis(50));
+        let (status, elapsed) = nap().timed_status().unwrap();
+        assert!(status.success());
+        assert!(elapsed >= Duration::from_millis(50));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_with_retry() {
+        let adb = Adb::default();
+        let counter = std::env::temp_dir().join(format!("adbr-retry-{}", std::process::id()));
+        let count = || -> u32 {
+            let count = std::fs::read_to_string(&counter).unwrap();
+            std::fs::remove_file(&counter).unwrap();
+            count.trim().parse().unwrap()
+        };
+        // fails with `stderr` until it's executed `succeed_at` times, counting the executions
+        let flaky = |succeed_at: u32, stderr: &str| {
+            let script = format!(
+                "n=$(($(cat '{counter}' 2>/dev/null || echo 0) + 1)); echo $n > '{counter}'; \
+                 if [ $n -lt {succeed_at} ]; then echo '{stderr}' >&2; exit 1; fi; echo ok",
+                counter = counter.display(),
+            );
+            Sh::new(adb.command(), script)
+        };
+        let backoff = Duration::from_millis(1);
+
+        // transient failures are retried until the command succeeds
+        let output = flaky(3, "error: protocol fault")
+            .run_with_retry(3, backoff)
+            .unwrap();
+        assert_eq!(output.stdout, b"ok\n");
+        assert_eq!(count(), 3);
+
+        // the error of the last attempt is returned if all attempts fail
+        let e = flaky(5, "error: device offline")
+            .run_with_retry(3, backoff)
+            .unwrap_err();
+        assert!(e.is_transient());
+        assert_eq!(count(), 3);
+
+        // deterministic failures aren't retried
+        let e = flaky(3, "Failure [INSTALL_FAILED_VERSION_DOWNGRADE]")
+            .run_with_retry(3, backoff)
+            .unwrap_err();
+        assert!(!e.is_transient());
+        assert_eq!(count(), 1);
+
+        // at least one attempt is made
+        assert!(flaky(1, "").run_with_retry(0, backoff).is_ok());
+        assert_eq!(count(), 1);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_span() {
+        use std::fmt::Debug;
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        /// A subscriber that captures the `argv` field of new spans.
+        struct Capture(Arc<Mutex<Vec<String>>>);
+
+        impl Visit for Capture {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                if field.name() == "argv" {
+                    self.0.lock().unwrap().push(format!("{:?}", value));
+                }
+            }
+        }
+
+        impl Subscriber for Capture {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                span.record(&mut Capture(self.0.clone()));
+                Id::from_u64(1)
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, _: &Event<'_>) {}
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        let argvs = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Capture(argvs.clone()), || {
+            // adb may be missing, the span is emitted anyway
+            let _ = Adb::default().custom("version").output();
+        });
+        // the program is resolved in `PATH` if adb is installed
+        let argvs = argvs.lock().unwrap();
+        assert_eq!(argvs.len(), 1);
+        assert!(argvs[0].contains("adb"), "{}", argvs[0]);
+        assert!(argvs[0].ends_with(r#"", "version"]"#), "{}", argvs[0]);
+    }
 }
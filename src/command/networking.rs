@@ -2,18 +2,51 @@
 //!
 //! - `connect HOST[:PORT]`: Connect to a device via TCP/IP (default `PORT=5555`).
 //! - `disconnect [HOST[:PORT]]`: Disconnect from given TCP/IP device (default `PORT=5555`), or all.
+//!   See [`AdbDisconnect`] and [`AdbDisconnectAll`].
 //! - `pair HOST[:PORT] [PAIRING_CODE]`: Pair with a device for secure TCP/IP communication.
 //! - `forward --list | [--no-rebind] LOCAL_REMOTE | --remove LOCAL | --remove-all`: Forward socket connections.
 //! - `reverse --list | [--no-rebind] LOCAL_REMOTE | --remove LOCAL | --remove-all`: Reverse socket connections.
 //! - `mdns check | services`: Perform mDNS subcommands.
+//!   See [`Adb::pair_mdns`] to pair with a discovered service.
 //!
 //! See [Networking Commands](https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/master/docs/user/adb.1.md#networking).
 
 use std::ffi::OsStr;
+use std::fmt::Display;
+use std::net::{IpAddr, SocketAddr};
 use std::process::Command;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use crate::command::{process, AdbCommandBuilder};
+use crate::error::ParseError;
+use crate::socket::{AdbSocketFamily, Tcp};
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
+
+/// The `HOST` part of `addr`, where IPv6 addresses are enclosed in brackets.
+fn addr_host(addr: &SocketAddr) -> String {
+    match addr {
+        SocketAddr::V4(addr) => addr.ip().to_string(),
+        SocketAddr::V6(addr) => format!("[{}]", addr.ip()),
+    }
+}
 
-use crate::command::AdbCommandBuilder;
-use crate::{Adb, AdbCommand};
+/// Checks that the socket argument `arg` of `forward` or `reverse` isn't empty,
+/// e.g. a [`Tcp`] without address and port is displayed as an empty string.
+fn check_socket_arg(arg: &OsStr, target: &'static str) -> AdbResult<()> {
+    process::check_nul([arg])?;
+    if arg.is_empty() {
+        Err(ParseError::with_description(
+            "",
+            target,
+            "Empty socket, e.g. `Tcp` without address and port",
+        )
+        .into())
+    } else {
+        Ok(())
+    }
+}
 
 /// `connect HOST[:PORT]`: Connect to a device via TCP/IP (default `PORT=5555`).
 #[derive(Debug, Clone)]
@@ -69,6 +102,14 @@ impl<'a, S: AsRef<OsStr>> AdbCommand for AdbConnect<'a, S> {
         };
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.host])
+    }
 }
 
 impl Adb {
@@ -91,9 +132,29 @@ impl Adb {
     ///     .status()
     ///     .expect("adb connect failed");
     /// ```
-    pub fn connect<S: AsRef<OsStr>>(&self, host: S) -> AdbConnect<S> {
+    pub fn connect<S: AsRef<OsStr>>(&self, host: S) -> AdbConnect<'_, S> {
         AdbConnect::new(self.command(), host)
     }
+
+    /// `connect HOST:PORT`: Connect to a device via TCP/IP at the socket address `addr`.
+    ///
+    /// IPv6 addresses are enclosed in brackets, e.g. `[::1]:5555`.
+    ///
+    /// # Example
+    ///
+    /// `adb connect 127.0.0.1:5555`
+    ///
+    /// ```no_run
+    /// # use std::net::{Ipv4Addr, SocketAddr};
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.connect_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 5555)))
+    ///     .status()
+    ///     .expect("adb connect failed");
+    /// ```
+    pub fn connect_addr(&self, addr: SocketAddr) -> AdbConnect<'_, String> {
+        self.command().connect_addr(addr)
+    }
 }
 
 impl<'a> AdbCommandBuilder<'a> {
@@ -103,29 +164,193 @@ impl<'a> AdbCommandBuilder<'a> {
     pub fn connect<S: AsRef<OsStr>>(self, host: S) -> AdbConnect<'a, S> {
         AdbConnect::new(self, host)
     }
+
+    /// `connect HOST:PORT`: Connect to a device via TCP/IP at the socket address `addr`.
+    ///
+    /// See [`Adb::connect_addr`] for more information.
+    pub fn connect_addr(self, addr: SocketAddr) -> AdbConnect<'a, String> {
+        AdbConnect::new(self, addr_host(&addr)).port(addr.port())
+    }
+}
+
+/// The delay before connecting to adbd restarted by `tcpip`, and between two `connect` attempts.
+const WIRELESS_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The number of `connect` attempts of [`Adb::enable_wireless`].
+const WIRELESS_CONNECT_ATTEMPTS: usize = 5;
+
+/// Parses the output of `ip route`, returning the source address of the Wi-Fi interface.
+///
+/// Routes of `wlan*` interfaces are preferred, then any route with a source address.
+fn parse_ip_route(output: &str) -> Option<IpAddr> {
+    let routes = output.lines().filter_map(|line| {
+        let mut words = line.split_whitespace();
+        let mut dev = None;
+        let mut src = None;
+        while let Some(word) = words.next() {
+            match word {
+                "dev" => dev = words.next(),
+                "src" => src = words.next().and_then(|ip| ip.parse::<IpAddr>().ok()),
+                _ => {}
+            }
+        }
+        src.map(|src| (dev.unwrap_or_default(), src))
+    });
+    let mut fallback = None;
+    for (dev, src) in routes {
+        if dev.starts_with("wlan") {
+            return Some(src);
+        }
+        fallback = fallback.or(Some(src));
+    }
+    fallback
+}
+
+/// Whether the output of `connect` reports a connection, e.g. `connected to 192.168.0.2:5555`
+/// or `already connected to 192.168.0.2:5555`, since adb exits with `0` even if it fails to connect.
+fn is_connected(output: &str) -> bool {
+    output.contains("connected to")
+}
+
+impl Adb {
+    /// Switches a (USB) device to TCP/IP debugging on `port`, then connects to it over Wi-Fi.
+    ///
+    /// The steps are:
+    ///
+    /// 1. Reads the Wi-Fi IP address of the device from `shell ip route`,
+    ///    or `shell getprop dhcp.wlan0.ipaddress` as a fallback.
+    /// 2. Restarts adbd listening on `port` with `tcpip PORT`.
+    /// 3. Connects to `IP:PORT` with `connect`, retrying a few times while adbd restarts.
+    ///
+    /// Returns the socket of the connected device, which can be used as its serial (e.g. `192.168.0.2:5555`).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Parse`] if the IP address can't be found,
+    /// or [`AdbError::ConnectFailed`] if all `connect` attempts fail.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let tcp = adb.enable_wireless(5555).expect("failed to enable wireless debugging");
+    /// println!("unplug the device and use {}", tcp);
+    /// ```
+    pub fn enable_wireless(&self, port: u16) -> AdbResult<Tcp> {
+        let routes = process::stdout_string(self.shell().args(["ip", "route"]).output()?)?;
+        let ip = match parse_ip_route(&routes) {
+            Some(ip) => ip,
+            None => {
+                let prop = process::stdout_string(
+                    self.shell()
+                        .args(["getprop", "dhcp.wlan0.ipaddress"])
+                        .output()?,
+                )?;
+                let prop = prop.trim();
+                prop.parse().map_err(|_| {
+                    ParseError::with_description(prop, "IpAddr", "No Wi-Fi IP address found")
+                })?
+            }
+        };
+        process::check(&self.tcp_ip(port).output()?)?;
+
+        let addr = SocketAddr::new(ip, port);
+        let mut last = String::new();
+        for _ in 0..WIRELESS_CONNECT_ATTEMPTS {
+            thread::sleep(WIRELESS_RETRY_INTERVAL);
+            let output = self.connect_addr(addr).output()?;
+            last = String::from_utf8_lossy(&output.stdout).into_owned();
+            last.push_str(&String::from_utf8_lossy(&output.stderr));
+            if output.status.success() && is_connected(&last) {
+                return Ok(Tcp::from(addr));
+            }
+        }
+        Err(AdbError::ConnectFailed(last))
+    }
+}
+
+/// What `disconnect` reported to have disconnected, see [`AdbDisconnect::run`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Disconnected {
+    /// `disconnected everything`: All TCP/IP devices, without a count.
+    Everything,
+    /// `disconnected HOST`: The number of disconnected hosts, `0` for `no such device`.
+    Hosts(usize),
 }
 
-/// `disconnect [HOST[:PORT]]`: Disconnect from given TCP/IP device (default `PORT=5555`), or all.
+/// Parses the (combined) output of `disconnect`, whose exit status is unreliable.
+///
+/// # Errors
+///
+/// Returns [`AdbError::Parse`] if the output reports neither a disconnection nor an unknown device.
+fn parse_disconnect(output: &str) -> AdbResult<Disconnected> {
+    let lines = output.lines().map(str::trim);
+    if lines.clone().any(|line| line == "disconnected everything") {
+        return Ok(Disconnected::Everything);
+    }
+    let hosts = lines
+        .clone()
+        .filter(|line| line.starts_with("disconnected "))
+        .count();
+    if hosts > 0 || output.contains("no such device") {
+        Ok(Disconnected::Hosts(hosts))
+    } else {
+        Err(ParseError::with_description(
+            output.trim(),
+            "Disconnected",
+            "Unrecognized output of `disconnect`",
+        )
+        .into())
+    }
+}
+
+/// Runs `cmd` (a `disconnect` command), then parses its output with [`parse_disconnect`].
+///
+/// Falls back to the exit status if the output isn't recognized, e.g. no adb server.
+fn run_disconnect(cmd: impl AdbCommand) -> AdbResult<Disconnected> {
+    let output = cmd.output()?;
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let result = parse_disconnect(&text);
+    if result.is_err() {
+        process::check(&output)?;
+    }
+    result
+}
+
+/// Whether `serial` is a device connected via TCP/IP, e.g. `192.168.0.2:5555`
+/// or an mDNS service, rather than an emulator or a USB device.
+fn is_tcp_serial(serial: &str) -> bool {
+    !serial.starts_with("emulator-") && (serial.contains(':') || serial.contains("._tcp"))
+}
+
+/// `disconnect HOST[:PORT]`: Disconnect from given TCP/IP device (default `PORT=5555`).
+///
+/// Use [`AdbDisconnectAll`] to disconnect from all TCP/IP devices.
 #[derive(Debug, Clone)]
 pub struct AdbDisconnect<'a, S: AsRef<OsStr>> {
     acb: AdbCommandBuilder<'a>,
-    /// `HOST`: The optional host to disconnect from.
-    host: Option<S>,
+    /// `HOST`: The host to disconnect from.
+    host: S,
     /// `PORT`: The optional port to disconnect from, default is `5555`.
     port: Option<u16>,
 }
 
 impl<'a, S: AsRef<OsStr>> AdbDisconnect<'a, S> {
-    /// Creates a new `Disconnect` command without the host and port.
-    fn new(acb: AdbCommandBuilder<'a>) -> Self {
+    /// Creates a new `Disconnect` command without the port.
+    fn new(acb: AdbCommandBuilder<'a>, host: S) -> Self {
         Self {
             acb,
-            host: None,
+            host,
             port: None,
         }
     }
 
-    /// `HOST`: The optional host to disconnect from.
+    /// `HOST`: The host to disconnect from.
     ///
     /// The previous host will be overwritten.
     ///
@@ -136,7 +361,7 @@ impl<'a, S: AsRef<OsStr>> AdbDisconnect<'a, S> {
     pub fn host<S1: AsRef<OsStr>>(self, host: S1) -> AdbDisconnect<'a, S1> {
         AdbDisconnect {
             acb: self.acb,
-            host: Some(host),
+            host,
             port: self.port,
         }
     }
@@ -148,26 +373,61 @@ impl<'a, S: AsRef<OsStr>> AdbDisconnect<'a, S> {
         self.port = Some(port);
         self
     }
+
+    /// Executes the command, returning what adb reported to have disconnected,
+    /// i.e. [`Disconnected::Hosts`] with `1` for `disconnected HOST` or `0` for `no such device`,
+    /// or [`Disconnected::Everything`] for an empty host.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NonZeroExit`] or [`AdbError::Parse`] if the output is unrecognized.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// use adbr::command::networking::Disconnected;
+    ///
+    /// let disconnected = adb.disconnect("192.168.0.2").run().unwrap();
+    /// assert_eq!(disconnected, Disconnected::Hosts(1));
+    /// ```
+    pub fn run(self) -> AdbResult<Disconnected> {
+        run_disconnect(self)
+    }
 }
 
 impl<'a, S: AsRef<OsStr>> AdbCommand for AdbDisconnect<'a, S> {
     fn build(self) -> Command {
         let mut cmd = self.acb.build();
         cmd.arg("disconnect");
-        if let Some(host) = self.host {
-            let mut arg = host.as_ref().to_os_string();
-            if let Some(port) = self.port {
-                arg.push(":");
-                arg.push(port.to_string());
-            }
-            cmd.arg(arg);
+        let mut arg = self.host.as_ref().to_os_string();
+        if let Some(port) = self.port {
+            arg.push(":");
+            arg.push(port.to_string());
         }
+        cmd.arg(arg);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.host])
+    }
 }
 
 impl Adb {
-    /// `disconnect [HOST[:PORT]]`: Disconnect from given TCP/IP device (default `PORT=5555`), or all.
+    /// `disconnect HOST[:PORT]`: Disconnect from given TCP/IP device (default `PORT=5555`).
+    ///
+    /// To disconnect from all TCP/IP devices, use [`Adb::disconnect_all`] instead.
+    ///
+    /// # Note
+    ///
+    /// The host can be an IP address or a domain name.
+    /// However, the validity of the host is not checked.
     ///
     /// # Example
     ///
@@ -176,23 +436,125 @@ impl Adb {
     /// ```no_run
     /// # use adbr::{Adb, AdbCommand};
     /// # let adb = Adb::new().unwrap();
-    /// adb.disconnect()
-    ///     .host("localhost")  // optional
-    ///     .port(5555)         // optional
+    /// adb.disconnect("localhost")
+    ///     .port(5555) // optional
     ///     .status()
     ///     .expect("adb disconnect failed");
     /// ```
-    pub fn disconnect(&self) -> AdbDisconnect<&str> {
-        AdbDisconnect::new(self.command())
+    pub fn disconnect<S: AsRef<OsStr>>(&self, host: S) -> AdbDisconnect<'_, S> {
+        AdbDisconnect::new(self.command(), host)
+    }
+
+    /// `disconnect HOST:PORT`: Disconnect from the TCP/IP device at the socket address `addr`.
+    ///
+    /// IPv6 addresses are enclosed in brackets, e.g. `[::1]:5555`.
+    ///
+    /// # Example
+    ///
+    /// `adb disconnect 127.0.0.1:5555`
+    ///
+    /// ```no_run
+    /// # use std::net::{Ipv4Addr, SocketAddr};
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.disconnect_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 5555)))
+    ///     .status()
+    ///     .expect("adb disconnect failed");
+    /// ```
+    pub fn disconnect_addr(&self, addr: SocketAddr) -> AdbDisconnect<'_, String> {
+        self.command().disconnect_addr(addr)
     }
 }
 
 impl<'a> AdbCommandBuilder<'a> {
-    /// `disconnect [HOST[:PORT]]`: Disconnect from given TCP/IP device (default `PORT=5555`), or all.
+    /// `disconnect HOST[:PORT]`: Disconnect from given TCP/IP device (default `PORT=5555`).
     ///
     /// See [`Adb::disconnect`] for more information.
-    pub fn disconnect(self) -> AdbDisconnect<'a, &'a str> {
-        AdbDisconnect::new(self)
+    pub fn disconnect<S: AsRef<OsStr>>(self, host: S) -> AdbDisconnect<'a, S> {
+        AdbDisconnect::new(self, host)
+    }
+
+    /// `disconnect HOST:PORT`: Disconnect from the TCP/IP device at the socket address `addr`.
+    ///
+    /// See [`Adb::disconnect_addr`] for more information.
+    pub fn disconnect_addr(self, addr: SocketAddr) -> AdbDisconnect<'a, String> {
+        AdbDisconnect::new(self, addr_host(&addr)).port(addr.port())
+    }
+}
+
+/// `disconnect`: Disconnect from all TCP/IP devices.
+#[derive(Debug, Clone)]
+pub struct AdbDisconnectAll<'a>(AdbCommandBuilder<'a>);
+
+impl<'a> AdbDisconnectAll<'a> {
+    /// Executes the command, verifying that it printed `disconnected everything`,
+    /// and returns the number of disconnected TCP/IP devices.
+    ///
+    /// adb doesn't print the count, so the TCP/IP devices are listed with `devices` beforehand.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NonZeroExit`] or [`AdbError::Parse`] if the output is unrecognized.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let count = adb.disconnect_all().run().unwrap();
+    /// println!("disconnected {} device(s)", count);
+    /// ```
+    pub fn run(self) -> AdbResult<usize> {
+        let devices = self.0.clone().devices().run()?;
+        match run_disconnect(self)? {
+            Disconnected::Everything => Ok(devices
+                .iter()
+                .filter(|device| is_tcp_serial(&device.serial))
+                .count()),
+            Disconnected::Hosts(hosts) => Ok(hosts),
+        }
+    }
+}
+
+impl<'a> AdbCommand for AdbDisconnectAll<'a> {
+    fn build(self) -> Command {
+        let mut cmd = self.0.build();
+        cmd.arg("disconnect");
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
+}
+
+impl Adb {
+    /// `disconnect`: Disconnect from all TCP/IP devices.
+    ///
+    /// To disconnect from a single TCP/IP device, use [`Adb::disconnect`] instead.
+    ///
+    /// # Example
+    ///
+    /// `adb disconnect`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.disconnect_all()
+    ///     .status()
+    ///     .expect("adb disconnect failed");
+    /// ```
+    pub fn disconnect_all(&self) -> AdbDisconnectAll<'_> {
+        AdbDisconnectAll(self.command())
+    }
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `disconnect`: Disconnect from all TCP/IP devices.
+    ///
+    /// See [`Adb::disconnect_all`] for more information.
+    pub fn disconnect_all(self) -> AdbDisconnectAll<'a> {
+        AdbDisconnectAll(self)
     }
 }
 
@@ -275,6 +637,15 @@ where
         }
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.host])?;
+        process::check_nul(&self.pairing_code)
+    }
 }
 
 impl Adb {
@@ -298,7 +669,7 @@ impl Adb {
     ///     .status()
     ///     .expect("adb pair failed");
     /// ```
-    pub fn pair<S: AsRef<OsStr>>(&self, host: S) -> AdbPair<S, S> {
+    pub fn pair<S: AsRef<OsStr>>(&self, host: S) -> AdbPair<'_, S, S> {
         AdbPair::new(self.command(), host)
     }
 }
@@ -440,7 +811,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb forward --remove-all` failed");
     /// ```
-    pub fn forward(&self) -> AdbForward {
+    pub fn forward(&self) -> AdbForward<'_> {
         AdbForward(self.command())
     }
 }
@@ -466,18 +837,328 @@ impl<'a> AdbCommandBuilder<'a> {
     }
 }
 
+/// Parses the output of `forward tcp:LOCAL_PORT REMOTE`, returning the forwarded local port.
+///
+/// adb prints the allocated port only if `local_port` is `0`.
+fn parse_forwarded_port(stdout: &str, local_port: u16) -> AdbResult<u16> {
+    if local_port != 0 {
+        return Ok(local_port);
+    }
+    let port = stdout.trim();
+    port.parse()
+        .map_err(|_| ParseError::with_description(port, "u16", "Invalid allocated port").into())
+}
+
+impl Adb {
+    /// `forward tcp:LOCAL_PORT tcp:REMOTE_PORT`: Forward a local tcp port to a remote tcp port.
+    ///
+    /// If `local_port` is `0`, adb picks any open port.
+    /// Returns the forwarded local port, i.e. the picked port or `local_port` itself.
+    ///
+    /// See [`AdbSocketFamily::tcp_pair`] to use the same port on both sides with [`Adb::forward`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let port = adb.forward_tcp(0, 8080).expect("`adb forward tcp:0 tcp:8080` failed");
+    /// println!("device port 8080 is reachable at localhost:{}", port);
+    /// ```
+    pub fn forward_tcp(&self, local_port: u16, remote_port: u16) -> AdbResult<u16> {
+        let local = AdbSocketFamily::from(Tcp::with_port(local_port)).to_string();
+        let remote = AdbSocketFamily::from(Tcp::with_port(remote_port)).to_string();
+        let stdout = process::stdout_string(self.forward().arg(local, remote).output()?)?;
+        parse_forwarded_port(&stdout, local_port)
+    }
+
+    /// `forward LOCAL REMOTE`: Forward socket connection, parsing the sockets leniently.
+    ///
+    /// A bare port number means `tcp:PORT`, e.g. `forward_lenient("1234", "5678")`
+    /// is `forward tcp:1234 tcp:5678`. See [`AdbSocketFamily::parse_forward_spec`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Parse`] if either socket is invalid, without executing the command.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.forward_lenient("1234", "localabstract:foo")
+    ///     .unwrap()
+    ///     .checked_status()
+    ///     .expect("`adb forward tcp:1234 localabstract:foo` failed");
+    /// ```
+    pub fn forward_lenient(
+        &self,
+        local: &str,
+        remote: &str,
+    ) -> AdbResult<AdbForwardNoRebind<'_, String, String>> {
+        self.command().forward_lenient(local, remote)
+    }
+
+    /// `reverse REMOTE LOCAL`: Reverse socket connection, parsing the sockets leniently.
+    ///
+    /// A bare port number means `tcp:PORT`, see [`Adb::forward_lenient`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Parse`] if either socket is invalid, without executing the command.
+    pub fn reverse_lenient(
+        &self,
+        remote: &str,
+        local: &str,
+    ) -> AdbResult<AdbReverseNoRebind<'_, String, String>> {
+        self.command().reverse_lenient(remote, local)
+    }
+
+    /// `-s SERIAL forward --remove LOCAL`: Remove the forward socket connection of `entry`.
+    ///
+    /// `forward --list` lists the connections of every device,
+    /// so the connection is removed on the device of `entry.serial`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// for entry in adb.forward().list().run().unwrap() {
+    ///     adb.remove_forward(&entry).checked_status().unwrap();
+    /// }
+    /// ```
+    pub fn remove_forward(&self, entry: &ForwardEntry) -> AdbForwardRemove<'_, String> {
+        self.command().remove_forward(entry)
+    }
+
+    /// `reverse --remove REMOTE`: Remove the reverse socket connection of `entry`.
+    ///
+    /// Unlike `forward --list`, `reverse --list` only lists the connections of the selected device,
+    /// and `entry.serial` is the name of the transport on the device, not a device serial.
+    /// Select the same device as the one listed, e.g. with [`Adb::s_serial`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// for entry in adb.reverse().list().run().unwrap() {
+    ///     adb.remove_reverse(&entry).checked_status().unwrap();
+    /// }
+    /// ```
+    pub fn remove_reverse(&self, entry: &ForwardEntry) -> AdbReverseRemove<'_, String> {
+        self.command().remove_reverse(entry)
+    }
+
+    /// Removes the forward socket connections matching `pred`,
+    /// by `forward --list` then `-s SERIAL forward --remove LOCAL` for each match,
+    /// so each connection is removed on its own device.
+    ///
+    /// Returns the number of removed connections.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of listing or removing, the connections removed before it stay removed.
+    ///
+    /// # Examples
+    ///
+    /// Remove all forwards to the device port `8080`:
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let removed = adb
+    ///     .forward_remove_where(|entry| entry.remote.tcp_port() == Some(8080))
+    ///     .unwrap();
+    /// println!("{} forwards removed", removed);
+    /// ```
+    pub fn forward_remove_where<F: Fn(&ForwardEntry) -> bool>(&self, pred: F) -> AdbResult<usize> {
+        remove_where(self.forward().list().run()?, pred, |entry| {
+            self.remove_forward(entry).checked_status()
+        })
+    }
+
+    /// Removes the reverse socket connections matching `pred` on the device with `serial`,
+    /// by `-s SERIAL reverse --list` then `-s SERIAL reverse --remove REMOTE` for each match.
+    ///
+    /// Returns the number of removed connections.
+    /// See [`ForwardEntry`] for the meaning of `local` and `remote` in `reverse`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of listing or removing, the connections removed before it stay removed.
+    ///
+    /// # Examples
+    ///
+    /// Remove all reverses from the device port `8080` of `emulator-5554`:
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let removed = adb
+    ///     .reverse_remove_where("emulator-5554", |entry| entry.local.tcp_port() == Some(8080))
+    ///     .unwrap();
+    /// println!("{} reverses removed", removed);
+    /// ```
+    pub fn reverse_remove_where<F: Fn(&ForwardEntry) -> bool>(
+        &self,
+        serial: &str,
+        pred: F,
+    ) -> AdbResult<usize> {
+        remove_where(
+            self.s_serial(serial).reverse().list().run()?,
+            pred,
+            |entry| self.s_serial(serial).remove_reverse(entry).checked_status(),
+        )
+    }
+}
+
+/// Calls `remove` on each of `entries` matching `pred`, returning the number of removed entries.
+fn remove_where<P, R>(entries: Vec<ForwardEntry>, pred: P, mut remove: R) -> AdbResult<usize>
+where
+    P: Fn(&ForwardEntry) -> bool,
+    R: FnMut(&ForwardEntry) -> AdbResult<()>,
+{
+    let mut removed = 0;
+    for entry in entries.iter().filter(|entry| pred(entry)) {
+        remove(entry)?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `forward LOCAL REMOTE`: Forward socket connection, parsing the sockets leniently.
+    ///
+    /// See [`Adb::forward_lenient`] for more information.
+    pub fn forward_lenient(
+        self,
+        local: &str,
+        remote: &str,
+    ) -> AdbResult<AdbForwardNoRebind<'a, String, String>> {
+        let local = AdbSocketFamily::parse_forward_spec(local)?.to_string();
+        let remote = AdbSocketFamily::parse_forward_spec(remote)?.to_string();
+        Ok(self.forward().arg(local, remote))
+    }
+
+    /// `reverse REMOTE LOCAL`: Reverse socket connection, parsing the sockets leniently.
+    ///
+    /// See [`Adb::reverse_lenient`] for more information.
+    pub fn reverse_lenient(
+        self,
+        remote: &str,
+        local: &str,
+    ) -> AdbResult<AdbReverseNoRebind<'a, String, String>> {
+        let remote = AdbSocketFamily::parse_forward_spec(remote)?.to_string();
+        let local = AdbSocketFamily::parse_forward_spec(local)?.to_string();
+        Ok(self.reverse().arg(remote, local))
+    }
+
+    /// `-s SERIAL forward --remove LOCAL`: Remove the forward socket connection of `entry`.
+    ///
+    /// See [`Adb::remove_forward`] for more information.
+    pub fn remove_forward(self, entry: &ForwardEntry) -> AdbForwardRemove<'a, String> {
+        self.s_serial(&entry.serial)
+            .forward()
+            .remove(entry.local.to_string())
+    }
+
+    /// `reverse --remove REMOTE`: Remove the reverse socket connection of `entry`.
+    ///
+    /// See [`Adb::remove_reverse`] for more information.
+    pub fn remove_reverse(self, entry: &ForwardEntry) -> AdbReverseRemove<'a, String> {
+        self.reverse().remove(entry.local.to_string())
+    }
+}
+
+/// A socket connection listed by `forward --list` or `reverse --list`.
+///
+/// `local` is the listening socket and `remote` is the socket connected to,
+/// i.e. for `reverse`, `local` is the `REMOTE` socket on the device.
+///
+/// # Examples
+///
+/// ```
+/// # use adbr::command::ForwardEntry;
+/// # use adbr::socket::{AdbSocketFamily, Tcp};
+/// let entry = "emulator-5554 tcp:8080 tcp:80".parse::<ForwardEntry>().unwrap();
+/// assert_eq!(entry.serial, "emulator-5554");
+/// assert_eq!(entry.local, AdbSocketFamily::Tcp(Tcp::with_port(8080)));
+/// assert_eq!(entry.to_string(), "emulator-5554 tcp:8080 tcp:80");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardEntry {
+    /// The serial of the device, or the transport name for `reverse`.
+    pub serial: String,
+    /// The listening socket.
+    pub local: AdbSocketFamily,
+    /// The socket connected to.
+    pub remote: AdbSocketFamily,
+}
+
+impl Display for ForwardEntry {
+    /// Formats the entry like a line of `forward --list`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.serial, self.local, self.remote)
+    }
+}
+
+impl FromStr for ForwardEntry {
+    type Err = AdbError;
+
+    /// Parses a line of `forward --list` or `reverse --list`, e.g. `emulator-5554 tcp:8080 tcp:80`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.split_whitespace().collect::<Vec<_>>();
+        let [serial, local, remote] = tokens[..] else {
+            return Err(ParseError::with_description(
+                s,
+                "ForwardEntry",
+                "Expected `SERIAL LOCAL REMOTE`",
+            )
+            .into());
+        };
+        Ok(ForwardEntry {
+            serial: serial.to_string(),
+            local: local.parse()?,
+            remote: remote.parse()?,
+        })
+    }
+}
+
+/// Parses the output of `forward --list` or `reverse --list`.
+fn parse_forward_list(output: &str) -> AdbResult<Vec<ForwardEntry>> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
 /// A subcommand of `forward`.
 ///
 /// `forward --list`: List all forward socket connections.
 #[derive(Debug, Clone)]
 pub struct AdbForwardList<'a>(AdbCommandBuilder<'a>);
 
+impl<'a> AdbForwardList<'a> {
+    /// Executes the command and parses the listed connections.
+    pub fn run(self) -> AdbResult<Vec<ForwardEntry>> {
+        parse_forward_list(&process::stdout_string(self.output()?)?)
+    }
+}
+
 impl<'a> AdbCommand for AdbForwardList<'a> {
     fn build(self) -> Command {
         let mut cmd = self.0.build();
         cmd.arg("forward").arg("--list");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 /// A subcommand of `forward`.
@@ -553,6 +1234,15 @@ where
         cmd.arg(self.local).arg(self.remote);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        check_socket_arg(self.local.as_ref(), "LOCAL")?;
+        check_socket_arg(self.remote.as_ref(), "REMOTE")
+    }
 }
 
 /// A subcommand of `forward`.
@@ -584,6 +1274,14 @@ impl<'a, S: AsRef<OsStr>> AdbCommand for AdbForwardRemove<'a, S> {
         cmd.arg("forward").arg("--remove").arg(self.local);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        check_socket_arg(self.local.as_ref(), "LOCAL")
+    }
 }
 
 /// A subcommand of `forward`.
@@ -598,6 +1296,10 @@ impl<'a> AdbCommand for AdbForwardRemoveAll<'a> {
         cmd.arg("forward").arg("--remove-all");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 /// `reverse --list | [--no-rebind] LOCAL REMOTE | --remove LOCAL | --remove-all`
@@ -720,7 +1422,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb reverse --remove-all` failed");
     /// ```
-    pub fn reverse(&self) -> AdbReverse {
+    pub fn reverse(&self) -> AdbReverse<'_> {
         AdbReverse(self.command())
     }
 }
@@ -748,12 +1450,25 @@ impl<'a> AdbCommandBuilder<'a> {
 #[derive(Debug, Clone)]
 pub struct AdbReverseList<'a>(AdbCommandBuilder<'a>);
 
+impl<'a> AdbReverseList<'a> {
+    /// Executes the command and parses the listed connections.
+    ///
+    /// See [`ForwardEntry`] for the meaning of `local` and `remote` in `reverse`.
+    pub fn run(self) -> AdbResult<Vec<ForwardEntry>> {
+        parse_forward_list(&process::stdout_string(self.output()?)?)
+    }
+}
+
 impl<'a> AdbCommand for AdbReverseList<'a> {
     fn build(self) -> Command {
         let mut cmd = self.0.build();
         cmd.arg("reverse").arg("--list");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 /// A subcommand of `reverse`.
@@ -829,6 +1544,15 @@ where
         cmd.arg(self.remote).arg(self.local);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        check_socket_arg(self.remote.as_ref(), "REMOTE")?;
+        check_socket_arg(self.local.as_ref(), "LOCAL")
+    }
 }
 
 /// A subcommand of `reverse`.
@@ -860,6 +1584,14 @@ impl<'a, S: AsRef<OsStr>> AdbCommand for AdbReverseRemove<'a, S> {
         cmd.arg("reverse").arg("--remove").arg(self.remote);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        check_socket_arg(self.remote.as_ref(), "REMOTE")
+    }
 }
 
 /// A subcommand of `reverse`.
@@ -874,6 +1606,10 @@ impl<'a> AdbCommand for AdbReverseRemoveAll<'a> {
         cmd.arg("reverse").arg("--remove-all");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 /// `mdns check | services`: Perform mDNS subcommands.
@@ -922,7 +1658,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb mdns services` failed");
     /// ```
-    pub fn mdns(&self) -> AdbMdns {
+    pub fn mdns(&self) -> AdbMdns<'_> {
         AdbMdns(self.command())
     }
 }
@@ -950,6 +1686,166 @@ impl<'a> AdbCommand for AdbMdnsCheck<'a> {
         cmd.arg("mdns").arg("check");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
+}
+
+/// A service listed by `mdns services`.
+///
+/// # Examples
+///
+/// ```
+/// # use adbr::command::networking::MdnsService;
+/// let service = "adb-R5CR10ABCDE-Xyz123\t_adb-tls-pairing._tcp\t192.168.0.2:37123"
+///     .parse::<MdnsService>()
+///     .unwrap();
+/// assert!(service.is_pairing());
+/// assert_eq!(service.address.port(), 37123);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdnsService {
+    /// The instance name of the service, e.g. `adb-R5CR10ABCDE-Xyz123`.
+    pub name: String,
+    /// The type of the service, e.g. `_adb-tls-pairing._tcp`.
+    pub service_type: String,
+    /// The address of the service.
+    pub address: SocketAddr,
+}
+
+impl MdnsService {
+    /// Whether the service accepts pairing (`_adb-tls-pairing`), i.e. for [`Adb::pair`].
+    pub fn is_pairing(&self) -> bool {
+        self.service_type.starts_with("_adb-tls-pairing.")
+    }
+
+    /// Whether the service accepts connections (`_adb-tls-connect` or `_adb`), i.e. for [`Adb::connect`].
+    pub fn is_connect(&self) -> bool {
+        self.service_type.starts_with("_adb-tls-connect.") || self.service_type.starts_with("_adb.")
+    }
+}
+
+impl Display for MdnsService {
+    /// Formats the service like a line of `mdns services`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}\t{}", self.name, self.service_type, self.address)
+    }
+}
+
+impl FromStr for MdnsService {
+    type Err = AdbError;
+
+    /// Parses a line of `mdns services`, e.g. `adb-SERIAL-ID\t_adb-tls-connect._tcp\t192.168.0.2:37017`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.split_whitespace().collect::<Vec<_>>();
+        let [name, service_type, address] = tokens[..] else {
+            return Err(ParseError::with_description(
+                s,
+                "MdnsService",
+                "Expected `NAME TYPE ADDRESS`",
+            )
+            .into());
+        };
+        Ok(MdnsService {
+            name: name.to_string(),
+            service_type: service_type.to_string(),
+            address: address
+                .parse()
+                .map_err(|e| ParseError::with_source(address, "SocketAddr", e))?,
+        })
+    }
+}
+
+/// Parses the output of `mdns services`, skipping the `List of discovered mdns services` header.
+fn parse_mdns_services(output: &str) -> AdbResult<Vec<MdnsService>> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("List of"))
+        .map(str::parse)
+        .collect()
+}
+
+/// Finds the pairing service named `name`, or the first one if `name` is `None`.
+fn find_pairing_service<'s>(
+    services: &'s [MdnsService],
+    name: Option<&str>,
+) -> Option<&'s MdnsService> {
+    services
+        .iter()
+        .filter(|service| service.is_pairing())
+        .find(|service| name.is_none_or(|name| service.name == name))
+}
+
+/// A device paired by [`Adb::pair_mdns`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairedDevice {
+    /// The pairing service the device was paired with.
+    pub service: MdnsService,
+    /// The GUID of the device reported by `pair`, e.g. `adb-R5CR10ABCDE-Xyz123`.
+    pub guid: Option<String>,
+}
+
+/// Parses the GUID of `Successfully paired to ADDRESS [guid=GUID]`,
+/// or returns `None` if the output doesn't report a pairing.
+fn parse_paired(output: &str) -> Option<Option<String>> {
+    let line = output
+        .lines()
+        .find(|line| line.contains("Successfully paired to"))?;
+    let guid = line
+        .split_once("[guid=")
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .map(|(guid, _)| guid.to_string());
+    Some(guid)
+}
+
+impl Adb {
+    /// Pairs with a device discovered by `mdns services`,
+    /// i.e. `pair ADDRESS PAIRING_CODE` with an `_adb-tls-pairing` service.
+    ///
+    /// If multiple pairing services are discovered, the one named `name` is used,
+    /// or the first one if `name` is `None`.
+    ///
+    /// Returns the paired service and the GUID of the device, which is also the name of
+    /// its `_adb-tls-connect` service, so it can be found by [`MdnsService::is_connect`] afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::PairFailed`] if no matching pairing service is discovered,
+    /// or `pair` doesn't report a success.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let paired = adb.pair_mdns(None, "123456").unwrap();
+    /// println!("paired with {}", paired.service.address);
+    /// ```
+    pub fn pair_mdns(&self, name: Option<&str>, pairing_code: &str) -> AdbResult<PairedDevice> {
+        let services = self.mdns().services().run()?;
+        let service = find_pairing_service(&services, name).ok_or_else(|| {
+            AdbError::PairFailed(match name {
+                Some(name) => format!("no mDNS pairing service named `{}`", name),
+                None => "no mDNS pairing service discovered".to_string(),
+            })
+        })?;
+        let output = self
+            .pair(addr_host(&service.address))
+            .port(service.address.port())
+            .pairing_code(pairing_code)
+            .output()?;
+        let mut out = String::from_utf8_lossy(&output.stdout).into_owned();
+        out.push_str(&String::from_utf8_lossy(&output.stderr));
+        match parse_paired(&out) {
+            Some(guid) if output.status.success() => Ok(PairedDevice {
+                service: service.clone(),
+                guid,
+            }),
+            _ => Err(AdbError::PairFailed(out)),
+        }
+    }
 }
 
 /// A subcommand of `mdns`.
@@ -958,10 +1854,291 @@ impl<'a> AdbCommand for AdbMdnsCheck<'a> {
 #[derive(Debug, Clone)]
 pub struct AdbMdnsServices<'a>(AdbCommandBuilder<'a>);
 
+impl<'a> AdbMdnsServices<'a> {
+    /// Executes the command and parses the discovered services.
+    pub fn run(self) -> AdbResult<Vec<MdnsService>> {
+        parse_mdns_services(&process::stdout_string(self.output()?)?)
+    }
+}
+
 impl<'a> AdbCommand for AdbMdnsServices<'a> {
     fn build(self) -> Command {
         let mut cmd = self.0.build();
         cmd.arg("mdns").arg("services");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_mdns_services() {
+        let services = parse_mdns_services(
+            "List of discovered mdns services\n\
+             adb-R5CR10ABCDE-Xyz123\t_adb-tls-connect._tcp\t192.168.0.2:37017\n\
+             adb-R5CR10ABCDE-Xyz123\t_adb-tls-pairing._tcp\t192.168.0.2:40123\n\
+             adb-0A1B2C3D-Abc456\t_adb-tls-pairing._tcp\t192.168.0.3:41234\n",
+        )
+        .unwrap();
+        assert_eq!(services.len(), 3);
+        assert!(services[0].is_connect() && !services[0].is_pairing());
+        assert_eq!(
+            services[1].to_string().parse::<MdnsService>().unwrap(),
+            services[1]
+        );
+        assert_eq!(find_pairing_service(&services, None), Some(&services[1]));
+        assert_eq!(
+            find_pairing_service(&services, Some("adb-0A1B2C3D-Abc456")),
+            Some(&services[2])
+        );
+        assert_eq!(find_pairing_service(&services, Some("adb-none")), None);
+        assert!(parse_mdns_services("List of discovered mdns services\n")
+            .unwrap()
+            .is_empty());
+        assert!(parse_mdns_services("adb-x _adb._tcp nowhere").is_err());
+    }
+
+    #[test]
+    fn test_parse_paired() {
+        assert_eq!(
+            parse_paired(
+                "Successfully paired to 192.168.0.2:40123 [guid=adb-R5CR10ABCDE-Xyz123]\n"
+            ),
+            Some(Some("adb-R5CR10ABCDE-Xyz123".to_string()))
+        );
+        assert_eq!(
+            parse_paired("Successfully paired to 192.168.0.2:40123"),
+            Some(None)
+        );
+        assert_eq!(
+            parse_paired("Failed: Wrong password or connection was dropped."),
+            None
+        );
+    }
+
+    #[test]
+    fn test_forward_entry() {
+        let adb = Adb::default();
+        let entries = parse_forward_list(
+            "emulator-5554 tcp:8080 tcp:80\n\
+             emulator-5554 localabstract:foo jdwp:1234\n",
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[1].to_string().parse::<ForwardEntry>().unwrap(),
+            entries[1]
+        );
+        assert_eq!(
+            args(adb.remove_forward(&entries[0])),
+            ["-s", "emulator-5554", "forward", "--remove", "tcp:8080"]
+        );
+        // the serial of the entry overrides the selected device
+        assert_eq!(
+            args(adb.s_serial("emulator-5556").remove_forward(&entries[0])),
+            ["-s", "emulator-5554", "forward", "--remove", "tcp:8080"]
+        );
+        assert_eq!(
+            args(adb.remove_reverse(&entries[1])),
+            ["reverse", "--remove", "localabstract:foo"]
+        );
+        assert!(parse_forward_list("").unwrap().is_empty());
+        assert!("emulator-5554 tcp:8080".parse::<ForwardEntry>().is_err());
+        assert!("emulator-5554 tcp:x tcp:80"
+            .parse::<ForwardEntry>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_empty_socket() {
+        let adb = Adb::default();
+        let empty = Tcp {
+            ip: None,
+            port: None,
+        }
+        .to_string();
+        assert!(adb
+            .forward()
+            .arg(&empty, "tcp:5678")
+            .try_build()
+            .unwrap_err()
+            .is_parse());
+        assert!(adb
+            .reverse()
+            .no_rebind("tcp:1234", &empty)
+            .try_build()
+            .unwrap_err()
+            .is_parse());
+        assert!(adb
+            .forward()
+            .remove(&empty)
+            .try_build()
+            .unwrap_err()
+            .is_parse());
+        assert!(adb
+            .reverse()
+            .remove(&empty)
+            .try_build()
+            .unwrap_err()
+            .is_parse());
+        assert!(adb
+            .forward()
+            .arg("tcp:1234", "tcp:5678")
+            .try_build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_lenient() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.forward_lenient("1234", "tcp:5678").unwrap()),
+            ["forward", "tcp:1234", "tcp:5678"]
+        );
+        assert_eq!(
+            args(
+                adb.reverse_lenient("localabstract:foo", " 8080")
+                    .unwrap()
+                    .no_rebind()
+            ),
+            ["reverse", "--no-rebind", "localabstract:foo", "tcp:8080"]
+        );
+        assert!(adb.forward_lenient("1234", "nope").unwrap_err().is_parse());
+    }
+
+    #[test]
+    fn test_remove_where() {
+        let entries = parse_forward_list(
+            "emulator-5554 tcp:8080 tcp:80\n\
+             emulator-5554 tcp:8081 tcp:80\n\
+             emulator-5554 tcp:9000 tcp:9000\n",
+        )
+        .unwrap();
+        let mut locals = Vec::new();
+        let removed = remove_where(
+            entries.clone(),
+            |entry| entry.remote.tcp_port() == Some(80),
+            |entry| {
+                locals.push(entry.local.to_string());
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(locals, ["tcp:8080", "tcp:8081"]);
+        assert_eq!(
+            remove_where(entries.clone(), |_| false, |_| Ok(())).unwrap(),
+            0
+        );
+        assert!(remove_where(
+            entries,
+            |_| true,
+            |_| Err(AdbError::NulArgument("\0".into()))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let adb = Adb::default();
+        assert_eq!(args(adb.disconnect_all()), ["disconnect"]);
+        assert_eq!(
+            args(adb.disconnect("localhost").port(5555)),
+            ["disconnect", "localhost:5555"]
+        );
+        assert_eq!(
+            args(adb.disconnect("localhost")),
+            ["disconnect", "localhost"]
+        );
+        assert_eq!(
+            args(adb.disconnect_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 5555)))),
+            ["disconnect", "127.0.0.1:5555"]
+        );
+        assert_eq!(
+            args(adb.disconnect_addr(SocketAddr::from((Ipv6Addr::LOCALHOST, 5555)))),
+            ["disconnect", "[::1]:5555"]
+        );
+    }
+
+    #[test]
+    fn test_parse_disconnect() {
+        assert_eq!(
+            parse_disconnect("disconnected everything\n").unwrap(),
+            Disconnected::Everything
+        );
+        assert_eq!(
+            parse_disconnect("disconnected 192.168.0.2:5555\n").unwrap(),
+            Disconnected::Hosts(1)
+        );
+        assert_eq!(
+            parse_disconnect("error: no such device '192.168.0.3:5555'\n").unwrap(),
+            Disconnected::Hosts(0)
+        );
+        assert!(parse_disconnect("").unwrap_err().is_parse());
+        assert!(is_tcp_serial("192.168.0.2:5555"));
+        assert!(is_tcp_serial("adb-R5CT-abc._adb-tls-connect._tcp"));
+        assert!(!is_tcp_serial("emulator-5554"));
+        assert!(!is_tcp_serial("R5CT1234"));
+    }
+
+    #[test]
+    fn test_connect_addr() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.connect_addr(SocketAddr::from((Ipv4Addr::new(192, 168, 0, 2), 5555)))),
+            ["connect", "192.168.0.2:5555"]
+        );
+        assert_eq!(
+            args(adb.connect_addr(SocketAddr::from((Ipv6Addr::LOCALHOST, 5556)))),
+            ["connect", "[::1]:5556"]
+        );
+    }
+
+    #[test]
+    fn test_parse_forwarded_port() {
+        assert_eq!(parse_forwarded_port("", 1234).unwrap(), 1234);
+        assert_eq!(parse_forwarded_port("41235\n", 0).unwrap(), 41235);
+        assert!(parse_forwarded_port("", 0).is_err());
+    }
+
+    #[test]
+    fn test_tcp_pair() {
+        let adb = Adb::default();
+        let (local, remote) = AdbSocketFamily::tcp_pair(8080);
+        assert_eq!(
+            args(adb.forward().arg(local.to_string(), remote.to_string())),
+            ["forward", "tcp:8080", "tcp:8080"]
+        );
+    }
+
+    #[test]
+    fn test_parse_ip_route() {
+        let output = "\
+            10.0.2.0/24 dev eth0 proto kernel scope link src 10.0.2.15\n\
+            192.168.1.0/24 dev wlan0 proto kernel scope link src 192.168.1.42\n";
+        assert_eq!(
+            parse_ip_route(output),
+            Some(IpAddr::from([192, 168, 1, 42]))
+        );
+        assert_eq!(
+            parse_ip_route("10.0.2.0/24 dev eth0 proto kernel scope link src 10.0.2.15 \n"),
+            Some(IpAddr::from([10, 0, 2, 15]))
+        );
+        assert_eq!(parse_ip_route("default via 192.168.1.1 dev wlan0\n"), None);
+        assert_eq!(parse_ip_route(""), None);
+        assert!(is_connected("connected to 192.168.1.42:5555"));
+        assert!(is_connected("already connected to 192.168.1.42:5555"));
+        assert!(!is_connected(
+            "failed to connect to '192.168.1.42:5555': Connection refused"
+        ));
+    }
 }
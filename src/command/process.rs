@@ -0,0 +1,578 @@
+//! Helpers for executing child processes with additional constraints.
+
+use std::collections::VecDeque;
+use std::ffi::OsStr;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::{AdbError, AdbResult};
+
+/// The interval between two polls of a running child process.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Waits for the child process to exit, at most `timeout`.
+///
+/// Returns [`None`] if the child is still running after `timeout`.
+fn wait_timeout(child: &mut Child, timeout: Duration) -> AdbResult<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// The time left until `deadline`.
+///
+/// # Errors
+///
+/// Returns [`AdbError::Timeout`] with a zero duration if `deadline` has already passed.
+pub(crate) fn remaining(deadline: Instant) -> AdbResult<Duration> {
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(remaining) if !remaining.is_zero() => Ok(remaining),
+        _ => Err(AdbError::Timeout(Duration::ZERO)),
+    }
+}
+
+/// Kills the child process and reaps it, so that no zombie or orphan is left behind.
+fn kill(child: &mut Child) -> AdbResult<()> {
+    // the child may exit between the last poll and the kill
+    if let Err(e) = child.kill() {
+        if e.kind() != std::io::ErrorKind::InvalidInput {
+            return Err(e.into());
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
+
+/// Reads the whole stream in a new thread, to avoid blocking the child on a full pipe.
+fn read_to_end<R: Read + Send + 'static>(stream: Option<R>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut stream) = stream {
+            let _ = stream.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Checks the exit status of the command.
+///
+/// # Errors
+///
+/// Returns [`AdbError::NonZeroExit`] with the (lossy) stderr if the command failed.
+pub(crate) fn check(output: &Output) -> AdbResult<()> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(AdbError::NonZeroExit {
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// Checks that none of `args` contains an interior NUL byte, which can't be passed to a process.
+///
+/// # Errors
+///
+/// Returns [`AdbError::NulArgument`] with the first offending argument.
+pub(crate) fn check_nul<I, S>(args: I) -> AdbResult<()>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    match args
+        .into_iter()
+        .find(|arg| arg.as_ref().as_encoded_bytes().contains(&0))
+    {
+        Some(arg) => Err(AdbError::NulArgument(arg.as_ref().to_os_string())),
+        None => Ok(()),
+    }
+}
+
+/// Removes the lines of `bytes` starting with any of `prefixes` (ignoring leading whitespace).
+pub(crate) fn strip_lines<S: AsRef<str>>(bytes: &[u8], prefixes: &[S]) -> Vec<u8> {
+    bytes
+        .split_inclusive(|&b| b == b'\n')
+        .filter(|line| {
+            let line = String::from_utf8_lossy(line);
+            let line = line.trim_start();
+            !prefixes
+                .iter()
+                .any(|prefix| line.starts_with(prefix.as_ref()))
+        })
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// Returns the stdout of a successful command as raw bytes, without UTF-8 conversion.
+///
+/// # Errors
+///
+/// Returns [`AdbError::NonZeroExit`] if the command failed.
+pub(crate) fn stdout_bytes(output: Output) -> AdbResult<Vec<u8>> {
+    check(&output)?;
+    Ok(output.stdout)
+}
+
+/// Returns the stdout of a successful command as a (lossy) string.
+///
+/// # Errors
+///
+/// Returns [`AdbError::NonZeroExit`] if the command failed.
+pub(crate) fn stdout_string(output: Output) -> AdbResult<String> {
+    check(&output)?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Spawns the command and waits for it with an optional `timeout`, collecting its piped output.
+///
+/// Stdio must be configured by the caller, streams that aren't piped are collected as empty.
+///
+/// # Errors
+///
+/// Returns [`AdbError::Timeout`] if the child process is killed.
+pub(crate) fn wait_with_output(mut cmd: Command, timeout: Option<Duration>) -> AdbResult<Output> {
+    let mut child = cmd.spawn()?;
+    let stdout = read_to_end(child.stdout.take());
+    let stderr = read_to_end(child.stderr.take());
+    let status = match timeout {
+        Some(timeout) => match wait_timeout(&mut child, timeout)? {
+            Some(status) => status,
+            None => {
+                // the reader threads are detached, since a grandchild may still hold the pipes
+                kill(&mut child)?;
+                return Err(AdbError::Timeout(timeout));
+            }
+        },
+        None => child.wait()?,
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Like [`Command::output`], but the child process is killed if it doesn't exit within `timeout`.
+///
+/// # Errors
+///
+/// Returns [`AdbError::Timeout`] if the child process is killed.
+pub(crate) fn output_timeout(mut cmd: Command, timeout: Duration) -> AdbResult<Output> {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    wait_with_output(cmd, Some(timeout))
+}
+
+/// Like [`Command::output`], but `input` is written to stdin, which is closed afterwards.
+///
+/// stdin is written on another thread while stdout and stderr are collected,
+/// so a child filling its output pipes before reading all of its input doesn't deadlock.
+/// A child exiting without reading all of its input (i.e. a broken pipe) isn't an error.
+pub(crate) fn output_with_stdin(mut cmd: Command, input: Vec<u8>) -> AdbResult<Output> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdin = child.stdin.take();
+    let writer = thread::spawn(move || match stdin {
+        Some(mut stdin) => match stdin.write_all(&input) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e),
+            _ => Ok(()),
+        },
+        None => Ok(()),
+    });
+    let output = child.wait_with_output()?;
+    writer.join().unwrap_or(Ok(()))?;
+    Ok(output)
+}
+
+/// Like [`Command::status`], but the child process is killed if it doesn't exit within `timeout`.
+///
+/// # Errors
+///
+/// Returns [`AdbError::Timeout`] if the child process is killed.
+pub(crate) fn status_timeout(mut cmd: Command, timeout: Duration) -> AdbResult<ExitStatus> {
+    let mut child = cmd.spawn()?;
+    match wait_timeout(&mut child, timeout)? {
+        Some(status) => Ok(status),
+        None => {
+            kill(&mut child)?;
+            Err(AdbError::Timeout(timeout))
+        }
+    }
+}
+
+/// Spawns the command with stdin, stdout and stderr set to null, without waiting for it.
+pub(crate) fn spawn_detached(mut cmd: Command) -> AdbResult<Child> {
+    Ok(cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?)
+}
+
+/// Spawns the command with stdout piped, copying all of it into `writer` until the child exits.
+///
+/// If the copy fails (e.g. the writer is closed), the child process is killed.
+pub(crate) fn pipe_to<W: Write>(mut cmd: Command, writer: &mut W) -> AdbResult<ExitStatus> {
+    let mut child = cmd.stdout(Stdio::piped()).spawn()?;
+    if let Some(mut stdout) = child.stdout.take() {
+        if let Err(e) = io::copy(&mut stdout, writer) {
+            kill(&mut child)?;
+            return Err(e.into());
+        }
+    }
+    Ok(child.wait()?)
+}
+
+/// Spawns the command with stdout piped, calling `f` on each (lossy) line of it until the child exits.
+///
+/// Line endings (`\n` or `\r\n`) are stripped, only one line is buffered at a time.
+/// If the read fails, the child process is killed.
+pub(crate) fn for_each_line<F: FnMut(&str)>(mut cmd: Command, mut f: F) -> AdbResult<ExitStatus> {
+    let mut child = cmd.stdout(Stdio::piped()).spawn()?;
+    if let Some(stdout) = child.stdout.take() {
+        let mut reader = BufReader::new(stdout);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {
+                    let s = String::from_utf8_lossy(&line);
+                    f(s.trim_end_matches('\n').trim_end_matches('\r'));
+                }
+                Err(e) => {
+                    kill(&mut child)?;
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+    Ok(child.wait()?)
+}
+
+/// A fixed-capacity buffer keeping the most recent bytes written to it.
+#[derive(Debug)]
+struct RingBuffer {
+    buf: VecDeque<u8>,
+    cap: usize,
+}
+
+impl RingBuffer {
+    fn new(cap: usize) -> Self {
+        Self {
+            buf: VecDeque::with_capacity(cap),
+            cap,
+        }
+    }
+
+    /// Appends `bytes`, dropping the oldest bytes beyond the capacity.
+    fn extend(&mut self, bytes: &[u8]) {
+        let bytes = &bytes[bytes.len().saturating_sub(self.cap)..];
+        let overflow = (self.buf.len() + bytes.len()).saturating_sub(self.cap);
+        self.buf.drain(..overflow);
+        self.buf.extend(bytes);
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        self.buf.iter().copied().collect()
+    }
+}
+
+/// Reads the whole stream in a new thread, keeping only its tail in `tail`.
+fn read_tail<R: Read + Send + 'static>(
+    stream: Option<R>,
+    tail: Arc<Mutex<RingBuffer>>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let Some(mut stream) = stream else {
+            return;
+        };
+        let mut chunk = [0; 8192];
+        while let Ok(n @ 1..) = stream.read(&mut chunk) {
+            lock(&tail).extend(&chunk[..n]);
+        }
+    })
+}
+
+/// Locks the tail, ignoring poisoning since [`RingBuffer::extend`] doesn't panic halfway.
+fn lock(tail: &Mutex<RingBuffer>) -> std::sync::MutexGuard<'_, RingBuffer> {
+    tail.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A child process spawned by [`AdbCommand::spawn_tail`](crate::AdbCommand::spawn_tail),
+/// whose stdout and stderr are drained into bounded buffers keeping their most recent bytes.
+#[derive(Debug)]
+pub struct TailHandle {
+    child: Child,
+    stdout: Arc<Mutex<RingBuffer>>,
+    stderr: Arc<Mutex<RingBuffer>>,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl TailHandle {
+    /// The last (at most `cap`) bytes of stdout received so far.
+    pub fn stdout_tail(&self) -> Vec<u8> {
+        lock(&self.stdout).to_vec()
+    }
+
+    /// The last (at most `cap`) bytes of stderr received so far.
+    pub fn stderr_tail(&self) -> Vec<u8> {
+        lock(&self.stderr).to_vec()
+    }
+
+    /// The child process, e.g. to get its id or to check whether it has exited.
+    ///
+    /// Its stdout and stderr are owned by the reader threads.
+    pub fn child(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// Waits for the child process to exit and its output to be drained,
+    /// so that the tails are complete afterwards.
+    pub fn wait(&mut self) -> AdbResult<ExitStatus> {
+        let status = self.child.wait()?;
+        for reader in self.readers.drain(..) {
+            let _ = reader.join();
+        }
+        Ok(status)
+    }
+
+    /// Kills the child process and waits for it like [`Self::wait`].
+    pub fn kill(&mut self) -> AdbResult<ExitStatus> {
+        kill(&mut self.child)?;
+        self.wait()
+    }
+}
+
+/// Spawns the command with stdout and stderr piped into ring buffers of `cap` bytes each.
+pub(crate) fn spawn_tail(mut cmd: Command, cap: usize) -> AdbResult<TailHandle> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let stdout = Arc::new(Mutex::new(RingBuffer::new(cap)));
+    let stderr = Arc::new(Mutex::new(RingBuffer::new(cap)));
+    let readers = vec![
+        read_tail(child.stdout.take(), stdout.clone()),
+        read_tail(child.stderr.take(), stderr.clone()),
+    ];
+    Ok(TailHandle {
+        child,
+        stdout,
+        stderr,
+        readers,
+    })
+}
+
+/// Runs the command with `run` in a `debug` span recording its argv,
+/// then records the exit status (extracted from the result by `status`) and the elapsed time.
+#[cfg(feature = "tracing")]
+pub(crate) fn traced<T>(
+    cmd: Command,
+    status: fn(&T) -> Option<ExitStatus>,
+    run: impl FnOnce(Command) -> AdbResult<T>,
+) -> AdbResult<T> {
+    let argv = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let span = tracing::debug_span!("adb", ?argv);
+    let _enter = span.enter();
+    let start = Instant::now();
+    let result = run(cmd);
+    let elapsed = start.elapsed();
+    match &result {
+        Ok(t) => tracing::debug!(status = ?status(t), ?elapsed, "adb command finished"),
+        Err(e) => tracing::debug!(error = %e, ?elapsed, "adb command failed"),
+    }
+    result
+}
+
+/// Runs the command with `run`, the no-op counterpart of the `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn traced<T>(
+    cmd: Command,
+    _status: fn(&T) -> Option<ExitStatus>,
+    run: impl FnOnce(Command) -> AdbResult<T>,
+) -> AdbResult<T> {
+    run(cmd)
+}
+
+/// Adds `increment` to the niceness of the child process before it executes the program.
+///
+/// Like `nice(1)`, a failure to change the niceness (e.g. a negative `increment` without privileges)
+/// is ignored and doesn't prevent the program from running.
+#[cfg(unix)]
+pub(crate) fn set_nice(cmd: &mut Command, increment: i32) {
+    use std::os::raw::c_int;
+    use std::os::unix::process::CommandExt;
+
+    extern "C" {
+        fn nice(inc: c_int) -> c_int;
+    }
+
+    // SAFETY: the closure runs in the forked child before `exec`,
+    // where only async-signal-safe functions may be called.
+    // `nice` is a thin wrapper of a system call, it doesn't allocate, lock or touch the parent's state.
+    unsafe {
+        cmd.pre_exec(move || {
+            nice(increment);
+            Ok(())
+        });
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Command {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(script);
+        cmd
+    }
+
+    #[test]
+    fn test_output_timeout() {
+        let output = output_timeout(sh("echo out; echo err >&2"), Duration::from_secs(10)).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+
+        let start = Instant::now();
+        let err = output_timeout(sh("sleep 10"), Duration::from_millis(100)).unwrap_err();
+        assert!(matches!(err, AdbError::Timeout(t) if t == Duration::from_millis(100)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_status_timeout() {
+        let status = status_timeout(sh("exit 3"), Duration::from_secs(10)).unwrap();
+        assert_eq!(status.code(), Some(3));
+
+        let start = Instant::now();
+        let err = status_timeout(sh("sleep 10"), Duration::from_millis(100)).unwrap_err();
+        assert!(matches!(err, AdbError::Timeout(_)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut tail = RingBuffer::new(4);
+        tail.extend(b"ab");
+        assert_eq!(tail.to_vec(), b"ab");
+        tail.extend(b"cde");
+        assert_eq!(tail.to_vec(), b"bcde");
+        tail.extend(b"0123456789");
+        assert_eq!(tail.to_vec(), b"6789");
+        tail.extend(b"");
+        assert_eq!(tail.to_vec(), b"6789");
+        let mut empty = RingBuffer::new(0);
+        empty.extend(b"abc");
+        assert!(empty.to_vec().is_empty());
+    }
+
+    #[test]
+    fn test_spawn_tail() {
+        let mut handle =
+            spawn_tail(sh("seq 1 1000; echo first >&2; echo last >&2; exit 3"), 8).unwrap();
+        assert_eq!(handle.wait().unwrap().code(), Some(3));
+        assert_eq!(handle.stdout_tail(), b"99\n1000\n");
+        assert_eq!(handle.stderr_tail(), b"st\nlast\n");
+
+        let mut handle = spawn_tail(sh("echo started; exec sleep 10"), 64).unwrap();
+        let start = Instant::now();
+        while handle.stdout_tail().is_empty() && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(POLL_INTERVAL);
+        }
+        assert_eq!(handle.stdout_tail(), b"started\n");
+        assert!(!handle.kill().unwrap().success());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_spawn_detached() {
+        let mut child = spawn_detached(sh(
+            r#"for fd in 0 1 2; do [ "$(readlink /proc/$$/fd/$fd)" = /dev/null ] || exit 1; done"#,
+        ))
+        .unwrap();
+        assert!(child.stdout.is_none() && child.stderr.is_none());
+        assert!(child.wait().unwrap().success());
+    }
+
+    #[test]
+    fn test_remaining() {
+        let left = remaining(Instant::now() + Duration::from_secs(10)).unwrap();
+        assert!(left > Duration::from_secs(9) && left <= Duration::from_secs(10));
+        assert!(matches!(
+            remaining(Instant::now()),
+            Err(AdbError::Timeout(Duration::ZERO))
+        ));
+    }
+
+    #[test]
+    fn test_set_nice() {
+        let niceness = |cmd: &mut Command| -> i32 {
+            let output = cmd.output().unwrap();
+            String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse()
+                .unwrap()
+        };
+        let base = niceness(&mut sh("nice"));
+        let mut cmd = sh("nice");
+        set_nice(&mut cmd, 5);
+        assert_eq!(niceness(&mut cmd), (base + 5).min(19));
+    }
+
+    #[test]
+    fn test_check() {
+        let mut cmd = sh("echo out; echo failed >&2; exit 2");
+        cmd.stdout(Stdio::null()).stderr(Stdio::piped());
+        let output = wait_with_output(cmd, None).unwrap();
+        assert!(output.stdout.is_empty());
+        match check(&output) {
+            Err(AdbError::NonZeroExit { status, stderr }) => {
+                assert_eq!(status.code(), Some(2));
+                assert_eq!(stderr, "failed\n");
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(check(&sh("true").output().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_pipe_to() {
+        let mut buf = Vec::new();
+        let status = pipe_to(sh("printf 'a\\0b'; exit 4"), &mut buf).unwrap();
+        assert_eq!(status.code(), Some(4));
+        assert_eq!(buf, b"a\0b");
+    }
+
+    #[test]
+    fn test_for_each_line() {
+        let mut lines = Vec::new();
+        let status = for_each_line(sh("printf 'a\\nb\\r\\n\\nc'; exit 5"), |line| {
+            lines.push(line.to_string())
+        })
+        .unwrap();
+        assert_eq!(status.code(), Some(5));
+        assert_eq!(lines, ["a", "b", "", "c"]);
+    }
+}
@@ -0,0 +1,151 @@
+//! System property commands, run through the remote shell.
+//!
+//! - `shell getprop KEY`: Get a system property, see [`Adb::get_prop`].
+//! - `shell setprop KEY VALUE`: Set a system property, see [`Adb::setprop`].
+
+use crate::command::app_management::shell_failure;
+use crate::command::{process, shell};
+use crate::error::ParseError;
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
+
+/// Parses a boolean property like `android::base::GetBoolProperty`,
+/// i.e. `1`, `y`, `yes`, `on` or `true` and `0`, `n`, `no`, `off` or `false`.
+fn parse_prop_bool(value: &str) -> AdbResult<bool> {
+    match value {
+        "1" | "y" | "yes" | "on" | "true" => Ok(true),
+        "0" | "n" | "no" | "off" | "false" => Ok(false),
+        _ => Err(
+            ParseError::with_description(value, "bool", "Unknown boolean property value").into(),
+        ),
+    }
+}
+
+/// Parses an integer property, in decimal or in hexadecimal with a `0x` prefix.
+fn parse_prop_int(value: &str) -> AdbResult<i64> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+    let parsed = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i64::from_str_radix(hex, 16),
+        None => digits.parse(),
+    };
+    parsed
+        .map(|n| if negative { -n } else { n })
+        .map_err(|e| ParseError::with_source(value, "i64", e).into())
+}
+
+impl Adb {
+    /// `shell getprop KEY`: Get the value of the system property `key`.
+    ///
+    /// Returns [`None`] if the property isn't set, which `getprop` prints as an empty line.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let model = adb.get_prop("ro.product.model").unwrap();
+    /// println!("model: {:?}", model);
+    /// ```
+    pub fn get_prop(&self, key: &str) -> AdbResult<Option<String>> {
+        let stdout = process::stdout_string(self.shell_cmd(["getprop", key]).output()?)?;
+        let value = stdout.trim_end_matches(['\r', '\n']);
+        Ok((!value.is_empty()).then(|| value.to_string()))
+    }
+
+    /// `shell getprop KEY`: Get the system property `key` as a boolean.
+    ///
+    /// `1`, `y`, `yes`, `on` and `true` are `true`,
+    /// `0`, `n`, `no`, `off` and `false` are `false`, like `GetBoolProperty` of Android.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Parse`] if the value is set but not a boolean.
+    pub fn get_prop_bool(&self, key: &str) -> AdbResult<Option<bool>> {
+        self.get_prop(key)?
+            .map(|value| parse_prop_bool(&value))
+            .transpose()
+    }
+
+    /// `shell getprop KEY`: Get the system property `key` as an integer,
+    /// in decimal or in hexadecimal with a `0x` prefix.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Parse`] if the value is set but not an integer.
+    pub fn get_prop_int(&self, key: &str) -> AdbResult<Option<i64>> {
+        self.get_prop(key)?
+            .map(|value| parse_prop_int(&value))
+            .transpose()
+    }
+
+    /// `shell setprop KEY VALUE`: Set the system property `key` to `value`, then verify it.
+    ///
+    /// `setprop` may exit successfully without changing the property,
+    /// e.g. without root or for read-only (`ro.`) properties that are already set,
+    /// so the property is read back with [`Self::get_prop`] and compared with `value`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::ShellCommandFailed`] if `setprop` fails
+    /// or if the property doesn't hold `value` afterwards.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell setprop debug.layout true`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.setprop("debug.layout", "true").unwrap();
+    /// assert_eq!(adb.get_prop_bool("debug.layout").unwrap(), Some(true));
+    /// ```
+    pub fn setprop(&self, key: &str, value: &str) -> AdbResult<()> {
+        let result = self.shell_run(["setprop", key, &shell::quote(value)])?;
+        if !result.success() {
+            return Err(shell_failure(result));
+        }
+        let actual = self.get_prop(key)?;
+        if actual.as_deref().unwrap_or_default() == value {
+            Ok(())
+        } else {
+            Err(AdbError::ShellCommandFailed(format!(
+                "setprop {} didn't take effect: expected {:?}, got {:?}",
+                key,
+                value,
+                actual.unwrap_or_default()
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prop_bool() {
+        for value in ["1", "y", "yes", "on", "true"] {
+            assert!(parse_prop_bool(value).unwrap());
+        }
+        for value in ["0", "n", "no", "off", "false"] {
+            assert!(!parse_prop_bool(value).unwrap());
+        }
+        assert!(parse_prop_bool("TRUE").unwrap_err().is_parse());
+        assert!(parse_prop_bool("").unwrap_err().is_parse());
+    }
+
+    #[test]
+    fn test_parse_prop_int() {
+        assert_eq!(parse_prop_int("34").unwrap(), 34);
+        assert_eq!(parse_prop_int("-1").unwrap(), -1);
+        assert_eq!(parse_prop_int("0x1F").unwrap(), 31);
+        assert_eq!(parse_prop_int("-0x10").unwrap(), -16);
+        assert!(parse_prop_int("1.5").unwrap_err().is_parse());
+        assert!(parse_prop_int("0x").unwrap_err().is_parse());
+    }
+}
@@ -0,0 +1,445 @@
+//! Screen commands.
+//!
+//! - `exec-out screencap -p`: Take a PNG screenshot, see [`Adb::screenshot`].
+//! - `shell dumpsys input`: Get the rotation of the display, see [`Adb::display_rotation`].
+//! - `shell dumpsys power`: Check whether the screen is on, see [`Adb::is_screen_on`].
+//! - `shell input keyevent KEYCODE_WAKEUP`: Wake up the device, see [`Adb::wake_up`].
+//! - `shell input swipe X1 Y1 X2 Y2 [DURATION]`: Swipe to unlock, see [`Adb::swipe_unlock`].
+
+use std::process::Command;
+use std::str::FromStr;
+
+use crate::command::app_management::shell_failure;
+use crate::command::{process, AdbCommandBuilder};
+use crate::error::ParseError;
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
+
+/// The rotation of the display from its natural orientation, like `Surface.ROTATION_*`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisplayRotation {
+    /// `0`: The natural orientation, e.g. portrait on phones.
+    #[default]
+    Rotation0,
+    /// `1`: Rotated by 90 degrees, e.g. landscape.
+    Rotation90,
+    /// `2`: Rotated by 180 degrees, e.g. upside down.
+    Rotation180,
+    /// `3`: Rotated by 270 degrees, e.g. reverse landscape.
+    Rotation270,
+}
+
+impl DisplayRotation {
+    /// Returns the rotation in degrees, i.e. `0`, `90`, `180` or `270`.
+    pub fn degrees(self) -> u16 {
+        match self {
+            DisplayRotation::Rotation0 => 0,
+            DisplayRotation::Rotation90 => 90,
+            DisplayRotation::Rotation180 => 180,
+            DisplayRotation::Rotation270 => 270,
+        }
+    }
+}
+
+impl FromStr for DisplayRotation {
+    type Err = AdbError;
+
+    /// Parses the rotation from the output of `dumpsys input`,
+    /// i.e. `SurfaceOrientation: N` or `orientation=N` of the internal viewport on newer versions.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.lines().find_map(|line| {
+            let line = line.trim();
+            if let Some(value) = line.strip_prefix("SurfaceOrientation:") {
+                Some(value.trim())
+            } else if line.starts_with("Viewport INTERNAL:") {
+                line.split([',', ' '])
+                    .find_map(|field| field.strip_prefix("orientation="))
+            } else {
+                None
+            }
+        });
+        match value {
+            Some("0") => Ok(DisplayRotation::Rotation0),
+            Some("1") => Ok(DisplayRotation::Rotation90),
+            Some("2") => Ok(DisplayRotation::Rotation180),
+            Some("3") => Ok(DisplayRotation::Rotation270),
+            Some(value) => Err(ParseError::with_description(
+                value,
+                "DisplayRotation",
+                "Unknown display orientation",
+            )
+            .into()),
+            None => Err(ParseError::with_description(
+                s,
+                "DisplayRotation",
+                "Missing display orientation",
+            )
+            .into()),
+        }
+    }
+}
+
+/// Parses whether the screen is on from the output of `dumpsys power`.
+///
+/// The first line found in the output decides, checked in this order:
+///
+/// - `Display Power: state=ON`, or any other state, e.g. `OFF` or `DOZE`.
+/// - `mWakefulness=Awake`, or any other wakefulness, e.g. `Asleep` or `Dozing`.
+/// - `mScreenOn=true` or `mScreenOn=false` on old versions.
+fn parse_screen_on(s: &str) -> AdbResult<bool> {
+    let find = |prefix: &str| {
+        s.lines()
+            .find_map(|line| line.trim().strip_prefix(prefix))
+            .map(|value| value.split_whitespace().next().unwrap_or_default())
+    };
+    if let Some(state) = find("Display Power: state=") {
+        Ok(state.eq_ignore_ascii_case("ON"))
+    } else if let Some(wakefulness) = find("mWakefulness=") {
+        Ok(wakefulness.eq_ignore_ascii_case("Awake"))
+    } else if let Some(screen_on) = find("mScreenOn=") {
+        screen_on
+            .parse()
+            .map_err(|e| ParseError::with_source(screen_on, "bool", e).into())
+    } else {
+        Err(ParseError::with_description(s, "bool", "Missing screen state").into())
+    }
+}
+
+/// A swipe gesture, `input swipe X1 Y1 X2 Y2 [DURATION]`, see [`Adb::swipe_unlock`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Swipe {
+    /// The start point, in pixels.
+    pub from: (u32, u32),
+    /// The end point, in pixels.
+    pub to: (u32, u32),
+    /// The duration of the swipe in milliseconds, the default of `input` if `None`.
+    pub duration_ms: Option<u32>,
+}
+
+impl Default for Swipe {
+    /// An upward swipe in the middle of a 1080x1920 screen, which dismisses a keyguard without a PIN.
+    fn default() -> Self {
+        Self {
+            from: (540, 1600),
+            to: (540, 400),
+            duration_ms: Some(300),
+        }
+    }
+}
+
+impl Swipe {
+    /// Returns the arguments of `input`, i.e. `swipe X1 Y1 X2 Y2 [DURATION]`.
+    fn args(&self) -> Vec<String> {
+        let mut args = vec!["swipe".to_string()];
+        args.extend(
+            [self.from.0, self.from.1, self.to.0, self.to.1]
+                .iter()
+                .map(u32::to_string),
+        );
+        args.extend(self.duration_ms.map(|ms| ms.to_string()));
+        args
+    }
+}
+
+/// A PNG screenshot taken by [`AdbScreenshot::run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    /// The PNG image, as captured by `screencap -p`.
+    ///
+    /// With the `image` feature and [`AdbScreenshot::auto_rotate`], the image is rotated
+    /// as displayed. Otherwise it isn't re-encoded, to show it as displayed,
+    /// rotate it counter-clockwise by the [`DisplayRotation::degrees`] of [`Self::rotation`].
+    pub png: Vec<u8>,
+    /// The rotation of the display when the screenshot was taken,
+    /// [`None`] unless [`AdbScreenshot::auto_rotate`] is set.
+    pub rotation: Option<DisplayRotation>,
+}
+
+/// Rotates the `png` counter-clockwise by the degrees of `rotation`,
+/// i.e. from the natural orientation of the display to the displayed one.
+///
+/// # Errors
+///
+/// Returns [`AdbError::Parse`] if `png` can't be decoded or the rotated image can't be encoded.
+#[cfg(feature = "image")]
+fn rotate_png(png: Vec<u8>, rotation: DisplayRotation) -> AdbResult<Vec<u8>> {
+    use image::ImageFormat;
+    use std::io::Cursor;
+
+    let rotate: fn(&image::DynamicImage) -> image::DynamicImage = match rotation {
+        DisplayRotation::Rotation0 => return Ok(png),
+        DisplayRotation::Rotation90 => image::DynamicImage::rotate270,
+        DisplayRotation::Rotation180 => image::DynamicImage::rotate180,
+        DisplayRotation::Rotation270 => image::DynamicImage::rotate90,
+    };
+    let decoded = image::load_from_memory_with_format(&png, ImageFormat::Png)
+        .map_err(|e| ParseError::with_source("screencap -p", "image::DynamicImage", e))?;
+    let mut rotated = Vec::new();
+    rotate(&decoded)
+        .write_to(&mut Cursor::new(&mut rotated), ImageFormat::Png)
+        .map_err(|e| ParseError::with_source("screencap -p", "PNG", e))?;
+    Ok(rotated)
+}
+
+/// `exec-out screencap -p`: Take a PNG screenshot.
+#[derive(Debug, Clone)]
+pub struct AdbScreenshot<'a> {
+    acb: AdbCommandBuilder<'a>,
+    /// Query the display rotation along with the screenshot.
+    auto_rotate: bool,
+}
+
+impl<'a> AdbScreenshot<'a> {
+    /// Queries the display rotation (see [`Adb::display_rotation`]) along with the screenshot,
+    /// since `screencap` captures in the natural orientation of the display.
+    ///
+    /// With the `image` feature, the screenshot is also rotated as displayed,
+    /// e.g. a landscape screenshot isn't sideways.
+    ///
+    /// Off by default, so the screenshot is exactly what `screencap` captured.
+    pub fn auto_rotate(mut self) -> Self {
+        self.auto_rotate = true;
+        self
+    }
+
+    /// Executes the command, returning the screenshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NonZeroExit`] if `screencap` fails,
+    /// or [`AdbError::Parse`] if the display rotation can't be recognized
+    /// (or with the `image` feature, if the screenshot can't be rotated).
+    pub fn run(self) -> AdbResult<Screenshot> {
+        let rotation = if self.auto_rotate {
+            Some(display_rotation(self.acb.clone())?)
+        } else {
+            None
+        };
+        let png = process::stdout_bytes(self.output()?)?;
+        #[cfg(feature = "image")]
+        let png = match rotation {
+            Some(rotation) => rotate_png(png, rotation)?,
+            None => png,
+        };
+        Ok(Screenshot { png, rotation })
+    }
+}
+
+impl<'a> AdbCommand for AdbScreenshot<'a> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("exec-out").arg("screencap").arg("-p");
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+}
+
+/// `shell dumpsys input`: Get the rotation of the display.
+fn display_rotation(acb: AdbCommandBuilder) -> AdbResult<DisplayRotation> {
+    process::stdout_string(acb.shell().args(["dumpsys", "input"]).output()?)?.parse()
+}
+
+impl Adb {
+    /// `exec-out screencap -p`: Take a PNG screenshot.
+    ///
+    /// # Examples
+    ///
+    /// `adb exec-out screencap -p > screen.png`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let screenshot = adb.screenshot().auto_rotate().run().unwrap();
+    /// std::fs::write("screen.png", &screenshot.png).unwrap();
+    /// if let Some(rotation) = screenshot.rotation {
+    ///     println!("the display is rotated by {} degrees", rotation.degrees());
+    /// }
+    /// ```
+    pub fn screenshot(&self) -> AdbScreenshot<'_> {
+        self.command().screenshot()
+    }
+
+    /// `shell dumpsys input`: Get the rotation of the display from its natural orientation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Parse`] if the rotation can't be found in the output.
+    pub fn display_rotation(&self) -> AdbResult<DisplayRotation> {
+        display_rotation(self.command())
+    }
+
+    /// `shell dumpsys power`: Check whether the screen is on.
+    ///
+    /// A dozing screen (e.g. always-on display) counts as off.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Parse`] if the screen state can't be found in the output.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// if !adb.is_screen_on().unwrap() {
+    ///     adb.wake_up().unwrap();
+    ///     adb.swipe_unlock(Default::default()).unwrap();
+    /// }
+    /// ```
+    pub fn is_screen_on(&self) -> AdbResult<bool> {
+        parse_screen_on(&process::stdout_string(
+            self.shell_cmd(["dumpsys", "power"]).output()?,
+        )?)
+    }
+
+    /// `shell input keyevent KEYCODE_WAKEUP`: Wake up the device, no-op if it's already awake.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::ShellCommandFailed`] if `input` fails.
+    pub fn wake_up(&self) -> AdbResult<()> {
+        let result = self.shell_run(["input", "keyevent", "KEYCODE_WAKEUP"])?;
+        if result.success() {
+            Ok(())
+        } else {
+            Err(shell_failure(result))
+        }
+    }
+
+    /// `shell input swipe X1 Y1 X2 Y2 [DURATION]`: Swipe to dismiss the keyguard.
+    ///
+    /// The keyguard must not be secured, and the screen must be on, see [`Adb::wake_up`].
+    /// The [default swipe](Swipe::default) suits most portrait phones.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::ShellCommandFailed`] if `input` fails.
+    pub fn swipe_unlock(&self, swipe: Swipe) -> AdbResult<()> {
+        let mut args = vec!["input".to_string()];
+        args.extend(swipe.args());
+        let result = self.shell_run(args)?;
+        if result.success() {
+            Ok(())
+        } else {
+            Err(shell_failure(result))
+        }
+    }
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `exec-out screencap -p`: Take a PNG screenshot.
+    ///
+    /// See [`Adb::screenshot`] for more information.
+    pub fn screenshot(self) -> AdbScreenshot<'a> {
+        AdbScreenshot {
+            acb: self,
+            auto_rotate: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_build() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.screenshot().auto_rotate()),
+            ["exec-out", "screencap", "-p"]
+        );
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_rotate_png() {
+        use image::{ImageFormat, Rgba, RgbaImage};
+        use std::io::Cursor;
+
+        // a 2x1 image: red on the left, blue on the right
+        let (red, blue) = (Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 255]));
+        let mut natural = RgbaImage::new(2, 1);
+        natural.put_pixel(0, 0, red);
+        natural.put_pixel(1, 0, blue);
+        let mut png = Vec::new();
+        natural
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        assert_eq!(
+            rotate_png(png.clone(), DisplayRotation::Rotation0).unwrap(),
+            png
+        );
+        let decode = |png: Vec<u8>| image::load_from_memory(&png).unwrap().to_rgba8();
+        // counter-clockwise: the right edge goes to the top
+        let rotated = decode(rotate_png(png.clone(), DisplayRotation::Rotation90).unwrap());
+        assert_eq!(rotated.dimensions(), (1, 2));
+        assert_eq!(rotated.get_pixel(0, 0), &blue);
+        let rotated = decode(rotate_png(png.clone(), DisplayRotation::Rotation270).unwrap());
+        assert_eq!(rotated.get_pixel(0, 0), &red);
+        let rotated = decode(rotate_png(png, DisplayRotation::Rotation180).unwrap());
+        assert_eq!(rotated.get_pixel(0, 0), &blue);
+        assert!(
+            rotate_png(b"not a png".to_vec(), DisplayRotation::Rotation90)
+                .unwrap_err()
+                .is_parse()
+        );
+    }
+
+    #[test]
+    fn test_display_rotation() {
+        let old = "INPUT MANAGER\n  Device 1: touchscreen\n      SurfaceOrientation: 1\n";
+        assert_eq!(
+            old.parse::<DisplayRotation>().unwrap(),
+            DisplayRotation::Rotation90
+        );
+        let new = "  Viewport INTERNAL: displayId=0, uniqueId=local:1, port=1, \
+                   orientation=3, logicalFrame=[0, 0, 2400, 1080], isActive=[true]\n";
+        assert_eq!(
+            new.parse::<DisplayRotation>().unwrap(),
+            DisplayRotation::Rotation270
+        );
+        assert_eq!(DisplayRotation::Rotation270.degrees(), 270);
+        assert!("SurfaceOrientation: 4"
+            .parse::<DisplayRotation>()
+            .unwrap_err()
+            .is_parse());
+        assert!("".parse::<DisplayRotation>().unwrap_err().is_parse());
+    }
+
+    #[test]
+    fn test_parse_screen_on() {
+        let awake = "POWER MANAGER (dumpsys power)\n\nPower Manager State:\n  \
+                     mDirty=0x0\n  mWakefulness=Awake\n  mWakefulnessChanging=false\n";
+        assert!(parse_screen_on(awake).unwrap());
+        let dozing = "  mWakefulness=Dozing\n  mWakefulnessRaw=Dozing\n";
+        assert!(!parse_screen_on(dozing).unwrap());
+        let display = "  mWakefulness=Awake\nDisplay Power: state=OFF\n";
+        assert!(!parse_screen_on(display).unwrap());
+        assert!(parse_screen_on("Display Power: state=ON\n").unwrap());
+        assert!(parse_screen_on("  mScreenOn=true\n").unwrap());
+        assert!(!parse_screen_on("  mScreenOn=false\n").unwrap());
+        assert!(parse_screen_on("  mScreenOn=maybe\n")
+            .unwrap_err()
+            .is_parse());
+        assert!(parse_screen_on("").unwrap_err().is_parse());
+    }
+
+    #[test]
+    fn test_swipe() {
+        assert_eq!(
+            Swipe::default().args(),
+            ["swipe", "540", "1600", "540", "400", "300"]
+        );
+        let swipe = Swipe {
+            from: (0, 10),
+            to: (20, 30),
+            duration_ms: None,
+        };
+        assert_eq!(swipe.args(), ["swipe", "0", "10", "20", "30"]);
+    }
+}
@@ -16,66 +16,132 @@
 //!
 //! See [Scripting Commands](https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/master/docs/user/adb.1.md#scripting).
 
-use std::ffi::OsStr;
+use std::ffi::{OsStr, OsString};
 use std::fmt::Display;
 use std::process::Command;
 use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::command::AdbCommandBuilder;
+use crate::command::{process, AdbCommandBuilder};
 use crate::error::ParseError;
-use crate::{Adb, AdbCommand, AdbError};
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
 
-/// A device state to wait for.
+/// The state of a device, as printed by `get-state` or waited for by `wait-for`.
+///
+/// `wait-for` only accepts [`DeviceState::Device`], [`DeviceState::Recovery`], [`DeviceState::Rescue`],
+/// [`DeviceState::Sideload`], [`DeviceState::Bootloader`] and [`DeviceState::Disconnect`],
+/// see [`DeviceState::can_wait_for`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum AdbWaitForState {
+pub enum DeviceState {
+    Offline,
+    Bootloader,
     Device,
+    Host,
     Recovery,
     Rescue,
     Sideload,
-    Bootloader,
+    Unauthorized,
+    Authorizing,
+    Connecting,
+    NoPermissions,
+    Detached,
+    /// The device is gone, only used by `wait-for`.
     Disconnect,
 }
 
-impl AsRef<OsStr> for AdbWaitForState {
-    fn as_ref(&self) -> &OsStr {
+/// A device state to wait for.
+#[deprecated(note = "use `DeviceState` instead")]
+pub type AdbWaitForState = DeviceState;
+
+impl DeviceState {
+    /// Returns all the device states, in declaration order.
+    pub fn all() -> &'static [DeviceState] {
+        &[
+            DeviceState::Offline,
+            DeviceState::Bootloader,
+            DeviceState::Device,
+            DeviceState::Host,
+            DeviceState::Recovery,
+            DeviceState::Rescue,
+            DeviceState::Sideload,
+            DeviceState::Unauthorized,
+            DeviceState::Authorizing,
+            DeviceState::Connecting,
+            DeviceState::NoPermissions,
+            DeviceState::Detached,
+            DeviceState::Disconnect,
+        ]
+    }
+
+    /// Whether the state is accepted by `wait-for`.
+    pub fn can_wait_for(self) -> bool {
+        matches!(
+            self,
+            DeviceState::Device
+                | DeviceState::Recovery
+                | DeviceState::Rescue
+                | DeviceState::Sideload
+                | DeviceState::Bootloader
+                | DeviceState::Disconnect
+        )
+    }
+}
+
+impl AsRef<str> for DeviceState {
+    fn as_ref(&self) -> &str {
         match self {
-            AdbWaitForState::Device => OsStr::new("device"),
-            AdbWaitForState::Recovery => OsStr::new("recovery"),
-            AdbWaitForState::Rescue => OsStr::new("rescue"),
-            AdbWaitForState::Sideload => OsStr::new("sideload"),
-            AdbWaitForState::Bootloader => OsStr::new("bootloader"),
-            AdbWaitForState::Disconnect => OsStr::new("disconnect"),
+            DeviceState::Offline => "offline",
+            DeviceState::Bootloader => "bootloader",
+            DeviceState::Device => "device",
+            DeviceState::Host => "host",
+            DeviceState::Recovery => "recovery",
+            DeviceState::Rescue => "rescue",
+            DeviceState::Sideload => "sideload",
+            DeviceState::Unauthorized => "unauthorized",
+            DeviceState::Authorizing => "authorizing",
+            DeviceState::Connecting => "connecting",
+            DeviceState::NoPermissions => "no permissions",
+            DeviceState::Detached => "detached",
+            DeviceState::Disconnect => "disconnect",
         }
     }
 }
 
-impl Display for AdbWaitForState {
+impl AsRef<OsStr> for DeviceState {
+    fn as_ref(&self) -> &OsStr {
+        OsStr::new(AsRef::<str>::as_ref(self))
+    }
+}
+
+impl Display for DeviceState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(match self {
-            AdbWaitForState::Device => "device",
-            AdbWaitForState::Recovery => "recovery",
-            AdbWaitForState::Rescue => "rescue",
-            AdbWaitForState::Sideload => "sideload",
-            AdbWaitForState::Bootloader => "bootloader",
-            AdbWaitForState::Disconnect => "disconnect",
-        })
+        f.write_str(self.as_ref())
     }
 }
 
-impl FromStr for AdbWaitForState {
+impl FromStr for DeviceState {
     type Err = AdbError;
 
+    /// Parses a device state, `no permissions` may be followed by details.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "device" => Ok(AdbWaitForState::Device),
-            "recovery" => Ok(AdbWaitForState::Recovery),
-            "rescue" => Ok(AdbWaitForState::Rescue),
-            "sideload" => Ok(AdbWaitForState::Sideload),
-            "bootloader" => Ok(AdbWaitForState::Bootloader),
-            "disconnect" => Ok(AdbWaitForState::Disconnect),
+            "offline" => Ok(DeviceState::Offline),
+            "bootloader" => Ok(DeviceState::Bootloader),
+            "device" => Ok(DeviceState::Device),
+            "host" => Ok(DeviceState::Host),
+            "recovery" => Ok(DeviceState::Recovery),
+            "rescue" => Ok(DeviceState::Rescue),
+            "sideload" => Ok(DeviceState::Sideload),
+            "unauthorized" => Ok(DeviceState::Unauthorized),
+            "authorizing" => Ok(DeviceState::Authorizing),
+            "connecting" => Ok(DeviceState::Connecting),
+            "detached" => Ok(DeviceState::Detached),
+            "disconnect" => Ok(DeviceState::Disconnect),
+            s if s.starts_with("no permissions") => Ok(DeviceState::NoPermissions),
             _ => Err(AdbError::Parse(ParseError::with_description(
                 s,
-                "AdbDeviceState",
+                "DeviceState",
                 "Unknown device state",
             ))),
         }
@@ -91,6 +157,17 @@ pub enum AdbWaitForTransport {
     Any,
 }
 
+impl AdbWaitForTransport {
+    /// Returns all the transports, in declaration order.
+    pub fn all() -> &'static [AdbWaitForTransport] {
+        &[
+            AdbWaitForTransport::Usb,
+            AdbWaitForTransport::Local,
+            AdbWaitForTransport::Any,
+        ]
+    }
+}
+
 impl AsRef<OsStr> for AdbWaitForTransport {
     fn as_ref(&self) -> &OsStr {
         match self {
@@ -135,13 +212,13 @@ impl FromStr for AdbWaitForTransport {
 pub struct AdbWaitFor<'a> {
     acb: AdbCommandBuilder<'a>,
     /// `STATE`: `device`, `recovery`, `rescue`, `sideload`, `bootloader`, or `disconnect`.
-    state: AdbWaitForState,
+    state: DeviceState,
     /// `TRANSPORT`: `usb`, `local`, or `any` (default=`any`).
     transport: Option<AdbWaitForTransport>,
 }
 
 impl<'a> AdbWaitFor<'a> {
-    fn new(acb: AdbCommandBuilder<'a>, state: AdbWaitForState) -> Self {
+    fn new(acb: AdbCommandBuilder<'a>, state: DeviceState) -> Self {
         Self {
             acb,
             state,
@@ -152,7 +229,7 @@ impl<'a> AdbWaitFor<'a> {
     /// `STATE`: `device`, `recovery`, `rescue`, `sideload`, `bootloader`, or `disconnect`.
     ///
     /// The previous state will be overwritten.
-    pub fn state(mut self, state: AdbWaitForState) -> Self {
+    pub fn state(mut self, state: DeviceState) -> Self {
         self.state = state;
         self
     }
@@ -164,6 +241,92 @@ impl<'a> AdbWaitFor<'a> {
         self.transport = Some(transport);
         self
     }
+
+    /// Waits for the device to be in the given state, then verifies it with `get-state`,
+    /// since `wait-for` may return as soon as the transport appears.
+    ///
+    /// The verification is retried until the state is reached or `timeout` expires.
+    /// For [`DeviceState::Disconnect`], the state is reached when `get-state` fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::Timeout`] if the state isn't reached within `timeout`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use adbr::Adb;
+    /// # use adbr::command::DeviceState;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.wait_for(DeviceState::Device)
+    ///     .wait_verified(Duration::from_secs(60))
+    ///     .expect("device not ready in 60 seconds");
+    /// ```
+    pub fn wait_verified(self, timeout: Duration) -> AdbResult<()> {
+        let deadline = Instant::now() + timeout;
+        let state = self.state;
+        let acb = self.acb.clone();
+        let output = self
+            .output_timeout(timeout)
+            .map_err(|e| map_timeout(e, timeout))?;
+        process::check(&output)?;
+        verify_state(state, deadline, timeout, |remaining| {
+            acb.clone().get_state().run_timeout(remaining)
+        })
+    }
+}
+
+/// The interval between two `get-state` polls of [`AdbWaitFor::wait_verified`].
+const VERIFY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Replaces the duration of a timeout error (`e`) with the overall `timeout`.
+fn map_timeout(e: AdbError, timeout: Duration) -> AdbError {
+    match e {
+        AdbError::Timeout(_) => AdbError::Timeout(timeout),
+        e => e,
+    }
+}
+
+/// Whether the result of `get-state` (`current`) means the device is in the `expected` state.
+fn reached(expected: DeviceState, current: &AdbResult<DeviceState>) -> bool {
+    match (expected, current) {
+        (DeviceState::Disconnect, current) => {
+            matches!(current, Err(AdbError::NonZeroExit { .. }))
+        }
+        (expected, Ok(current)) => expected == *current,
+        (_, Err(_)) => false,
+    }
+}
+
+/// Polls `get_state` (with the remaining time) until the device is in the `expected` state.
+///
+/// A failed `get-state` (e.g. the device is gone for a moment) is retried, other errors are returned.
+fn verify_state<F>(
+    expected: DeviceState,
+    deadline: Instant,
+    timeout: Duration,
+    mut get_state: F,
+) -> AdbResult<()>
+where
+    F: FnMut(Duration) -> AdbResult<DeviceState>,
+{
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let current = get_state(remaining);
+        if reached(expected, &current) {
+            return Ok(());
+        }
+        match current {
+            Ok(_) | Err(AdbError::NonZeroExit { .. }) => {}
+            Err(e) => return Err(map_timeout(e, timeout)),
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(AdbError::Timeout(timeout));
+        }
+        thread::sleep(VERIFY_INTERVAL.min(remaining));
+    }
 }
 
 impl<'a> AdbCommand for AdbWaitFor<'a> {
@@ -179,6 +342,23 @@ impl<'a> AdbCommand for AdbWaitFor<'a> {
         cmd.arg(arg);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        if self.state.can_wait_for() {
+            Ok(())
+        } else {
+            Err(ParseError::with_description(
+                self.state,
+                "AdbWaitFor",
+                "State not accepted by `wait-for`",
+            )
+            .into())
+        }
+    }
 }
 
 impl Adb {
@@ -190,16 +370,16 @@ impl Adb {
     ///
     /// ```no_run
     /// # use adbr::{Adb, AdbCommand};
-    /// # use adbr::command::AdbWaitForState;
+    /// # use adbr::command::DeviceState;
     /// # let adb = Adb::new().unwrap();
-    /// adb.wait_for(AdbWaitForState::Device)
+    /// adb.wait_for(DeviceState::Device)
     ///     .build()
     ///     .arg("shell")
     ///     .arg("getprop")
     ///     .status()
     ///     .expect("`adb wait-for-device shell getprop` failed");
     /// ```
-    pub fn wait_for(&self, state: AdbWaitForState) -> AdbWaitFor {
+    pub fn wait_for(&self, state: DeviceState) -> AdbWaitFor<'_> {
         AdbWaitFor::new(self.command(), state)
     }
 }
@@ -208,7 +388,7 @@ impl<'a> AdbCommandBuilder<'a> {
     /// - `wait-for [-TRANSPORT] -STATE...`: Wait for device to be in a given state.
     ///
     /// See [`Adb::wait_for`] for more information.
-    pub fn wait_for(self, state: AdbWaitForState) -> AdbWaitFor<'a> {
+    pub fn wait_for(self, state: DeviceState) -> AdbWaitFor<'a> {
         AdbWaitFor::new(self, state)
     }
 }
@@ -217,12 +397,34 @@ impl<'a> AdbCommandBuilder<'a> {
 #[derive(Debug, Clone)]
 pub struct AdbGetState<'a>(AdbCommandBuilder<'a>);
 
+impl<'a> AdbGetState<'a> {
+    /// Executes `get-state` and parses the state of the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NonZeroExit`] if there is no device, e.g. `error: no devices/emulators found`.
+    pub fn run(self) -> AdbResult<DeviceState> {
+        process::stdout_string(self.output()?)?.trim().parse()
+    }
+
+    /// Like [`Self::run`], but `get-state` is killed if it doesn't finish within `timeout`.
+    fn run_timeout(self, timeout: Duration) -> AdbResult<DeviceState> {
+        process::stdout_string(self.output_timeout(timeout)?)?
+            .trim()
+            .parse()
+    }
+}
+
 impl<'a> AdbCommand for AdbGetState<'a> {
     fn build(self) -> Command {
         let mut cmd = self.0.build();
         cmd.arg("get-state");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -239,7 +441,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb get-state` failed");
     /// ```
-    pub fn get_state(&self) -> AdbGetState {
+    pub fn get_state(&self) -> AdbGetState<'_> {
         AdbGetState(self.command())
     }
 }
@@ -263,6 +465,10 @@ impl<'a> AdbCommand for AdbGetSerialNo<'a> {
         cmd.arg("get-serialno");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -279,7 +485,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb get-serialno` failed");
     /// ```
-    pub fn get_serial_no(&self) -> AdbGetSerialNo {
+    pub fn get_serial_no(&self) -> AdbGetSerialNo<'_> {
         AdbGetSerialNo(self.command())
     }
 }
@@ -303,6 +509,10 @@ impl<'a> AdbCommand for AdbGetDevPath<'a> {
         cmd.arg("get-devpath");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -319,7 +529,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb get-devpath` failed");
     /// ```
-    pub fn get_dev_path(&self) -> AdbGetDevPath {
+    pub fn get_dev_path(&self) -> AdbGetDevPath<'_> {
         AdbGetDevPath(self.command())
     }
 }
@@ -367,6 +577,10 @@ impl<'a> AdbCommand for AdbRemount<'a> {
         }
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
 }
 
 impl Adb {
@@ -384,7 +598,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb remount -R` failed");
     /// ```
-    pub fn remount(&self) -> AdbRemount {
+    pub fn remount(&self) -> AdbRemount<'_> {
         AdbRemount::new(self.command())
     }
 }
@@ -407,6 +621,18 @@ pub enum AdbRebootTarget {
     SideloadAutoReboot,
 }
 
+impl AdbRebootTarget {
+    /// Returns all the reboot targets, in declaration order.
+    pub fn all() -> &'static [AdbRebootTarget] {
+        &[
+            AdbRebootTarget::Bootloader,
+            AdbRebootTarget::Recovery,
+            AdbRebootTarget::Sideload,
+            AdbRebootTarget::SideloadAutoReboot,
+        ]
+    }
+}
+
 impl AsRef<OsStr> for AdbRebootTarget {
     fn as_ref(&self) -> &OsStr {
         match self {
@@ -447,12 +673,15 @@ impl FromStr for AdbRebootTarget {
     }
 }
 
+impl_try_from_os_str!(DeviceState, AdbWaitForTransport, AdbRebootTarget);
+impl_value_enum!(DeviceState, AdbWaitForTransport, AdbRebootTarget);
+
 /// - `reboot [bootloader|recovery|sideload|sideload-auto-reboot]`: Reboot the device; defaults to booting system image but supports bootloader and recovery too.
 #[derive(Debug, Clone)]
 pub struct AdbReboot<'a> {
     acb: AdbCommandBuilder<'a>,
     /// The target to reboot, defaults to booting system image.
-    target: Option<AdbRebootTarget>,
+    target: Option<OsString>,
 }
 
 impl<'a> AdbReboot<'a> {
@@ -464,7 +693,31 @@ impl<'a> AdbReboot<'a> {
     ///
     /// The previous target will be overwritten.
     pub fn target(mut self, target: AdbRebootTarget) -> Self {
-        self.target = Some(target);
+        self.target = Some(target.as_ref().to_os_string());
+        self
+    }
+
+    /// An arbitrary reboot reason, such as the vendor-specific `edl` or `fastboot`.
+    ///
+    /// Prefer [`Self::target`] for the targets in [`AdbRebootTarget`],
+    /// the raw reason is passed to the device as is, without any validation.
+    ///
+    /// The previous target will be overwritten.
+    ///
+    /// # Examples
+    ///
+    /// `adb reboot edl`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.reboot()
+    ///     .target_raw("edl")
+    ///     .status()
+    ///     .expect("`adb reboot edl` failed");
+    /// ```
+    pub fn target_raw<S: AsRef<OsStr>>(mut self, target: S) -> Self {
+        self.target = Some(target.as_ref().to_os_string());
         self
     }
 }
@@ -478,6 +731,14 @@ impl<'a> AdbCommand for AdbReboot<'a> {
         }
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(&self.target)
+    }
 }
 
 impl Adb {
@@ -494,7 +755,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb reboot` failed");
     /// ```
-    pub fn reboot(&self) -> AdbReboot {
+    pub fn reboot(&self) -> AdbReboot<'_> {
         AdbReboot::new(self.command())
     }
 }
@@ -545,6 +806,14 @@ impl<'a, S: AsRef<OsStr>> AdbCommand for AdbSideload<'a, S> {
         }
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(&self.ota_package)
+    }
 }
 
 impl Adb {
@@ -573,7 +842,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb sideload OTAPACKAGE` failed");
     /// ```
-    pub fn sideload(&self) -> AdbSideload<&str> {
+    pub fn sideload(&self) -> AdbSideload<'_, &str> {
         AdbSideload::new(self.command())
     }
 }
@@ -598,6 +867,10 @@ impl<'a> AdbCommand for AdbSideloadAutoReboot<'a> {
         cmd.arg("sideload-auto-reboot");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -614,7 +887,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb sideload-auto-reboot` failed");
     /// ```
-    pub fn sideload_auto_reboot(&self) -> AdbSideloadAutoReboot {
+    pub fn sideload_auto_reboot(&self) -> AdbSideloadAutoReboot<'_> {
         AdbSideloadAutoReboot(self.command())
     }
 }
@@ -638,6 +911,10 @@ impl<'a> AdbCommand for AdbRoot<'a> {
         cmd.arg("root");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -654,7 +931,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb root` failed");
     /// ```
-    pub fn root(&self) -> AdbRoot {
+    pub fn root(&self) -> AdbRoot<'_> {
         AdbRoot(self.command())
     }
 }
@@ -668,6 +945,65 @@ impl<'a> AdbCommandBuilder<'a> {
     }
 }
 
+/// Whether adbd runs as root, see [`Adb::root_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AdbRootStatus {
+    /// adbd runs as root.
+    Root,
+    /// adbd doesn't run as root, but `root` is possible (`ro.debuggable=1`).
+    NotRoot,
+    /// adbd doesn't run as root, and `root` is impossible, e.g. on user builds.
+    Unavailable,
+}
+
+/// Parses the output of `id` (e.g. `uid=0(root) gid=0(root)`), returning whether the uid is 0.
+fn parse_id_is_root(id: &str) -> AdbResult<bool> {
+    id.split_whitespace()
+        .find_map(|field| field.strip_prefix("uid="))
+        .and_then(|uid| uid.split('(').next())
+        .and_then(|uid| uid.parse::<u32>().ok())
+        .map(|uid| uid == 0)
+        .ok_or_else(|| ParseError::with_description(id.trim(), "uid", "Unknown `id` output").into())
+}
+
+impl Adb {
+    /// Whether adbd runs as root, by parsing the uid in `adb shell id`.
+    ///
+    /// Useful to skip a redundant [`Adb::root`] and the transport reset it causes.
+    /// See [`Adb::root_status`] to tell whether `root` is possible at all.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// if !adb.is_root().unwrap() {
+    ///     adb.root().status().expect("`adb root` failed");
+    /// }
+    /// ```
+    pub fn is_root(&self) -> AdbResult<bool> {
+        let id = process::stdout_string(self.shell().arg("id").output()?)?;
+        parse_id_is_root(&id)
+    }
+
+    /// Whether adbd runs as root, and if not, whether `root` is possible.
+    ///
+    /// Runs `adb shell id`, then `adb shell getprop ro.debuggable` if adbd isn't root,
+    /// since adbd can only restart as root on debuggable (`userdebug` or `eng`) builds.
+    pub fn root_status(&self) -> AdbResult<AdbRootStatus> {
+        if self.is_root()? {
+            return Ok(AdbRootStatus::Root);
+        }
+        let debuggable =
+            process::stdout_string(self.shell().arg("getprop").arg("ro.debuggable").output()?)?;
+        Ok(if debuggable.trim() == "1" {
+            AdbRootStatus::NotRoot
+        } else {
+            AdbRootStatus::Unavailable
+        })
+    }
+}
+
 /// - `unroot`: Restart adbd without root permissions.
 #[derive(Debug, Clone)]
 pub struct AdbUnroot<'a>(AdbCommandBuilder<'a>);
@@ -678,6 +1014,10 @@ impl<'a> AdbCommand for AdbUnroot<'a> {
         cmd.arg("unroot");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -694,7 +1034,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb unroot` failed");
     /// ```
-    pub fn unroot(&self) -> AdbUnroot {
+    pub fn unroot(&self) -> AdbUnroot<'_> {
         AdbUnroot(self.command())
     }
 }
@@ -718,6 +1058,10 @@ impl<'a> AdbCommand for AdbUsb<'a> {
         cmd.arg("usb");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -734,7 +1078,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb usb` failed");
     /// ```
-    pub fn usb(&self) -> AdbUsb {
+    pub fn usb(&self) -> AdbUsb<'_> {
         AdbUsb(self.command())
     }
 }
@@ -777,6 +1121,10 @@ impl<'a> AdbCommand for AdbTcpIp<'a> {
         cmd.arg(self.port.to_string());
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
 }
 
 impl Adb {
@@ -793,7 +1141,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb tcpip 5555` failed");
     /// ```
-    pub fn tcp_ip(&self, port: u16) -> AdbTcpIp {
+    pub fn tcp_ip(&self, port: u16) -> AdbTcpIp<'_> {
         AdbTcpIp::new(self.command(), port)
     }
 }
@@ -806,3 +1154,186 @@ impl<'a> AdbCommandBuilder<'a> {
         AdbTcpIp::new(self, port)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_all() {
+        assert_eq!(DeviceState::all().len(), 13);
+        for &state in DeviceState::all() {
+            assert_eq!(state.to_string().parse::<DeviceState>().unwrap(), state);
+        }
+        for &transport in AdbWaitForTransport::all() {
+            assert_eq!(
+                transport
+                    .to_string()
+                    .parse::<AdbWaitForTransport>()
+                    .unwrap(),
+                transport
+            );
+        }
+        for &target in AdbRebootTarget::all() {
+            assert_eq!(
+                target.to_string().parse::<AdbRebootTarget>().unwrap(),
+                target
+            );
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_try_from_os_str() {
+        assert_eq!(
+            AdbWaitForState::try_from(OsStr::new("recovery")).unwrap(),
+            DeviceState::Recovery
+        );
+        assert_eq!(
+            AdbWaitForTransport::try_from(OsStr::new("usb")).unwrap(),
+            AdbWaitForTransport::Usb
+        );
+        assert_eq!(
+            AdbRebootTarget::try_from(OsStr::new("bootloader")).unwrap(),
+            AdbRebootTarget::Bootloader
+        );
+        assert!(AdbRebootTarget::try_from(OsStr::new("edl"))
+            .unwrap_err()
+            .is_parse());
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let invalid = OsStr::from_bytes(b"us\xffb");
+            assert!(AdbWaitForTransport::try_from(invalid)
+                .unwrap_err()
+                .is_parse());
+        }
+    }
+
+    #[test]
+    fn test_reboot_target() {
+        let adb = Adb::default();
+        assert_eq!(args(adb.reboot()), ["reboot"]);
+        assert_eq!(
+            args(adb.reboot().target(AdbRebootTarget::Bootloader)),
+            ["reboot", "bootloader"]
+        );
+        assert_eq!(args(adb.reboot().target_raw("edl")), ["reboot", "edl"]);
+        assert_eq!(
+            args(
+                adb.reboot()
+                    .target_raw("edl")
+                    .target(AdbRebootTarget::Recovery)
+            ),
+            ["reboot", "recovery"]
+        );
+    }
+
+    #[test]
+    fn test_parse_id_is_root() {
+        assert!(
+            parse_id_is_root("uid=0(root) gid=0(root) groups=0(root) context=u:r:su:s0\n").unwrap()
+        );
+        assert!(!parse_id_is_root(
+            "uid=2000(shell) gid=2000(shell) groups=2000(shell),1004(input) context=u:r:shell:s0\n"
+        )
+        .unwrap());
+        assert!(parse_id_is_root("uid=0 gid=0").unwrap());
+        assert!(parse_id_is_root("/system/bin/sh: id: not found").is_err());
+        assert!(parse_id_is_root("").is_err());
+    }
+
+    #[test]
+    fn test_device_state_from_str() {
+        // the round trip of every state is covered by `test_all`
+        assert_eq!(
+            "no permissions (user in plugdev group; are your udev rules wrong?)"
+                .parse::<DeviceState>()
+                .unwrap(),
+            DeviceState::NoPermissions
+        );
+        assert!("unknown".parse::<DeviceState>().is_err());
+        assert!(DeviceState::Disconnect.can_wait_for());
+        assert!(!DeviceState::Offline.can_wait_for());
+    }
+
+    #[test]
+    fn test_wait_for() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(
+                adb.wait_for(DeviceState::Sideload)
+                    .transport(AdbWaitForTransport::Usb)
+            ),
+            ["wait-for-usb-sideload"]
+        );
+        for &state in DeviceState::all() {
+            let result = adb.wait_for(state).try_build();
+            assert_eq!(result.is_ok(), state.can_wait_for(), "{state}");
+        }
+        assert!(matches!(
+            adb.wait_for(DeviceState::Offline).try_build(),
+            Err(AdbError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_state() {
+        use crate::command::tests::output;
+
+        let failed =
+            || process::check(&output(1, "", "error: no devices/emulators found")).unwrap_err();
+
+        let timeout = Duration::from_secs(5);
+        let mut sequence = vec![
+            Err(failed()),
+            Ok(DeviceState::Offline),
+            Ok(DeviceState::Authorizing),
+            Ok(DeviceState::Device),
+        ]
+        .into_iter();
+        let mut polls = 0;
+        verify_state(
+            DeviceState::Device,
+            Instant::now() + timeout,
+            timeout,
+            |_| {
+                polls += 1;
+                sequence.next().unwrap()
+            },
+        )
+        .unwrap();
+        assert_eq!(polls, 4);
+
+        let mut sequence = vec![Ok(DeviceState::Device), Err(failed())].into_iter();
+        verify_state(
+            DeviceState::Disconnect,
+            Instant::now() + timeout,
+            timeout,
+            |_| sequence.next().unwrap(),
+        )
+        .unwrap();
+
+        let timeout = Duration::from_millis(300);
+        assert!(matches!(
+            verify_state(
+                DeviceState::Recovery,
+                Instant::now() + timeout,
+                timeout,
+                |_| Ok(DeviceState::Device),
+            ),
+            Err(AdbError::Timeout(t)) if t == timeout
+        ));
+
+        assert!(matches!(
+            verify_state(
+                DeviceState::Device,
+                Instant::now() + timeout,
+                timeout,
+                |_| "unknown".parse(),
+            ),
+            Err(AdbError::Parse(_))
+        ));
+    }
+}
@@ -9,8 +9,8 @@
 use std::ffi::OsStr;
 use std::process::Command;
 
-use crate::command::AdbCommandBuilder;
-use crate::{Adb, AdbCommand};
+use crate::command::{process, AdbCommandBuilder};
+use crate::{Adb, AdbCommand, AdbResult};
 
 /// `disable-verity`: Disable dm-verity checking on userdebug builds.
 #[derive(Debug, Clone)]
@@ -22,6 +22,10 @@ impl<'a> AdbCommand for AdbDisableVerity<'a> {
         cmd.arg("disable-verity");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -38,7 +42,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb disable-verity` failed");
     /// ```
-    pub fn disable_verity(&self) -> AdbDisableVerity {
+    pub fn disable_verity(&self) -> AdbDisableVerity<'_> {
         AdbDisableVerity(self.command())
     }
 }
@@ -62,6 +66,10 @@ impl<'a> AdbCommand for AdbEnableVerity<'a> {
         cmd.arg("enable-verity");
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.0)
+    }
 }
 
 impl Adb {
@@ -78,7 +86,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb enable-verity` failed");
     /// ```
-    pub fn enable_verity(&self) -> AdbEnableVerity {
+    pub fn enable_verity(&self) -> AdbEnableVerity<'_> {
         AdbEnableVerity(self.command())
     }
 }
@@ -118,6 +126,14 @@ impl<'a, S: AsRef<OsStr>> AdbCommand for AdbKeygen<'a, S> {
         cmd.arg("keygen").arg(self.file);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.file])
+    }
 }
 
 impl Adb {
@@ -134,7 +150,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb keygen /path/to/private/key` failed");
     /// ```
-    pub fn keygen<S: AsRef<OsStr>>(&self, file: S) -> AdbKeygen<S> {
+    pub fn keygen<S: AsRef<OsStr>>(&self, file: S) -> AdbKeygen<'_, S> {
         AdbKeygen::new(self.command(), file)
     }
 }
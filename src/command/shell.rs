@@ -1,34 +1,155 @@
 //! Shell commands
 //!
 //! - `shell [-e ESCAPE] [-n] [-Tt] [-x] [COMMAND...]`:
-//!     Run remote shell command (interactive shell if no command given).
+//!   Run remote shell command (interactive shell if no command given).
 //! - `emu COMMAND`: Run emulator console `COMMAND`.
 //!
 //! See [Shell Commands](https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/master/docs/user/adb.1.md#shell).
 
 use std::ffi::{OsStr, OsString};
-use std::process::Command;
+use std::process::{Command, Output};
 
-use crate::command::AdbCommandBuilder;
-use crate::{Adb, AdbCommand};
+use crate::command::features::Feature;
+use crate::command::{process, AdbCommandBuilder};
+use crate::error::ParseError;
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
 
-/// Whether to allocate a pty.
+/// Whether to allocate a pty, see [`AdbShell::pty`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum AdbPtyAllocation {
+pub enum PtyMode {
     /// `-T`: Disable pty allocation.
     Disable,
     /// `-t`: Allocate a pty if on a tty.
-    Enable,
+    Auto,
     /// `-tt`: Force pty allocation.
     Force,
 }
 
-impl AsRef<OsStr> for AdbPtyAllocation {
+/// The flavor of `su` on the device, which decides how a command is run as root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AdbSuFlavor {
+    /// `su 0 sh -c COMMAND`: The `su` of AOSP `userdebug` and `eng` builds, which takes a uid.
+    Aosp,
+    /// `su -c COMMAND`: The `su` of Magisk and most other root solutions.
+    Magisk,
+}
+
+impl AdbSuFlavor {
+    /// Wraps the remote shell command (`command`) to run as root.
+    ///
+    /// `command` is joined by spaces as the remote shell does, then quoted as a single argument of `su`,
+    /// so that compound commands like `a && b` run entirely as root.
+    /// An empty `command` starts an interactive root shell.
+    fn wrap(self, command: &[OsString]) -> Vec<OsString> {
+        if command.is_empty() {
+            return vec!["su".into()];
+        }
+        let command = quote(&join(command));
+        let su: &[&str] = match self {
+            AdbSuFlavor::Aosp => &["su", "0", "sh", "-c"],
+            AdbSuFlavor::Magisk => &["su", "-c"],
+        };
+        su.iter()
+            .map(OsString::from)
+            .chain([OsString::from(command)])
+            .collect()
+    }
+}
+
+/// Joins the arguments of a remote shell command by spaces, like adb does.
+///
+/// Non-UTF-8 arguments are converted lossily.
+fn join(command: &[OsString]) -> String {
+    command
+        .iter()
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quotes `s` as a single word for the remote (POSIX) shell.
+pub(crate) fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// The stdin of [`AdbShell::script`], i.e. the script terminated by an `exit` line.
+fn script_input(script: &str) -> Vec<u8> {
+    let mut input = script.to_string();
+    if !input.ends_with('\n') {
+        input.push('\n');
+    }
+    input.push_str("exit\n");
+    input.into_bytes()
+}
+
+/// The marker echoed after the command to recover the remote exit code without `shell_v2`.
+const EXIT_CODE_MARKER: &str = "__rc:";
+
+/// The exit code and output of a remote shell command, see [`AdbShell::run`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ShellResult {
+    /// The exit code of the remote command.
+    pub exit_code: i32,
+    /// The stdout of the remote command (lossy).
+    pub stdout: String,
+    /// The stderr of the remote command (lossy), always empty without `shell_v2`.
+    pub stderr: String,
+}
+
+impl ShellResult {
+    /// Whether the remote command exited with `0`.
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    /// Parses the output of `shell` with `shell_v2`,
+    /// where adb exits with the remote exit code and separates stdout and stderr.
+    fn from_output(output: Output) -> AdbResult<Self> {
+        let exit_code = match output.status.code() {
+            Some(code) => code,
+            None => return process::check(&output).map(|_| Self::default()),
+        };
+        Ok(Self {
+            exit_code,
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    /// Parses the output of `shell -x` with [`EXIT_CODE_MARKER`] echoed after the command,
+    /// folding stderr into stdout.
+    fn from_marked_output(output: Output) -> AdbResult<Self> {
+        let mut stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let Some(index) = stdout.rfind(EXIT_CODE_MARKER) else {
+            // adb failed before running the command, e.g. no device
+            process::check(&output)?;
+            return Err(ParseError::with_description(
+                stdout.trim(),
+                "ShellResult",
+                "Missing exit code marker",
+            )
+            .into());
+        };
+        let code = stdout[index + EXIT_CODE_MARKER.len()..].trim();
+        let exit_code = code
+            .parse()
+            .map_err(|_| ParseError::with_description(code, "ShellResult", "Invalid exit code"))?;
+        stdout.truncate(index);
+        stdout.push_str(&String::from_utf8_lossy(&output.stderr));
+        Ok(Self {
+            exit_code,
+            stdout,
+            stderr: String::new(),
+        })
+    }
+}
+
+impl AsRef<OsStr> for PtyMode {
     fn as_ref(&self) -> &OsStr {
         match self {
-            AdbPtyAllocation::Disable => OsStr::new("-T"),
-            AdbPtyAllocation::Enable => OsStr::new("-t"),
-            AdbPtyAllocation::Force => OsStr::new("-tt"),
+            PtyMode::Disable => OsStr::new("-T"),
+            PtyMode::Auto => OsStr::new("-t"),
+            PtyMode::Force => OsStr::new("-tt"),
         }
     }
 }
@@ -50,11 +171,17 @@ pub struct AdbShell<'a> {
     /// `-T`: Disable pty allocation.
     /// `-t`: Allocate a pty if on a tty.
     /// `-tt`: force pty allocation.
-    t: Option<AdbPtyAllocation>,
+    t: Option<PtyMode>,
     /// `-x`: Disable remote exit codes and stdout/stderr separation.
     x: bool,
     /// `COMMAND...`: The command to run.
     command: Vec<OsString>,
+    /// Run the command as root with `su`.
+    su: Option<AdbSuFlavor>,
+    /// The columns and rows of the allocated pty.
+    pty_size: Option<(u16, u16)>,
+    /// Bound the remote command with `timeout SECS`.
+    remote_timeout: Option<u32>,
 }
 
 impl<'a> AdbShell<'a> {
@@ -69,6 +196,9 @@ impl<'a> AdbShell<'a> {
             t: None,
             x: false,
             command: Vec::new(),
+            su: None,
+            pty_size: None,
+            remote_timeout: None,
         }
     }
 
@@ -84,23 +214,34 @@ impl<'a> AdbShell<'a> {
         self
     }
 
+    /// `-T`/`-t`/`-tt`: Set the pty allocation mode.
+    ///
+    /// The flags are mutually exclusive, the previous mode will be overwritten.
+    pub fn pty(mut self, mode: PtyMode) -> Self {
+        self.t = Some(mode);
+        self
+    }
+
     /// `-T`: Disable pty allocation.
+    ///
+    /// Same as [`AdbShell::pty`] with [`PtyMode::Disable`].
     #[allow(non_snake_case)]
-    pub fn T(mut self) -> Self {
-        self.t = Some(AdbPtyAllocation::Disable);
-        self
+    pub fn T(self) -> Self {
+        self.pty(PtyMode::Disable)
     }
 
     /// `-t`: Allocate a pty if on a tty.
-    pub fn t(mut self) -> Self {
-        self.t = Some(AdbPtyAllocation::Enable);
-        self
+    ///
+    /// Same as [`AdbShell::pty`] with [`PtyMode::Auto`].
+    pub fn t(self) -> Self {
+        self.pty(PtyMode::Auto)
     }
 
     /// `-tt`: Force pty allocation.
-    pub fn tt(mut self) -> Self {
-        self.t = Some(AdbPtyAllocation::Force);
-        self
+    ///
+    /// Same as [`AdbShell::pty`] with [`PtyMode::Force`].
+    pub fn tt(self) -> Self {
+        self.pty(PtyMode::Force)
     }
 
     /// `-x`: Disable remote exit codes and stdout/stderr separation.
@@ -125,10 +266,225 @@ impl<'a> AdbShell<'a> {
             .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
         self
     }
+
+    /// Runs the command as root with `su` of the given flavor,
+    /// or starts an interactive root shell if no command given.
+    ///
+    /// The command is quoted as a whole, so shell syntax in it (e.g. `&&`, `|`, `>`)
+    /// is interpreted by the root shell rather than the shell running `su`.
+    ///
+    /// The previous flavor will be overwritten.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell su -c 'ls /data && id'`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// use adbr::command::AdbSuFlavor;
+    ///
+    /// adb.shell()
+    ///     .arg("ls /data && id")
+    ///     .su(AdbSuFlavor::Magisk)
+    ///     .status()
+    ///     .expect("`adb shell su -c 'ls /data && id'` failed");
+    /// ```
+    pub fn su(mut self, flavor: AdbSuFlavor) -> Self {
+        self.su = Some(flavor);
+        self
+    }
+
+    /// Sets the size of the allocated pty by prefixing the command with `stty cols COLS rows ROWS;`.
+    ///
+    /// # Note
+    ///
+    /// adb has no option for the pty size, so the prefix is only a hint with some limitations:
+    ///
+    /// - It's only emitted if a pty is requested with [`Self::t`] or [`Self::tt`],
+    ///   whether `-t` actually allocates a pty (i.e. stdin is a tty) is not checked.
+    /// - It's only emitted if a command is given, an interactive shell keeps the default size.
+    /// - The remote shell must provide `stty`.
+    ///
+    /// The previous size will be overwritten.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell -tt stty cols 120 rows 40; top`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.shell()
+    ///     .tt()
+    ///     .pty_size(120, 40)
+    ///     .arg("top")
+    ///     .status()
+    ///     .expect("`adb shell -tt stty cols 120 rows 40; top` failed");
+    /// ```
+    pub fn pty_size(mut self, cols: u16, rows: u16) -> Self {
+        self.pty_size = Some((cols, rows));
+        self
+    }
+
+    /// Bounds the remote command with the `timeout` utility of the device,
+    /// which kills the command on the device after `secs` seconds.
+    ///
+    /// The command is joined and quoted as a whole, like `timeout SECS sh -c COMMAND`,
+    /// so compound commands are bounded entirely. Nothing is emitted without a command.
+    ///
+    /// Unlike [`AdbCommand::output_timeout`], which kills the local adb process,
+    /// the remote command itself is stopped, and adb exits normally
+    /// (with the exit code `124` of `timeout` if it's recovered, see [`Self::run`]).
+    ///
+    /// # Note
+    ///
+    /// The device must provide `timeout`, e.g. by toybox on Android 6.0 and later.
+    ///
+    /// The previous timeout will be overwritten.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell timeout 10 sh -c 'logcat | grep ActivityManager'`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.shell()
+    ///     .arg("logcat | grep ActivityManager")
+    ///     .remote_timeout(10)
+    ///     .status()
+    ///     .expect("`adb shell timeout 10 sh -c 'logcat | grep ActivityManager'` failed");
+    /// ```
+    pub fn remote_timeout(mut self, secs: u32) -> Self {
+        self.remote_timeout = Some(secs);
+        self
+    }
+
+    /// Wraps the command with `timeout SECS sh -c COMMAND` if [`Self::remote_timeout`] is set,
+    /// so that commands appended later (e.g. the exit code marker) are not bounded.
+    fn apply_remote_timeout(&mut self) {
+        if let Some(secs) = self.remote_timeout.take() {
+            if !self.command.is_empty() {
+                let command = quote(&join(&self.command));
+                self.command = vec![
+                    "timeout".into(),
+                    secs.to_string().into(),
+                    "sh".into(),
+                    "-c".into(),
+                    command.into(),
+                ];
+            }
+        }
+    }
+
+    /// Executes the command, collecting the remote exit code and output together.
+    ///
+    /// The exit code is recovered in one of two modes:
+    ///
+    /// - `shell_v2` (default): adb exits with the remote exit code,
+    ///   and the remote stdout and stderr are kept separated.
+    /// - Legacy (if [`Self::x`] is set): `; echo "__rc:$?"` is appended to the command,
+    ///   then the marker line is stripped from stdout and parsed as the exit code.
+    ///   The remote streams aren't separated, so stderr is folded into stdout.
+    ///
+    /// [`Adb::shell_run`] picks the mode by the features of the device,
+    /// use this method to force one of them.
+    ///
+    /// # Errors
+    ///
+    /// In the legacy mode, returns [`AdbError::NonZeroExit`]
+    /// if adb fails before running the command (e.g. no device),
+    /// or [`AdbError::Parse`] if the marker is missing.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell -x ls /data; echo "__rc:$?"`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let result = adb.shell().x().arg("ls /data").run().unwrap();
+    /// assert!(result.success(), "{}", result.stdout);
+    /// ```
+    pub fn run(mut self) -> AdbResult<ShellResult> {
+        if self.x {
+            self.apply_remote_timeout();
+            self.command
+                .push(format!("; echo \"{}$?\"", EXIT_CODE_MARKER).into());
+            ShellResult::from_marked_output(self.output()?)
+        } else {
+            ShellResult::from_output(self.output()?)
+        }
+    }
+
+    /// Runs a (multi-line) script with a remote `sh -s`, sending it over stdin,
+    /// so newlines and quotes reach the remote shell unchanged.
+    ///
+    /// The arguments of the command (see [`Self::arg`]) are passed to the script
+    /// as its positional parameters (`$1`, `$2`, ...), and [`Self::n`] is ignored.
+    /// An `exit` line is appended to the script, so the remote shell exits at the end of it
+    /// even if the device doesn't close stdin without `shell_v2`.
+    ///
+    /// Returns the output like [`AdbCommand::output`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let output = adb
+    ///     .shell()
+    ///     .arg("com.example.app")
+    ///     .script("pid=$(pidof \"$1\")\necho \"pid: $pid\"")
+    ///     .unwrap();
+    /// println!("{}", String::from_utf8_lossy(&output.stdout));
+    /// ```
+    pub fn script(self, script: &str) -> AdbResult<Output> {
+        process::traced(
+            self.into_script().try_build()?,
+            |output| Some(output.status),
+            |cmd| process::output_with_stdin(cmd, script_input(script)),
+        )
+    }
+
+    /// Turns the command into `sh -s ARGS...`, reading the script from stdin.
+    fn into_script(mut self) -> Self {
+        self.n = false;
+        self.command.splice(0..0, ["sh".into(), "-s".into()]);
+        self
+    }
+
+    /// Executes the command, returning its stdout as raw bytes if adb exits successfully.
+    ///
+    /// Unlike the string helpers (e.g. [`Adb::shell_lines`]), the output isn't converted
+    /// from UTF-8, so binary output (e.g. `screencap -p`) is returned unchanged.
+    /// Note that a pty translates `\n` into `\r\n`, disable it with [`Self::T`]
+    /// or use `exec-out` (e.g. `adb.custom("exec-out")`) for binary data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NonZeroExit`] if adb exits unsuccessfully,
+    /// which is the remote exit code with `shell_v2`.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell -T screencap -p`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let png = adb.shell().T().arg("screencap -p").output_bytes().unwrap();
+    /// assert!(png.starts_with(b"\x89PNG"));
+    /// ```
+    pub fn output_bytes(self) -> AdbResult<Vec<u8>> {
+        process::stdout_bytes(self.output()?)
+    }
 }
 
 impl<'a> AdbCommand for AdbShell<'a> {
-    fn build(self) -> Command {
+    fn build(mut self) -> Command {
+        self.apply_remote_timeout();
         let mut cmd = self.acb.build();
         cmd.arg("shell");
         if let Some(e) = self.e {
@@ -143,9 +499,27 @@ impl<'a> AdbCommand for AdbShell<'a> {
         if self.x {
             cmd.arg("-x");
         }
-        cmd.args(self.command);
+        if let Some((cols, rows)) = self.pty_size {
+            let pty = matches!(self.t, Some(PtyMode::Auto | PtyMode::Force));
+            if pty && !self.command.is_empty() {
+                cmd.arg(format!("stty cols {} rows {};", cols, rows));
+            }
+        }
+        match self.su {
+            Some(su) => cmd.args(su.wrap(&self.command)),
+            None => cmd.args(self.command),
+        };
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul(self.e.map(|e| e.to_string()))?;
+        process::check_nul(&self.command)
+    }
 }
 
 impl Adb {
@@ -170,11 +544,256 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb shell ls -l` failed");
     /// ```
-    pub fn shell(&self) -> AdbShell {
+    pub fn shell(&self) -> AdbShell<'_> {
         AdbShell::new(self.command())
     }
+
+    /// `shell COMMAND...`: Run a remote shell command, seeded with `args`.
+    ///
+    /// Shortcut for [`Adb::shell`] followed by [`AdbShell::args`],
+    /// the options of [`AdbShell`] can still be set afterwards.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell getprop ro.build.version.sdk`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let output = adb
+    ///     .shell_cmd(["getprop", "ro.build.version.sdk"])
+    ///     .output()
+    ///     .expect("`adb shell getprop ro.build.version.sdk` failed");
+    /// ```
+    pub fn shell_cmd<I, S>(&self, args: I) -> AdbShell<'_>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.shell().args(args)
+    }
+
+    /// Runs a remote shell command, collecting its exit code and output together.
+    ///
+    /// Executes `features` first to check whether the device supports `shell_v2`,
+    /// then runs the command with [`AdbShell::run`] in the matching mode.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let result = adb.shell_run(["pm", "path", "com.example.app"]).unwrap();
+    /// assert_eq!(result.exit_code, 0, "{}", result.stderr);
+    /// assert!(result.stdout.starts_with("package:"));
+    /// ```
+    pub fn shell_run<I, S>(&self, args: I) -> AdbResult<ShellResult>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let shell = self.shell().args(args);
+        if self.supports_shell_v2()? {
+            shell.run()
+        } else {
+            shell.x().run()
+        }
+    }
+
+    /// Runs a remote shell command, returning the lines of its stdout.
+    ///
+    /// Line endings (`\n` or `\r\n`) are stripped and the trailing empty line is dropped,
+    /// while empty lines in between are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NonZeroExit`] if adb exits with a non-zero status,
+    /// which includes the remote exit code only if the device supports `shell_v2`,
+    /// see [`Adb::shell_run`] otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let packages = adb.shell_lines(["pm", "list", "packages"]).unwrap();
+    /// assert!(packages.iter().all(|line| line.starts_with("package:")));
+    /// ```
+    pub fn shell_lines<I, S>(&self, args: I) -> AdbResult<Vec<String>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let stdout = process::stdout_string(self.shell_cmd(args).output()?)?;
+        Ok(split_lines(&stdout))
+    }
+
+    /// Whether the device supports `shell_v2`, by executing `features`.
+    fn supports_shell_v2(&self) -> AdbResult<bool> {
+        Ok(Feature::from_command(self.features())?.contains(&Feature::from("shell_v2")))
+    }
+
+    /// Prepares the device for UI tests, by disabling animations and staying awake while charging.
+    ///
+    /// Runs the following remote shell commands in order:
+    ///
+    /// - `settings put global window_animation_scale 0`
+    /// - `settings put global transition_animation_scale 0`
+    /// - `settings put global animator_duration_scale 0`
+    /// - `settings put global stay_on_while_plugged_in 3` (AC and USB)
+    ///
+    /// Each step is best-effort, a failed step doesn't prevent the following ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::StepsFailed`] with every failed command and its error,
+    /// or the error of `features` if the shell mode can't be detected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.prepare_for_ui_test().expect("failed to prepare the device");
+    /// ```
+    pub fn prepare_for_ui_test(&self) -> AdbResult<()> {
+        let shell_v2 = self.supports_shell_v2()?;
+        let failures = UI_TEST_SETTINGS
+            .iter()
+            .filter_map(|(key, value)| {
+                let command = format!("settings put global {} {}", key, value);
+                let shell = self.shell().arg(&command);
+                let result = if shell_v2 {
+                    shell.run()
+                } else {
+                    shell.x().run()
+                };
+                match result {
+                    Ok(result) if result.success() => None,
+                    Ok(result) => Some((
+                        command,
+                        AdbError::ShellCommandFailed(result.stdout + &result.stderr),
+                    )),
+                    Err(e) => Some((command, e)),
+                }
+            })
+            .collect::<Vec<_>>();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(AdbError::StepsFailed(failures))
+        }
+    }
+}
+
+impl Adb {
+    /// Runs several remote shell commands in a single `shell` invocation,
+    /// saving the connection cost of executing them one by one.
+    ///
+    /// The commands run in order in the same remote shell, so state like the working directory
+    /// is shared, and a command calling `exit` skips the following ones (resulting in an error).
+    /// The stderr of each command is folded into its stdout, so [`ShellResult::stderr`] is always empty.
+    ///
+    /// Each command is followed by `echo "SEPARATOR$?"` with a random separator
+    /// that doesn't occur in the commands, the output is then split back by the separators.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::NonZeroExit`] if adb fails before running the commands (e.g. no device),
+    /// or [`AdbError::Parse`] if fewer separators than commands are found.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell '{ id\n} 2>&1; echo "SEPARATOR$?"; { ls /data\n} 2>&1; echo "SEPARATOR$?"'`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// let results = adb.shell_batch(&["id", "ls /data"]).unwrap();
+    /// assert_eq!(results.len(), 2);
+    /// assert!(results[0].success());
+    /// ```
+    pub fn shell_batch(&self, commands: &[&str]) -> AdbResult<Vec<ShellResult>> {
+        if commands.is_empty() {
+            return Ok(Vec::new());
+        }
+        let separator = batch_separator(commands);
+        let script = commands
+            .iter()
+            .map(|command| format!("{{ {}\n}} 2>&1; echo \"{}$?\"", command, separator))
+            .collect::<Vec<_>>()
+            .join("; ");
+        let output = self.shell().arg(script).output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        split_batch(&stdout, &separator, commands.len()).or_else(|e| {
+            process::check(&output)?;
+            Err(e)
+        })
+    }
+}
+
+/// Splits `stdout` into lines for [`Adb::shell_lines`], without line endings and the trailing empty line.
+fn split_lines(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect()
+}
+
+/// Generates a separator for [`Adb::shell_batch`], which doesn't occur in any of the `commands`.
+fn batch_separator(commands: &[&str]) -> String {
+    let mut nonce = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+        ^ u64::from(std::process::id()).rotate_left(32);
+    loop {
+        let separator = format!("__adbr_sep_{:016x}__:", nonce);
+        if commands.iter().all(|command| !command.contains(&separator)) {
+            return separator;
+        }
+        nonce = nonce.wrapping_add(1);
+    }
+}
+
+/// Splits the output of [`Adb::shell_batch`] into the results of `count` commands,
+/// each one ending with `SEPARATOR<exit code>\n`.
+fn split_batch(stdout: &str, separator: &str, count: usize) -> AdbResult<Vec<ShellResult>> {
+    let mut results = Vec::with_capacity(count);
+    let mut rest = stdout;
+    for _ in 0..count {
+        let Some((output, after)) = rest.split_once(separator) else {
+            return Err(ParseError::with_description(
+                stdout.trim(),
+                "ShellResult",
+                "Missing batch separator",
+            )
+            .into());
+        };
+        let (code, after) = after.split_once('\n').unwrap_or((after, ""));
+        let code = code.trim();
+        let exit_code = code
+            .parse()
+            .map_err(|_| ParseError::with_description(code, "ShellResult", "Invalid exit code"))?;
+        results.push(ShellResult {
+            exit_code,
+            stdout: output.to_string(),
+            stderr: String::new(),
+        });
+        rest = after;
+    }
+    Ok(results)
 }
 
+/// The global settings applied by [`Adb::prepare_for_ui_test`], as `(KEY, VALUE)`.
+const UI_TEST_SETTINGS: [(&str, &str); 4] = [
+    ("window_animation_scale", "0"),
+    ("transition_animation_scale", "0"),
+    ("animator_duration_scale", "0"),
+    // BatteryManager.BATTERY_PLUGGED_AC | BATTERY_PLUGGED_USB
+    ("stay_on_while_plugged_in", "3"),
+];
+
 impl<'a> AdbCommandBuilder<'a> {
     /// `shell [-e ESCAPE] [-n] [-Tt] [-x] [COMMAND...]`:
     /// Run remote shell command (interactive shell if no command given).
@@ -188,6 +807,17 @@ impl<'a> AdbCommandBuilder<'a> {
     pub fn shell(self) -> AdbShell<'a> {
         AdbShell::new(self)
     }
+
+    /// `shell COMMAND...`: Run a remote shell command, seeded with `args`.
+    ///
+    /// See [`Adb::shell_cmd`] for more information.
+    pub fn shell_cmd<I, S>(self, args: I) -> AdbShell<'a>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.shell().args(args)
+    }
 }
 
 /// `emu COMMAND`: Run emulator console `COMMAND`.
@@ -211,6 +841,14 @@ impl<'a, S: AsRef<OsStr>> AdbCommand for AdbEmu<'a, S> {
         cmd.arg("emu").arg(self.command);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.command])
+    }
 }
 
 impl Adb {
@@ -225,7 +863,7 @@ impl Adb {
     /// # let adb = Adb::new().unwrap();
     /// adb.emu("kill").status().expect("`adb emu kill` failed");
     /// ```
-    pub fn emu<S: AsRef<OsStr>>(&self, command: S) -> AdbEmu<S> {
+    pub fn emu<S: AsRef<OsStr>>(&self, command: S) -> AdbEmu<'_, S> {
         AdbEmu::new(self.command(), command)
     }
 }
@@ -237,4 +875,411 @@ impl<'a> AdbCommandBuilder<'a> {
     pub fn emu<S: AsRef<OsStr>>(self, command: S) -> AdbEmu<'a, S> {
         AdbEmu::new(self, command)
     }
+
+    /// `emu kill`: Kill the emulator instance.
+    ///
+    /// See [`Adb::emu_kill`] for more information.
+    pub fn emu_kill(self) -> AdbEmu<'a, String> {
+        self.emu("kill".to_string())
+    }
+
+    /// `emu avd name`: Print the name of the running AVD.
+    ///
+    /// See [`Adb::emu_avd_name`] for more information.
+    pub fn emu_avd_name(self) -> AdbEmu<'a, String> {
+        self.emu("avd name".to_string())
+    }
+
+    /// `emu rotate`: Rotate the screen clockwise by 90 degrees.
+    ///
+    /// See [`Adb::emu_rotate`] for more information.
+    pub fn emu_rotate(self) -> AdbEmu<'a, String> {
+        self.emu("rotate".to_string())
+    }
+
+    /// `emu geo fix LONGITUDE LATITUDE`: Send a GPS fix to the emulator.
+    ///
+    /// See [`Adb::emu_geo_fix`] for more information.
+    pub fn emu_geo_fix(self, longitude: f64, latitude: f64) -> AdbEmu<'a, String> {
+        self.emu(geo_fix_command(longitude, latitude))
+    }
+
+    /// `emu sms send NUMBER TEXT`: Simulate an inbound SMS.
+    ///
+    /// See [`Adb::emu_sms_send`] for more information.
+    pub fn emu_sms_send(self, number: &str, text: &str) -> AdbEmu<'a, String> {
+        self.emu(sms_send_command(number, text))
+    }
+}
+
+/// Formats `geo fix LONGITUDE LATITUDE`, where the longitude comes first.
+fn geo_fix_command(longitude: f64, latitude: f64) -> String {
+    format!("geo fix {} {}", longitude, latitude)
+}
+
+/// Formats `sms send NUMBER TEXT`.
+///
+/// The console reads one command per line, so line breaks in `text` are replaced by spaces,
+/// and whitespace in `number` is removed since it must be a single word.
+fn sms_send_command(number: &str, text: &str) -> String {
+    let number = number
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    let text = text.replace("\r\n", " ").replace(['\r', '\n'], " ");
+    format!("sms send {} {}", number, text)
+}
+
+impl Adb {
+    /// `emu kill`: Kill the emulator instance.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.emu_kill().status().expect("`adb emu kill` failed");
+    /// ```
+    pub fn emu_kill(&self) -> AdbEmu<'_, String> {
+        self.command().emu_kill()
+    }
+
+    /// `emu avd name`: Print the name of the running AVD, followed by `OK`.
+    pub fn emu_avd_name(&self) -> AdbEmu<'_, String> {
+        self.command().emu_avd_name()
+    }
+
+    /// `emu rotate`: Rotate the screen clockwise by 90 degrees.
+    pub fn emu_rotate(&self) -> AdbEmu<'_, String> {
+        self.command().emu_rotate()
+    }
+
+    /// `emu geo fix LONGITUDE LATITUDE`: Send a GPS fix to the emulator.
+    ///
+    /// Note that the console expects the longitude **before** the latitude, in decimal degrees.
+    ///
+    /// # Examples
+    ///
+    /// `adb emu geo fix -122.084 37.422`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.emu_geo_fix(-122.084, 37.422)
+    ///     .status()
+    ///     .expect("`adb emu geo fix -122.084 37.422` failed");
+    /// ```
+    pub fn emu_geo_fix(&self, longitude: f64, latitude: f64) -> AdbEmu<'_, String> {
+        self.command().emu_geo_fix(longitude, latitude)
+    }
+
+    /// `emu sms send NUMBER TEXT`: Simulate an inbound SMS from `number`.
+    ///
+    /// Line breaks in `text` are replaced by spaces, since the console reads one command per line.
+    ///
+    /// # Examples
+    ///
+    /// `adb emu sms send 5551234 hello world`
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// adb.emu_sms_send("5551234", "hello world")
+    ///     .status()
+    ///     .expect("`adb emu sms send 5551234 hello world` failed");
+    /// ```
+    pub fn emu_sms_send(&self, number: &str, text: &str) -> AdbEmu<'_, String> {
+        self.command().emu_sms_send(number, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_remote_timeout() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.shell().arg("logcat | grep 'I '").remote_timeout(5)),
+            [
+                "shell",
+                "timeout",
+                "5",
+                "sh",
+                "-c",
+                r"'logcat | grep '\''I '\'''"
+            ]
+        );
+        assert_eq!(args(adb.shell().remote_timeout(5)), ["shell"]);
+        assert_eq!(
+            args(
+                adb.shell()
+                    .args(["sleep", "9"])
+                    .remote_timeout(5)
+                    .remote_timeout(1)
+                    .su(AdbSuFlavor::Magisk)
+            ),
+            ["shell", "su", "-c", r"'timeout 1 sh -c '\''sleep 9'\'''"]
+        );
+    }
+
+    // `timeout` is provided by coreutils on linux
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_remote_timeout() {
+        use crate::command::tests::fake_adb;
+
+        // runs the arguments after `shell [-x]` like adbd, with the `timeout` of the host
+        let dir = fake_adb(
+            "remote-timeout",
+            "shift\n[ \"$1\" = -x ] && shift\nPATH=/usr/bin:/bin exec /bin/sh -c \"$*\"",
+        );
+        let adb = Adb::default();
+        let shell = || adb.command().env("PATH", &dir).shell();
+        let result = shell().x().arg("echo hi").remote_timeout(3).run().unwrap();
+        assert_eq!((result.exit_code, result.stdout.as_str()), (0, "hi\n"));
+        // the exit code marker is appended outside of `timeout`
+        let result = shell()
+            .x()
+            .args(["sleep", "5"])
+            .remote_timeout(1)
+            .run()
+            .unwrap();
+        assert_eq!(result.exit_code, 124);
+        let result = shell()
+            .args(["sleep", "5"])
+            .remote_timeout(1)
+            .run()
+            .unwrap();
+        assert_eq!(result.exit_code, 124);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_split_batch() {
+        let sep = "__sep__:";
+        let results = split_batch(
+            "uid=0(root)\n__sep__:0\nno such file\n__sep__:1\r\nx__sep__:0\n",
+            sep,
+            3,
+        )
+        .unwrap();
+        assert_eq!(
+            results,
+            [
+                ShellResult {
+                    exit_code: 0,
+                    stdout: "uid=0(root)\n".to_string(),
+                    stderr: String::new()
+                },
+                ShellResult {
+                    exit_code: 1,
+                    stdout: "no such file\n".to_string(),
+                    stderr: String::new()
+                },
+                ShellResult {
+                    exit_code: 0,
+                    stdout: "x".to_string(),
+                    stderr: String::new()
+                },
+            ]
+        );
+        assert!(split_batch("a\n__sep__:0\n", sep, 2).is_err());
+        assert!(split_batch("a\n__sep__:x\n", sep, 1).is_err());
+    }
+
+    #[test]
+    fn test_batch_separator() {
+        let sep = batch_separator(&["echo hi"]);
+        assert!(sep.starts_with("__adbr_sep_") && sep.ends_with("__:"));
+        let commands = [sep.as_str()];
+        assert_ne!(batch_separator(&commands), sep);
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("ls -l"), "'ls -l'");
+        assert_eq!(quote("echo 'a b'"), r"'echo '\''a b'\'''");
+        assert_eq!(quote(""), "''");
+    }
+
+    #[test]
+    fn test_su() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(
+                adb.shell()
+                    .args(["ls", "/data && id"])
+                    .su(AdbSuFlavor::Aosp)
+            ),
+            ["shell", "su", "0", "sh", "-c", "'ls /data && id'"]
+        );
+        assert_eq!(
+            args(adb.shell().arg("echo 'hi'").su(AdbSuFlavor::Magisk)),
+            ["shell", "su", "-c", r"'echo '\''hi'\'''"]
+        );
+        assert_eq!(
+            args(adb.shell().t().su(AdbSuFlavor::Magisk)),
+            ["shell", "-t", "su"]
+        );
+    }
+
+    #[test]
+    fn test_emu_commands() {
+        assert_eq!(geo_fix_command(-122.084, 37.422), "geo fix -122.084 37.422");
+        assert_eq!(geo_fix_command(0.0, 51.5), "geo fix 0 51.5");
+        assert_eq!(
+            sms_send_command("+1 555 1234", "hello\r\nworld\nagain"),
+            "sms send +15551234 hello world again"
+        );
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.emu_geo_fix(2.35, 48.85)),
+            ["emu", "geo fix 2.35 48.85"]
+        );
+        assert_eq!(args(adb.emu_kill()), ["emu", "kill"]);
+        assert_eq!(args(adb.emu_avd_name()), ["emu", "avd name"]);
+    }
+
+    #[test]
+    fn test_split_lines() {
+        assert!(split_lines("").is_empty());
+        assert_eq!(split_lines("a\n"), ["a"]);
+        assert_eq!(split_lines("a\r\nb\r\n"), ["a", "b"]);
+        assert_eq!(split_lines("a\n\nb"), ["a", "", "b"]);
+        assert_eq!(split_lines("\n"), [""]);
+    }
+
+    #[test]
+    fn test_shell_cmd() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.shell_cmd(["getprop", "ro.build.version.sdk"])),
+            ["shell", "getprop", "ro.build.version.sdk"]
+        );
+        assert_eq!(
+            args(adb.d_usb().shell_cmd(["ls"]).T().arg("/sdcard")),
+            ["-d", "shell", "-T", "ls", "/sdcard"]
+        );
+    }
+
+    #[test]
+    fn test_pty() {
+        let adb = Adb::default();
+        assert_eq!(args(adb.shell().pty(PtyMode::Disable)), ["shell", "-T"]);
+        assert_eq!(args(adb.shell().pty(PtyMode::Auto)), ["shell", "-t"]);
+        assert_eq!(args(adb.shell().pty(PtyMode::Force)), ["shell", "-tt"]);
+        assert_eq!(args(adb.shell().T().t().tt()), ["shell", "-tt"]);
+        assert_eq!(
+            args(adb.shell().tt().pty(PtyMode::Disable)),
+            ["shell", "-T"]
+        );
+    }
+
+    #[test]
+    fn test_pty_size() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.shell().tt().pty_size(120, 40).arg("top")),
+            ["shell", "-tt", "stty cols 120 rows 40;", "top"]
+        );
+        assert_eq!(
+            args(
+                adb.shell()
+                    .t()
+                    .pty_size(80, 24)
+                    .arg("top")
+                    .su(AdbSuFlavor::Magisk)
+            ),
+            ["shell", "-t", "stty cols 80 rows 24;", "su", "-c", "'top'"]
+        );
+        assert_eq!(
+            args(adb.shell().pty_size(120, 40).arg("top")),
+            ["shell", "top"]
+        );
+        assert_eq!(
+            args(adb.shell().T().pty_size(120, 40).arg("top")),
+            ["shell", "-T", "top"]
+        );
+        assert_eq!(args(adb.shell().tt().pty_size(120, 40)), ["shell", "-tt"]);
+    }
+
+    #[test]
+    fn test_script() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.shell().n().arg("com.example.app").into_script()),
+            ["shell", "sh", "-s", "com.example.app"]
+        );
+        assert_eq!(script_input("id"), b"id\nexit\n");
+        assert_eq!(script_input("id\n"), b"id\nexit\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_script_round_trip() {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-s").arg("two");
+        let script = "echo \"one 'quoted'\"\necho \"$1\"";
+        let output = process::output_with_stdin(cmd, script_input(script)).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"one 'quoted'\ntwo\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_bytes() {
+        use crate::command::tests::fake_adb;
+
+        // runs the script after `shell`
+        let dir = fake_adb("output-bytes", "shift\nexec /bin/sh -c \"$1\"");
+        let adb = Adb::default();
+        let shell = |script: &str| adb.command().env("PATH", &dir).shell().arg(script);
+        assert_eq!(
+            shell(r"printf '\211PNG\r\n\032\n\000\377'")
+                .output_bytes()
+                .unwrap(),
+            b"\x89PNG\r\n\x1a\n\x00\xff"
+        );
+        assert!(matches!(
+            shell("printf '\\377'; exit 1").output_bytes(),
+            Err(AdbError::NonZeroExit { status, .. }) if status.code() == Some(1)
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_shell_result() {
+        use crate::command::tests::output;
+
+        assert_eq!(
+            ShellResult::from_output(output(2, "out\n", "err\n")).unwrap(),
+            ShellResult {
+                exit_code: 2,
+                stdout: "out\n".to_string(),
+                stderr: "err\n".to_string(),
+            }
+        );
+        assert_eq!(
+            ShellResult::from_marked_output(output(0, "out\nerr\n__rc:1\n", "")).unwrap(),
+            ShellResult {
+                exit_code: 1,
+                stdout: "out\nerr\n".to_string(),
+                stderr: String::new(),
+            }
+        );
+        assert!(ShellResult::from_marked_output(output(0, "__rc:0\r\n", ""))
+            .unwrap()
+            .success());
+        assert!(matches!(
+            ShellResult::from_marked_output(output(1, "", "error: no devices/emulators found")),
+            Err(crate::AdbError::NonZeroExit { .. })
+        ));
+        assert!(matches!(
+            ShellResult::from_marked_output(output(0, "out\n", "")),
+            Err(crate::AdbError::Parse(_))
+        ));
+    }
 }
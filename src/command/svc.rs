@@ -0,0 +1,265 @@
+//! Service control commands (see also `adb shell svc help`)
+//!
+//! - `shell svc wifi enable|disable`: Turn Wi-Fi on or off.
+//! - `shell svc data enable|disable`: Turn mobile data on or off.
+//! - `shell svc power stayon true|false|usb|ac|wireless`: Keep the screen on while plugged in.
+//! - `shell svc usb setFunctions [FUNCTION]`: Set the current USB functions.
+//!
+//! See [`Adb::svc`].
+
+use std::ffi::OsStr;
+use std::fmt::Display;
+use std::process::Command;
+use std::str::FromStr;
+
+use crate::command::{process, AdbCommandBuilder};
+use crate::error::ParseError;
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
+
+/// When the device stays awake, the argument of `svc power stayon`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StayOn {
+    /// `true`: Always stay on.
+    True,
+    /// `false`: Never stay on, the screen times out as usual.
+    False,
+    /// `usb`: Stay on while plugged in via USB.
+    Usb,
+    /// `ac`: Stay on while plugged in to an AC charger.
+    Ac,
+    /// `wireless`: Stay on while charging wirelessly.
+    Wireless,
+}
+
+impl AsRef<OsStr> for StayOn {
+    fn as_ref(&self) -> &OsStr {
+        match self {
+            StayOn::True => OsStr::new("true"),
+            StayOn::False => OsStr::new("false"),
+            StayOn::Usb => OsStr::new("usb"),
+            StayOn::Ac => OsStr::new("ac"),
+            StayOn::Wireless => OsStr::new("wireless"),
+        }
+    }
+}
+
+impl Display for StayOn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StayOn::True => "true",
+            StayOn::False => "false",
+            StayOn::Usb => "usb",
+            StayOn::Ac => "ac",
+            StayOn::Wireless => "wireless",
+        })
+    }
+}
+
+impl FromStr for StayOn {
+    type Err = AdbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "true" => Ok(StayOn::True),
+            "false" => Ok(StayOn::False),
+            "usb" => Ok(StayOn::Usb),
+            "ac" => Ok(StayOn::Ac),
+            "wireless" => Ok(StayOn::Wireless),
+            _ => Err(AdbError::Parse(ParseError::with_description(
+                s,
+                "StayOn",
+                "Unknown stay on condition",
+            ))),
+        }
+    }
+}
+
+impl_try_from_os_str!(StayOn);
+
+/// The subsystem controlled by `svc`, with its argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SvcCommand {
+    Wifi(bool),
+    Data(bool),
+    PowerStayOn(StayOn),
+    UsbSetFunctions(String),
+}
+
+/// `shell svc SUBSYSTEM ARG`: Control a system service.
+///
+/// Exactly one subsystem must be chosen, the last call wins.
+#[derive(Debug, Clone)]
+pub struct AdbSvc<'a> {
+    acb: AdbCommandBuilder<'a>,
+    command: Option<SvcCommand>,
+}
+
+impl<'a> AdbSvc<'a> {
+    /// `wifi enable|disable`: Turn Wi-Fi on or off.
+    pub fn wifi(mut self, enable: bool) -> Self {
+        self.command = Some(SvcCommand::Wifi(enable));
+        self
+    }
+
+    /// `data enable|disable`: Turn mobile data on or off.
+    pub fn data(mut self, enable: bool) -> Self {
+        self.command = Some(SvcCommand::Data(enable));
+        self
+    }
+
+    /// `power stayon true|false|usb|ac|wireless`: Keep the device awake under the `condition`.
+    pub fn power_stayon(mut self, condition: StayOn) -> Self {
+        self.command = Some(SvcCommand::PowerStayOn(condition));
+        self
+    }
+
+    /// `usb setFunctions [FUNCTION]`: Set the current USB functions (e.g. `mtp`, `ptp`, `rndis`),
+    /// or reset them to the default if `functions` is empty.
+    pub fn usb<S: Into<String>>(mut self, functions: S) -> Self {
+        self.command = Some(SvcCommand::UsbSetFunctions(functions.into()));
+        self
+    }
+
+    /// Executes the command like [`AdbCommand::checked_status`].
+    pub fn run(self) -> AdbResult<()> {
+        self.checked_status()
+    }
+}
+
+impl<'a> AdbCommand for AdbSvc<'a> {
+    fn build(self) -> Command {
+        let mut cmd = self.acb.build();
+        cmd.arg("shell").arg("svc");
+        let toggle = |enable| if enable { "enable" } else { "disable" };
+        match self.command {
+            Some(SvcCommand::Wifi(enable)) => cmd.arg("wifi").arg(toggle(enable)),
+            Some(SvcCommand::Data(enable)) => cmd.arg("data").arg(toggle(enable)),
+            Some(SvcCommand::PowerStayOn(condition)) => {
+                cmd.arg("power").arg("stayon").arg(condition)
+            }
+            Some(SvcCommand::UsbSetFunctions(functions)) => {
+                cmd.arg("usb").arg("setFunctions");
+                if !functions.is_empty() {
+                    cmd.arg(functions);
+                }
+                &mut cmd
+            }
+            None => &mut cmd,
+        };
+        cmd
+    }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        match &self.command {
+            Some(SvcCommand::UsbSetFunctions(functions)) => process::check_nul([functions]),
+            Some(_) => Ok(()),
+            None => Err(ParseError::with_description(
+                "",
+                "AdbSvc",
+                "No subsystem, e.g. `wifi`, `data`, `power` or `usb`",
+            )
+            .into()),
+        }
+    }
+}
+
+impl Adb {
+    /// `shell svc SUBSYSTEM ARG`: Control a system service, e.g. Wi-Fi, mobile data or power.
+    ///
+    /// # Examples
+    ///
+    /// `adb shell svc wifi disable`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// adb.svc()
+    ///     .wifi(false)
+    ///     .run()
+    ///     .expect("`adb shell svc wifi disable` failed");
+    /// ```
+    ///
+    /// `adb shell svc power stayon usb`
+    ///
+    /// ```no_run
+    /// # use adbr::Adb;
+    /// # let adb = Adb::new().unwrap();
+    /// use adbr::command::svc::StayOn;
+    ///
+    /// adb.svc()
+    ///     .power_stayon(StayOn::Usb)
+    ///     .run()
+    ///     .expect("`adb shell svc power stayon usb` failed");
+    /// ```
+    pub fn svc(&self) -> AdbSvc<'_> {
+        self.command().svc()
+    }
+}
+
+impl<'a> AdbCommandBuilder<'a> {
+    /// `shell svc SUBSYSTEM ARG`: Control a system service, e.g. Wi-Fi, mobile data or power.
+    ///
+    /// See [`Adb::svc`] for more information.
+    pub fn svc(self) -> AdbSvc<'a> {
+        AdbSvc {
+            acb: self,
+            command: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::tests::args;
+
+    #[test]
+    fn test_svc() {
+        let adb = Adb::default();
+        assert_eq!(
+            args(adb.svc().wifi(true)),
+            ["shell", "svc", "wifi", "enable"]
+        );
+        assert_eq!(
+            args(adb.svc().data(false)),
+            ["shell", "svc", "data", "disable"]
+        );
+        assert_eq!(
+            args(adb.svc().power_stayon(StayOn::Wireless)),
+            ["shell", "svc", "power", "stayon", "wireless"]
+        );
+        assert_eq!(
+            args(adb.svc().usb("mtp")),
+            ["shell", "svc", "usb", "setFunctions", "mtp"]
+        );
+        assert_eq!(
+            args(adb.svc().usb("")),
+            ["shell", "svc", "usb", "setFunctions"]
+        );
+        // the last subsystem wins
+        assert_eq!(
+            args(adb.svc().wifi(true).data(true)),
+            ["shell", "svc", "data", "enable"]
+        );
+        assert!(matches!(adb.svc().try_build(), Err(AdbError::Parse(_))));
+    }
+
+    #[test]
+    fn test_stay_on() {
+        for condition in [
+            StayOn::True,
+            StayOn::False,
+            StayOn::Usb,
+            StayOn::Ac,
+            StayOn::Wireless,
+        ] {
+            assert_eq!(condition.to_string().parse::<StayOn>().unwrap(), condition);
+            assert_eq!(StayOn::try_from(condition.as_ref()).unwrap(), condition);
+        }
+        assert!("always".parse::<StayOn>().is_err());
+    }
+}
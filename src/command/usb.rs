@@ -5,13 +5,28 @@
 //! - `attach SERIAL`: Attach a detached USB device identified by its `SERIAL` number.
 //! - `detach SERIAL`: Detach from a USB device identified by its `SERIAL` to allow use by other processes.
 //!
+//! `run` methods (e.g. [`AdbAttach::run`]) check the backend before executing the command,
+//! while [`AdbCommand`] methods (e.g. [`AdbCommand::status`]) bypass the check.
+//!
 //! See [USB Commands](https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/master/docs/user/adb.1.md#usb).
 
 use std::ffi::OsStr;
 use std::process::Command;
 
-use crate::command::AdbCommandBuilder;
-use crate::{Adb, AdbCommand};
+use crate::command::{process, AdbCommandBuilder};
+use crate::{Adb, AdbCommand, AdbError, AdbResult};
+
+/// Checks that the libusb backend required by `subcommand` is not disabled.
+///
+/// It's a soft check, only `ADB_LIBUSB=0` in the [`AdbEnvs`](crate::AdbEnvs) of the command
+/// (i.e. of the instance, overlaid with [`AdbCommandBuilder::with_envs`]) fails it,
+/// since the default backend depends on the OS and the adb version.
+fn check_libusb(acb: &AdbCommandBuilder, subcommand: &'static str) -> AdbResult<()> {
+    match acb.envs().adb_libusb() {
+        Some(false) => Err(AdbError::LibusbDisabled(subcommand)),
+        _ => Ok(()),
+    }
+}
 
 /// `attach SERIAL`: Attach a detached USB device identified by its `SERIAL` number.
 #[derive(Debug, Clone)]
@@ -36,6 +51,20 @@ impl<'a, S: AsRef<OsStr>> AdbAttach<'a, S> {
             serial,
         }
     }
+
+    /// Executes the command like [`AdbCommand::checked_status`],
+    /// checking that the libusb backend is not disabled first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::LibusbDisabled`] without executing the command
+    /// if `ADB_LIBUSB` is set to `0` in the [`AdbEnvs`](crate::AdbEnvs) of the instance
+    /// or in the [overrides](AdbCommandBuilder::with_envs) of the command.
+    /// Use [`AdbCommand::status`] to bypass the check.
+    pub fn run(self) -> AdbResult<()> {
+        check_libusb(&self.acb, "attach")?;
+        self.checked_status()
+    }
 }
 
 impl<'a, S: AsRef<OsStr>> AdbCommand for AdbAttach<'a, S> {
@@ -45,6 +74,14 @@ impl<'a, S: AsRef<OsStr>> AdbCommand for AdbAttach<'a, S> {
         cmd.arg(self.serial);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.serial])
+    }
 }
 
 impl Adb {
@@ -61,7 +98,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb attach serial` failed");
     /// ```
-    pub fn attach<S: AsRef<OsStr>>(&self, serial: S) -> AdbAttach<S> {
+    pub fn attach<S: AsRef<OsStr>>(&self, serial: S) -> AdbAttach<'_, S> {
         AdbAttach::new(self.command(), serial)
     }
 }
@@ -98,6 +135,20 @@ impl<'a, S: AsRef<OsStr>> AdbDetach<'a, S> {
             serial,
         }
     }
+
+    /// Executes the command like [`AdbCommand::checked_status`],
+    /// checking that the libusb backend is not disabled first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AdbError::LibusbDisabled`] without executing the command
+    /// if `ADB_LIBUSB` is set to `0` in the [`AdbEnvs`](crate::AdbEnvs) of the instance
+    /// or in the [overrides](AdbCommandBuilder::with_envs) of the command.
+    /// Use [`AdbCommand::status`] to bypass the check.
+    pub fn run(self) -> AdbResult<()> {
+        check_libusb(&self.acb, "detach")?;
+        self.checked_status()
+    }
 }
 
 impl<'a, S: AsRef<OsStr>> AdbCommand for AdbDetach<'a, S> {
@@ -107,6 +158,14 @@ impl<'a, S: AsRef<OsStr>> AdbCommand for AdbDetach<'a, S> {
         cmd.arg(self.serial);
         cmd
     }
+
+    fn command_builder(&self) -> Option<&AdbCommandBuilder<'_>> {
+        Some(&self.acb)
+    }
+
+    fn check_args(&self) -> AdbResult<()> {
+        process::check_nul([&self.serial])
+    }
 }
 
 impl Adb {
@@ -123,7 +182,7 @@ impl Adb {
     ///     .status()
     ///     .expect("`adb detach serial` failed");
     /// ```
-    pub fn detach<S: AsRef<OsStr>>(&self, serial: S) -> AdbDetach<S> {
+    pub fn detach<S: AsRef<OsStr>>(&self, serial: S) -> AdbDetach<'_, S> {
         AdbDetach::new(self.command(), serial)
     }
 }
@@ -136,3 +195,39 @@ impl<'a> AdbCommandBuilder<'a> {
         AdbDetach::new(self, serial)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AdbEnvs;
+
+    #[test]
+    fn test_check_libusb() {
+        let mut adb = Adb::default();
+        assert!(check_libusb(&adb.command(), "attach").is_ok());
+        adb.envs_mut().set_adb_libusb(true);
+        assert!(check_libusb(&adb.command(), "attach").is_ok());
+        adb.envs_mut().set_adb_libusb(false);
+        assert!(matches!(
+            adb.attach("serial").run(),
+            Err(AdbError::LibusbDisabled("attach"))
+        ));
+        let err = adb.detach("serial").run().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`detach` requires the libusb backend, but it is disabled by `ADB_LIBUSB=0`"
+        );
+
+        // the overrides of the command are checked, on top of the instance
+        let mut enabled = AdbEnvs::default();
+        enabled.set_adb_libusb(true);
+        assert!(check_libusb(&adb.with_envs(&enabled), "attach").is_ok());
+        adb.envs_mut().set_adb_libusb(true);
+        let mut disabled = AdbEnvs::default();
+        disabled.set_adb_libusb(false);
+        assert!(matches!(
+            adb.with_envs(&disabled).attach("serial").run(),
+            Err(AdbError::LibusbDisabled("attach"))
+        ));
+    }
+}
@@ -11,12 +11,12 @@
 //! - [`AdbLocalTransportMaxPort`]: Max emulator scan port (default 5585, 16 emulators).
 //! - [`AdbMdnsAutoConnect`]: Comma-separated list of mdns services to allow auto-connect (default adb-tls-connect).
 //! - [`AdbMdnsOpenScreen`]: The default mDNS-SD backend is Bonjour (mdnsResponder).
-//!     For machines where Bonjour is not installed, adb can spawn its own, embedded, mDNS-SD back end, openscreen.
-//!     If set to `1`, this env variable forces mDNS backend to openscreen.
+//!   For machines where Bonjour is not installed, adb can spawn its own, embedded, mDNS-SD back end, openscreen.
+//!   If set to `1`, this env variable forces mDNS backend to openscreen.
 //! - [`AdbLibusb`]: ADB has its own USB backend implementation but can also employ libusb.
-//!     use `adb devices -l` (usb: prefix is omitted for libusb) or `adb host-features` (look for libusb in the output list) to identify which is in use.
-//!     To override the default for your OS, set `ADB_LIBUSB` to `1` to enable libusb, or `0` to enable the ADB backend implementation.
-//! 
+//!   use `adb devices -l` (usb: prefix is omitted for libusb) or `adb host-features` (look for libusb in the output list) to identify which is in use.
+//!   To override the default for your OS, set `ADB_LIBUSB` to `1` to enable libusb, or `0` to enable the ADB backend implementation.
+//!
 //! To get and modify the environment variables at instance level, you can use [`crate::Adb::envs`] and [`crate::Adb::envs_mut`],
 //! or [`AdbEnv::get`] and [`AdbEnv::set`] methods at process level, see [crate level documentation](crate#environment-variables).
 
@@ -56,13 +56,30 @@ pub struct AdbEnvs {
     adb_libusb: Option<AdbLibusb>,
 }
 
-/// Applies the value of an adb environment variable to a command.
+/// Plans the value of an adb environment variable, [`None`] to remove it.
+#[inline]
+fn _plan<T: AdbEnv>(var: Option<&T>) -> (&'static str, Option<String>) {
+    (T::NAME, var.map(T::to_string))
+}
+
+/// Overwrites the value of an adb environment variable (`var`) with `other` if it is set.
+#[inline]
+fn _merge<T: AdbEnv + Clone>(var: &mut Option<T>, other: Option<&T>) {
+    if let Some(other) = other {
+        *var = Some(other.clone());
+    }
+}
+
+/// Records the change of an adb environment variable from `old` to `new` if they differ.
 #[inline]
-fn _apply<T: AdbEnv>(var: Option<&T>, cmd: &mut Command) {
-    match var {
-        Some(var) => cmd.env(T::NAME, var.to_string()),
-        None => cmd.env_remove(T::NAME),
-    };
+fn _diff<T: AdbEnv + PartialEq>(
+    diff: &mut Vec<(&'static str, Option<String>, Option<String>)>,
+    old: Option<&T>,
+    new: Option<&T>,
+) {
+    if old != new {
+        diff.push((T::NAME, old.map(T::to_string), new.map(T::to_string)));
+    }
 }
 
 impl AdbEnvs {
@@ -83,15 +100,179 @@ impl AdbEnvs {
     }
 
     /// Apply the current environment variable values to the given [`Command`].
+    ///
+    /// The variables are set or removed as planned by [`Self::planned_env`], in the same order.
     pub fn apply(&self, cmd: &mut Command) {
-        _apply(self.adb_trace.as_ref(), cmd);
-        _apply(self.adb_vendor_keys.as_ref(), cmd);
-        _apply(self.android_serial.as_ref(), cmd);
-        _apply(self.android_log_tags.as_ref(), cmd);
-        _apply(self.adb_local_transport_max_port.as_ref(), cmd);
-        _apply(self.adb_mdns_auto_connect.as_ref(), cmd);
-        _apply(self.adb_mdns_openscreen.as_ref(), cmd);
-        _apply(self.adb_libusb.as_ref(), cmd);
+        for (name, value) in self.planned_env() {
+            match value {
+                Some(value) => cmd.env(name, value),
+                None => cmd.env_remove(name),
+            };
+        }
+    }
+
+    /// Returns what [`Self::apply`] does to a [`Command`], without a command.
+    ///
+    /// Every environment variable is listed with the value it's set to (`Some`),
+    /// or [`None`] if it's removed, formatted as passed to adb.
+    /// The tuples are ordered like the fields of [`AdbEnvs`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::AdbEnvs;
+    /// let mut envs = AdbEnvs::default();
+    /// envs.set_adb_libusb(true);
+    ///
+    /// let plan = envs.planned_env();
+    /// assert!(plan.contains(&("ADB_LIBUSB", Some("1".to_string()))));
+    /// assert!(plan.contains(&("ANDROID_SERIAL", None)));
+    /// ```
+    pub fn planned_env(&self) -> Vec<(&'static str, Option<String>)> {
+        vec![
+            _plan(self.adb_trace.as_ref()),
+            _plan(self.adb_vendor_keys.as_ref()),
+            _plan(self.android_serial.as_ref()),
+            _plan(self.android_log_tags.as_ref()),
+            _plan(self.adb_local_transport_max_port.as_ref()),
+            _plan(self.adb_mdns_auto_connect.as_ref()),
+            _plan(self.adb_mdns_openscreen.as_ref()),
+            _plan(self.adb_libusb.as_ref()),
+        ]
+    }
+
+    /// Overlays `other` on the current environment variable values.
+    ///
+    /// The values set in `other` overwrite the current ones,
+    /// while the values not set (i.e. [`None`]) in `other` leave the current ones unchanged.
+    ///
+    /// # Note
+    ///
+    /// [`None`] means "don't override" here, so merging can't remove an environment variable,
+    /// use the `remove_` methods (e.g. [`Self::remove_android_serial`]) instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::AdbEnvs;
+    /// let mut base = AdbEnvs::default();
+    /// base.set_android_serial("emulator-5554".to_string());
+    /// base.set_adb_libusb(true);
+    ///
+    /// let mut overrides = AdbEnvs::default();
+    /// overrides.set_android_serial("emulator-5556".to_string());
+    ///
+    /// base.merge(&overrides);
+    /// assert_eq!(base.android_serial(), Some("emulator-5556"));
+    /// assert_eq!(base.adb_libusb(), Some(true));
+    /// ```
+    pub fn merge(&mut self, other: &AdbEnvs) -> &mut Self {
+        _merge(&mut self.adb_trace, other.adb_trace.as_ref());
+        _merge(&mut self.adb_vendor_keys, other.adb_vendor_keys.as_ref());
+        _merge(&mut self.android_serial, other.android_serial.as_ref());
+        _merge(&mut self.android_log_tags, other.android_log_tags.as_ref());
+        _merge(
+            &mut self.adb_local_transport_max_port,
+            other.adb_local_transport_max_port.as_ref(),
+        );
+        _merge(
+            &mut self.adb_mdns_auto_connect,
+            other.adb_mdns_auto_connect.as_ref(),
+        );
+        _merge(
+            &mut self.adb_mdns_openscreen,
+            other.adb_mdns_openscreen.as_ref(),
+        );
+        _merge(&mut self.adb_libusb, other.adb_libusb.as_ref());
+        self
+    }
+
+    /// Returns the current environment variable values overlaid with `other`.
+    ///
+    /// See [`Self::merge`] for more information.
+    pub fn merged(mut self, other: &AdbEnvs) -> Self {
+        self.merge(other);
+        self
+    }
+
+    /// Compares the current environment variable values with `other`.
+    ///
+    /// Returns a `(name, old, new)` tuple for every environment variable that differs,
+    /// where `old` is the value in `self` and `new` is the value in `other`,
+    /// both formatted as they would be passed to adb ([`None`] if not set).
+    /// The tuples are ordered like the fields of [`AdbEnvs`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::AdbEnvs;
+    /// let old = AdbEnvs::default();
+    /// let mut new = old.clone();
+    /// new.set_adb_libusb(true);
+    ///
+    /// assert_eq!(old.diff(&new), [("ADB_LIBUSB", None, Some("1".to_string()))]);
+    /// ```
+    pub fn diff(&self, other: &AdbEnvs) -> Vec<(&'static str, Option<String>, Option<String>)> {
+        let mut diff = Vec::new();
+        _diff(&mut diff, self.adb_trace.as_ref(), other.adb_trace.as_ref());
+        _diff(
+            &mut diff,
+            self.adb_vendor_keys.as_ref(),
+            other.adb_vendor_keys.as_ref(),
+        );
+        _diff(
+            &mut diff,
+            self.android_serial.as_ref(),
+            other.android_serial.as_ref(),
+        );
+        _diff(
+            &mut diff,
+            self.android_log_tags.as_ref(),
+            other.android_log_tags.as_ref(),
+        );
+        _diff(
+            &mut diff,
+            self.adb_local_transport_max_port.as_ref(),
+            other.adb_local_transport_max_port.as_ref(),
+        );
+        _diff(
+            &mut diff,
+            self.adb_mdns_auto_connect.as_ref(),
+            other.adb_mdns_auto_connect.as_ref(),
+        );
+        _diff(
+            &mut diff,
+            self.adb_mdns_openscreen.as_ref(),
+            other.adb_mdns_openscreen.as_ref(),
+        );
+        _diff(
+            &mut diff,
+            self.adb_libusb.as_ref(),
+            other.adb_libusb.as_ref(),
+        );
+        diff
+    }
+
+    /// Removes all environment variables, like calling every `remove_` method.
+    ///
+    /// Afterwards, the environment is the same as [`AdbEnvs::default`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::AdbEnvs;
+    /// let mut envs = AdbEnvs::default();
+    /// envs.set_android_serial("emulator-5554".to_string());
+    /// envs.clear_all();
+    /// assert!(envs.is_empty());
+    /// ```
+    pub fn clear_all(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Whether no environment variable is set, i.e. all values are [`None`].
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
     }
 
     /// `$ADB_TRACE`: Comma (or space) separated list of debug info to log.
@@ -660,3 +841,121 @@ impl Display for AdbLibusb {
 impl AdbEnv for AdbLibusb {
     const NAME: &'static str = "ADB_LIBUSB";
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clear_all() {
+        let mut envs = AdbEnvs::default();
+        assert!(envs.is_empty());
+        envs.set_adb_trace(vec![AdbTraceEnum::Adb]);
+        envs.set_android_serial("emulator-5554".to_string());
+        envs.set_adb_libusb(false);
+        assert!(!envs.is_empty());
+        envs.clear_all();
+        assert!(envs.is_empty());
+        assert_eq!(envs, AdbEnvs::default());
+    }
+
+    #[test]
+    fn test_planned_env() {
+        let mut envs = AdbEnvs::default();
+        envs.set_android_serial("emulator-5554".to_string());
+        envs.set_adb_local_transport_max_port(5600);
+        envs.set_adb_libusb(false);
+        let plan = envs.planned_env();
+        assert_eq!(
+            plan,
+            [
+                ("ADB_TRACE", None),
+                ("ADB_VENDOR_KEYS", None),
+                ("ANDROID_SERIAL", Some("emulator-5554".to_string())),
+                ("ANDROID_LOG_TAGS", None),
+                ("ADB_LOCAL_TRANSPORT_MAX_PORT", Some("5600".to_string())),
+                ("ADB_MDNS_AUTO_CONNECT", None),
+                ("ADB_MDNS_OPENSCREEN", None),
+                ("ADB_LIBUSB", Some("0".to_string())),
+            ]
+        );
+
+        let mut cmd = Command::new("adb");
+        envs.apply(&mut cmd);
+        let mut applied = cmd
+            .get_envs()
+            .map(|(key, value)| {
+                (
+                    key.to_string_lossy().into_owned(),
+                    value.map(|value| value.to_string_lossy().into_owned()),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut plan = plan
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect::<Vec<_>>();
+        applied.sort();
+        plan.sort();
+        assert_eq!(applied, plan);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut base = AdbEnvs::default();
+        base.set_adb_trace(vec![AdbTraceEnum::Adb]);
+        base.set_android_serial("emulator-5554".to_string());
+        base.set_adb_libusb(true);
+
+        let mut overrides = AdbEnvs::default();
+        overrides.set_android_serial("emulator-5556".to_string());
+        overrides.set_adb_local_transport_max_port(5600);
+        overrides.set_adb_libusb(false);
+
+        let merged = base.clone().merged(&overrides);
+        assert_eq!(merged.adb_trace(), Some(&[AdbTraceEnum::Adb][..]));
+        assert_eq!(merged.android_serial(), Some("emulator-5556"));
+        assert_eq!(merged.adb_local_transport_max_port(), Some(5600));
+        assert_eq!(merged.adb_libusb(), Some(false));
+        assert_eq!(merged.adb_vendor_keys(), None);
+        assert_eq!(merged.android_log_tags(), None);
+
+        assert_eq!(base.clone().merged(&AdbEnvs::default()), base);
+        assert_eq!(AdbEnvs::default().merged(&base), base);
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut old = AdbEnvs::default();
+        old.set_android_serial("emulator-5554".to_string());
+        old.set_adb_libusb(true);
+        assert!(old.diff(&old.clone()).is_empty());
+        assert!(AdbEnvs::default().diff(&AdbEnvs::default()).is_empty());
+
+        let mut new = old.clone();
+        new.set_android_serial("emulator-5556".to_string());
+        assert_eq!(
+            old.diff(&new),
+            [(
+                "ANDROID_SERIAL",
+                Some("emulator-5554".to_string()),
+                Some("emulator-5556".to_string())
+            )]
+        );
+
+        new.remove_adb_libusb();
+        new.set_adb_trace(vec![AdbTraceEnum::Adb, AdbTraceEnum::Usb]);
+        assert_eq!(
+            old.diff(&new),
+            [
+                ("ADB_TRACE", None, Some("adb,usb".to_string())),
+                (
+                    "ANDROID_SERIAL",
+                    Some("emulator-5554".to_string()),
+                    Some("emulator-5556".to_string())
+                ),
+                ("ADB_LIBUSB", Some("1".to_string()), None),
+            ]
+        );
+    }
+}
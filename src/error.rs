@@ -2,11 +2,32 @@
 
 use std::env::VarError;
 use std::error::Error;
+use std::ffi::OsString;
 use std::fmt::Display;
 use std::io;
+use std::process::ExitStatus;
+use std::time::Duration;
 
 use thiserror::Error;
 
+use crate::command::app_installation::InstallFailureReason;
+
+/// The stderr patterns of [`AdbError::NonZeroExit`] considered transient by [`AdbError::is_transient`],
+/// matched case-insensitively as substrings.
+pub const TRANSIENT_STDERR_PATTERNS: &[&str] = &[
+    "device offline",
+    "error: closed",
+    "protocol fault",
+    "device still authorizing",
+];
+
+/// The stderr patterns of [`AdbError::NonZeroExit`] that mark a deterministic failure,
+/// which [`AdbError::is_transient`] never considers transient, even if a transient pattern matches.
+///
+/// They're the prefixes of the failure codes reported by the package manager,
+/// e.g. `INSTALL_FAILED_VERSION_DOWNGRADE` or `INSTALL_PARSE_FAILED_NO_CERTIFICATES`.
+pub const PERMANENT_STDERR_PATTERNS: &[&str] = &["INSTALL_FAILED_", "INSTALL_PARSE_FAILED_"];
+
 /// Adb errors.
 #[derive(Debug, Error)]
 pub enum AdbError {
@@ -19,6 +40,137 @@ pub enum AdbError {
     /// Parse error.
     #[error(transparent)]
     Parse(ParseError),
+    /// The command didn't finish in time and was killed.
+    #[error("Command timed out after {0:?}")]
+    Timeout(Duration),
+    /// The argument contains an interior NUL byte, which can't be passed to a process.
+    #[error("Argument {0:?} contains an interior NUL byte")]
+    NulArgument(OsString),
+    /// The command exited with a non-zero status.
+    #[error("Command failed with {status}: {}", stderr.trim())]
+    NonZeroExit {
+        /// The exit status of the command.
+        status: ExitStatus,
+        /// The captured stderr of the command.
+        stderr: String,
+    },
+    /// The package manager reported `Failure [REASON]` for an installation.
+    #[error("Installation failed: {0}")]
+    InstallFailed(InstallFailureReason),
+    /// The command (e.g. `attach`) requires the libusb backend,
+    /// but it's disabled by `ADB_LIBUSB=0` in the [`AdbEnvs`](crate::AdbEnvs) of the instance.
+    #[error("`{0}` requires the libusb backend, but it is disabled by `ADB_LIBUSB=0`")]
+    LibusbDisabled(&'static str),
+    /// `connect` printed no `connected to` message, with the output of the last attempt.
+    #[error("Failed to connect: {}", .0.trim())]
+    ConnectFailed(String),
+    /// `pair` printed no `Successfully paired` message, or no pairing service was discovered,
+    /// with the output of `pair` or a description of the missing service.
+    #[error("Failed to pair: {}", .0.trim())]
+    PairFailed(String),
+    /// The checksum (or size) of the transferred file differs on the host and the device,
+    /// or the file is missing on either side, with the remote path of the file.
+    #[error("Verification failed for `{0}`")]
+    VerificationFailed(String),
+    /// The remote shell command reported a failure in its output despite a successful exit status,
+    /// e.g. `pm clear` printing `Failed`, with the output of the command.
+    #[error("Shell command failed: {}", .0.trim())]
+    ShellCommandFailed(String),
+    /// The package isn't installed on the device, with the name of the package.
+    #[error("Package not found: {0}")]
+    PackageNotFound(String),
+    /// Some steps of a best-effort sequence (e.g. [`Adb::prepare_for_ui_test`](crate::Adb::prepare_for_ui_test))
+    /// failed, with each failed step and its error. The other steps were still executed.
+    #[error("{} step(s) failed: {}", .0.len(), display_steps(.0))]
+    StepsFailed(Vec<(String, AdbError)>),
+}
+
+impl AdbError {
+    /// Whether the adb binary (or another program) wasn't found,
+    /// i.e. an [`AdbError::Io`] with [`io::ErrorKind::NotFound`].
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, AdbError::Io(e) if e.kind() == io::ErrorKind::NotFound)
+    }
+
+    /// Whether the command was killed for timing out, i.e. an [`AdbError::Timeout`].
+    pub fn is_timed_out(&self) -> bool {
+        matches!(self, AdbError::Timeout(_))
+    }
+
+    /// Whether the command was executed but reported a failure,
+    /// by its exit status or its output (e.g. [`AdbError::NonZeroExit`], [`AdbError::InstallFailed`]).
+    pub fn is_command_failed(&self) -> bool {
+        matches!(
+            self,
+            AdbError::NonZeroExit { .. }
+                | AdbError::InstallFailed(_)
+                | AdbError::ConnectFailed(_)
+                | AdbError::PairFailed(_)
+                | AdbError::VerificationFailed(_)
+                | AdbError::ShellCommandFailed(_)
+                | AdbError::PackageNotFound(_)
+                | AdbError::StepsFailed(_)
+        )
+    }
+
+    /// Whether the error is likely to go away if the command is retried, e.g. while a device reconnects.
+    ///
+    /// The following errors are transient:
+    ///
+    /// - [`AdbError::Io`] with [`io::ErrorKind::BrokenPipe`], [`io::ErrorKind::ConnectionReset`],
+    ///   [`io::ErrorKind::ConnectionAborted`] or [`io::ErrorKind::Interrupted`].
+    /// - [`AdbError::NonZeroExit`] whose stderr contains any of [`TRANSIENT_STDERR_PATTERNS`]
+    ///   (case-insensitive), i.e. `device offline`, `error: closed`, `protocol fault` or `device still authorizing`,
+    ///   unless it contains any of [`PERMANENT_STDERR_PATTERNS`] (e.g. `INSTALL_FAILED_VERSION_DOWNGRADE`).
+    ///
+    /// Timeouts are not transient, since retrying doubles the wait, check [`Self::is_timed_out`] as well if needed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use adbr::AdbError;
+    /// assert!(AdbError::Io(io::ErrorKind::BrokenPipe.into()).is_transient());
+    /// assert!(!AdbError::Io(io::ErrorKind::NotFound.into()).is_transient());
+    /// ```
+    pub fn is_transient(&self) -> bool {
+        match self {
+            AdbError::Io(e) => matches!(
+                e.kind(),
+                io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::Interrupted
+            ),
+            AdbError::NonZeroExit { stderr, .. } => {
+                if PERMANENT_STDERR_PATTERNS
+                    .iter()
+                    .any(|pattern| stderr.contains(pattern))
+                {
+                    return false;
+                }
+                let stderr = stderr.to_lowercase();
+                TRANSIENT_STDERR_PATTERNS
+                    .iter()
+                    .any(|pattern| stderr.contains(pattern))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a value (e.g. the output of a command) failed to parse, i.e. an [`AdbError::Parse`].
+    pub fn is_parse(&self) -> bool {
+        matches!(self, AdbError::Parse(_))
+    }
+}
+
+/// Joins the failed steps of [`AdbError::StepsFailed`] as `STEP: ERROR; ...`.
+fn display_steps(steps: &[(String, AdbError)]) -> String {
+    steps
+        .iter()
+        .map(|(step, err)| format!("`{}`: {}", step, err))
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 /// Information about a parse failure.
@@ -84,3 +236,84 @@ impl From<ParseError> for AdbError {
         Self::Parse(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::command::tests::output;
+
+    #[test]
+    fn test_predicates() {
+        let not_found = AdbError::Io(io::Error::from(io::ErrorKind::NotFound));
+        assert!(not_found.is_not_found());
+        assert!(!AdbError::Io(io::Error::from(io::ErrorKind::PermissionDenied)).is_not_found());
+
+        let timeout = AdbError::Timeout(Duration::from_secs(1));
+        assert!(timeout.is_timed_out());
+        assert!(!not_found.is_timed_out());
+
+        let failed = AdbError::NonZeroExit {
+            status: output(1, "", "").status,
+            stderr: String::new(),
+        };
+        assert!(failed.is_command_failed());
+        assert!(AdbError::ShellCommandFailed("Failed".to_string()).is_command_failed());
+        assert!(!timeout.is_command_failed());
+        assert!(!not_found.is_command_failed());
+
+        let parse = AdbError::from(ParseError::with_description("x", "u32", "invalid"));
+        assert!(parse.is_parse());
+        assert!(!parse.is_command_failed());
+        assert!(!failed.is_parse());
+    }
+
+    #[test]
+    fn test_is_transient() {
+        let non_zero_exit = |stderr: &str| AdbError::NonZeroExit {
+            status: output(1, "", stderr).status,
+            stderr: stderr.to_string(),
+        };
+        assert!(non_zero_exit("error: device offline\n").is_transient());
+        assert!(non_zero_exit("error: closed").is_transient());
+        assert!(non_zero_exit("error: protocol fault (couldn't read status)").is_transient());
+        assert!(non_zero_exit("error: device still authorizing").is_transient());
+        assert!(non_zero_exit("error: Device Offline").is_transient());
+        assert!(!non_zero_exit("error: device unauthorized.").is_transient());
+        assert!(!non_zero_exit("").is_transient());
+        // a permanent error of the app, not adb losing the connection
+        assert!(!non_zero_exit("stream closed by remote: permission denied").is_transient());
+        assert!(!non_zero_exit(
+            "adb: failed to install app.apk: Failure [INSTALL_FAILED_VERSION_DOWNGRADE]"
+        )
+        .is_transient());
+        assert!(
+            !non_zero_exit("Failure [INSTALL_PARSE_FAILED_NO_CERTIFICATES]\nerror: closed")
+                .is_transient()
+        );
+
+        assert!(AdbError::Io(io::ErrorKind::ConnectionReset.into()).is_transient());
+        assert!(!AdbError::Io(io::ErrorKind::PermissionDenied.into()).is_transient());
+        assert!(!AdbError::Timeout(Duration::from_secs(1)).is_transient());
+        assert!(!AdbError::ShellCommandFailed("closed".to_string()).is_transient());
+    }
+
+    #[test]
+    fn test_steps_failed() {
+        let err = AdbError::StepsFailed(vec![
+            (
+                "settings put global a 0".to_string(),
+                AdbError::ShellCommandFailed("denied\n".to_string()),
+            ),
+            (
+                "settings put global b 0".to_string(),
+                AdbError::NulArgument("b\0".into()),
+            ),
+        ]);
+        assert_eq!(
+            err.to_string(),
+            "2 step(s) failed: `settings put global a 0`: Shell command failed: denied; \
+             `settings put global b 0`: Argument \"b\\0\" contains an interior NUL byte"
+        );
+    }
+}
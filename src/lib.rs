@@ -79,6 +79,26 @@
 //! );
 //! ```
 //!
+//! To layer per-device overrides on a base configuration,
+//! you can use [`AdbEnvs::merge`] or [`AdbEnvs::merged`],
+//! where only the variables set in the overrides replace the base ones.
+//!
+//! ```
+//! # use adbr::{Adb, AdbEnvs};
+//! let mut base = AdbEnvs::default();
+//! base.set_adb_libusb(true);
+//!
+//! for serial in ["emulator-5554", "emulator-5556"] {
+//!     let mut overrides = AdbEnvs::default();
+//!     overrides.set_android_serial(serial.to_string());
+//!
+//!     let mut adb = Adb::default();
+//!     *adb.envs_mut() = base.clone().merged(&overrides);
+//!     assert_eq!(adb.envs().android_serial(), Some(serial));
+//!     assert_eq!(adb.envs().adb_libusb(), Some(true));
+//! }
+//! ```
+//!
 //! To get and modify the environment variables of the current process,
 //! you can use [`envs::AdbEnv::get`] and [`envs::AdbEnv::set`] methods.
 //!
@@ -91,19 +111,72 @@
 //!     Some(AdbLocalTransportMaxPort(1234)),
 //! );
 //! ```
+//!
+//! # Command Line Integration
+//!
+//! The option enums ([`AdbCompressionAlgorithm`],
+//! [`DeviceState`](command::DeviceState), [`AdbWaitForTransport`](command::AdbWaitForTransport),
+//! [`AdbRebootTarget`](command::AdbRebootTarget) and [`AdbSyncTarget`](command::AdbSyncTarget))
+//! list their variants with `all()`, display their canonical names with [`Display`](std::fmt::Display),
+//! and parse them back with [`FromStr`](std::str::FromStr) or `TryFrom<&OsStr>`,
+//! which is everything a command line parser needs for the possible values and the help:
+//!
+//! ```
+//! use adbr::command::AdbCompressionAlgorithm;
+//!
+//! let names = AdbCompressionAlgorithm::all()
+//!     .iter()
+//!     .map(ToString::to_string)
+//!     .collect::<Vec<_>>();
+//! assert_eq!(names, ["any", "none", "brotli", "lz4", "zstd"]);
+//! for name in names {
+//!     assert_eq!(name.parse::<AdbCompressionAlgorithm>().unwrap().to_string(), name);
+//! }
+//! ```
+//!
+//! With the `clap` feature, they also implement `clap::ValueEnum`,
+//! so they can be used as typed arguments with the possible values listed in the help:
+//!
+//! ```
+//! # #[cfg(feature = "clap")]
+//! # {
+//! use adbr::command::AdbCompressionAlgorithm;
+//! use clap::builder::EnumValueParser;
+//!
+//! let cmd = clap::Command::new("push").arg(
+//!     clap::Arg::new("compression")
+//!         .long("compression")
+//!         .value_parser(EnumValueParser::<AdbCompressionAlgorithm>::new()),
+//! );
+//! let matches = cmd.get_matches_from(["push", "--compression", "zstd"]);
+//! assert_eq!(
+//!     matches.get_one::<AdbCompressionAlgorithm>("compression"),
+//!     Some(&AdbCompressionAlgorithm::Zstd)
+//! );
+//! # }
+//! ```
 
 pub mod command;
 pub mod envs;
 pub mod error;
 pub mod socket;
 
+mod cache;
+
+use std::collections::BTreeSet;
+use std::env;
+use std::ffi::{OsStr, OsString};
 use std::fs::canonicalize;
 use std::io;
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use cache::{Cache, Once};
+use command::file_transfer::AdbCompressionAlgorithm;
 use command::AdbCommandBuilder;
 
-pub use command::global_option::AdbGlobalOption;
+pub use command::global_option::{AdbGlobalOption, DEFAULT_ADB_SERVER_PORT};
 pub use command::AdbCommand;
 pub use envs::AdbEnvs;
 pub use error::AdbError;
@@ -116,6 +189,13 @@ pub type AdbResult<T> = Result<T, AdbError>;
 /// It contains working directory and environment variables to build and execute adb commands.
 ///
 /// See [crate level documentation](crate) for more information.
+///
+/// # Equality
+///
+/// [`PartialEq`] compares all the settings, including the default timeout and the remote server.
+/// The working directory is compared as canonicalized when it was set,
+/// so it may differ from the current canonical path if a symlink has changed since,
+/// use [`Adb::same_config`] to compare the adb binary and environment variables only.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Adb {
     /// The canonical directory where the adb binary is located.
@@ -123,6 +203,50 @@ pub struct Adb {
     working_directory: Option<PathBuf>,
     /// Adb environment variables.
     envs: AdbEnvs,
+    /// The default timeout of [`AdbCommand::output`] and [`AdbCommand::status`].
+    default_timeout: Option<Duration>,
+    /// The adb server (`-H` and `-P`) preset on every command, see [`Adb::remote`].
+    remote_server: Option<(IpAddr, u16)>,
+    /// The global options preset on every command, see [`Adb::add_default_global_option`].
+    default_global_options: BTreeSet<AdbGlobalOption>,
+    /// The compression algorithms negotiated by `z_auto` (e.g. [`AdbPush::z_auto`](command::file_transfer::AdbPush::z_auto)),
+    /// keyed by the global options of the commands selecting the device with `-s SERIAL`.
+    compression: Cache<Option<AdbCompressionAlgorithm>>,
+    /// The adb binary resolved in `PATH` ([`None`] if it can't be), see [`Adb::program`].
+    binary: Once<Option<PathBuf>>,
+}
+
+/// The file name of the adb binary.
+const ADB: &str = "adb";
+
+/// Resolves `name` in the directories of `path` (the value of `PATH`) like `execvp` does,
+/// i.e. the first executable file named `name`.
+///
+/// Returns [`None`] if it's not found or if a relative directory comes first,
+/// since it would be resolved against the working directory of the child process.
+#[cfg(unix)]
+fn resolve_in_path(path: &OsStr, name: &str) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    for dir in env::split_paths(path) {
+        if dir.is_relative() {
+            return None;
+        }
+        let candidate = dir.join(name);
+        if let Ok(metadata) = candidate.metadata() {
+            if metadata.is_file() && metadata.permissions().mode() & 0o111 != 0 {
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+/// Always [`None`], since Windows searches other directories (e.g. the one of the current executable)
+/// before `PATH`, so the lookup is left to the OS.
+#[cfg(not(unix))]
+fn resolve_in_path(_: &OsStr, _: &str) -> Option<PathBuf> {
+    None
 }
 
 impl Adb {
@@ -133,9 +257,55 @@ impl Adb {
         Ok(Self {
             working_directory: None,
             envs: AdbEnvs::new()?,
+            default_timeout: None,
+            remote_server: None,
+            default_global_options: BTreeSet::new(),
+            compression: Cache::default(),
+            binary: Once::default(),
         })
     }
 
+    /// Creates a new [`Adb`] instance targeting the adb server at `host:port`.
+    ///
+    /// The `-H` and `-P` global options are preset on every command built from this instance,
+    /// so there's no need to call [`Adb::server`] on each of them.
+    ///
+    /// # Note
+    ///
+    /// A local adb binary (in `PATH`, or set by [`Self::set_working_directory`]) is still required,
+    /// it's the client that talks to the remote server.
+    ///
+    /// # Examples
+    ///
+    /// `adb -H 192.168.0.2 -P 5037 devices`
+    ///
+    /// ```no_run
+    /// # use std::net::Ipv4Addr;
+    /// # use adbr::{Adb, AdbCommand};
+    /// let adb = Adb::remote(Ipv4Addr::new(192, 168, 0, 2), 5037).unwrap();
+    /// adb.devices()
+    ///     .status()
+    ///     .expect("`adb -H 192.168.0.2 -P 5037 devices` failed");
+    /// ```
+    pub fn remote<A: Into<IpAddr>>(host: A, port: u16) -> AdbResult<Self> {
+        let mut adb = Self::new()?;
+        adb.set_remote_server(Some((host.into(), port)));
+        Ok(adb)
+    }
+
+    /// The adb server (host and port) preset on every command, see [`Self::remote`].
+    pub fn remote_server(&self) -> Option<(IpAddr, u16)> {
+        self.remote_server
+    }
+
+    /// Sets the adb server (host and port) preset on every command, see [`Self::remote`].
+    ///
+    /// `None` means no preset, which is the default.
+    pub fn set_remote_server(&mut self, server: Option<(IpAddr, u16)>) -> &mut Self {
+        self.remote_server = server;
+        self
+    }
+
     /// Creates a new `Adb` instance with the adb binary located at `path`.
     ///
     /// See [`Self::set_working_directory`] for more information.
@@ -152,6 +322,32 @@ impl Adb {
         self.working_directory.as_deref()
     }
 
+    /// Whether `self` and `other` run the same adb binary with the same environment variables,
+    /// e.g. to deduplicate instances configured from equivalent paths.
+    ///
+    /// Unlike [`PartialEq`], the working directories are canonicalized again before comparison
+    /// (falling back to the stored path if that fails), and the other settings
+    /// (e.g. [`Self::default_timeout`]) are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use adbr::Adb;
+    /// let a = Adb::default();
+    /// let mut b = Adb::default();
+    /// b.with_default_timeout(Some(Duration::from_secs(5)));
+    /// assert_ne!(a, b);
+    /// assert!(a.same_config(&b));
+    /// ```
+    pub fn same_config(&self, other: &Adb) -> bool {
+        fn normalize(dir: Option<&Path>) -> Option<PathBuf> {
+            dir.map(|dir| canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf()))
+        }
+        self.envs == other.envs
+            && normalize(self.working_directory()) == normalize(other.working_directory())
+    }
+
     /// Sets the directory where the adb binary is located.
     ///
     /// The input `path` will be canonicalized.
@@ -171,6 +367,7 @@ impl Adb {
         let dir = canonicalize(path)?;
         if dir.is_dir() {
             self.working_directory = Some(dir);
+            self.binary = Once::default();
             Ok(self)
         } else {
             Err(io::Error::new(
@@ -186,9 +383,24 @@ impl Adb {
     /// The adb binary will be searched in an OS-defined way in `PATH`.
     pub fn reset_working_directory(&mut self) -> &mut Self {
         self.working_directory = None;
+        self.binary = Once::default();
         self
     }
 
+    /// The program of adb commands, i.e. the adb binary resolved in `PATH`.
+    ///
+    /// The binary is resolved once, so building many commands doesn't search `PATH` repeatedly.
+    /// `PATH` is frozen at the first command: later changes of `PATH`, or moving the binary,
+    /// aren't noticed until the working directory is set or reset.
+    /// If it can't be resolved, `adb` is returned (and the failure cached),
+    /// leaving the lookup (and its error) to the OS at each spawn.
+    pub(crate) fn program(&self) -> OsString {
+        self.binary
+            .get_or_init(|| env::var_os("PATH").and_then(|path| resolve_in_path(&path, ADB)))
+            .as_ref()
+            .map_or_else(|| ADB.into(), |path| path.clone().into_os_string())
+    }
+
     /// Gets the adb environment variables.
     pub fn envs(&self) -> &AdbEnvs {
         &self.envs
@@ -199,8 +411,202 @@ impl Adb {
         &mut self.envs
     }
 
-    /// Creates a new [`AdbCommandBuilder`].
-    fn command(&self) -> AdbCommandBuilder {
-        AdbCommandBuilder::new(self)
+    /// The default timeout of [`AdbCommand::output`] and [`AdbCommand::status`].
+    ///
+    /// If [`None`] (the default), commands run without a time bound.
+    pub fn default_timeout(&self) -> Option<Duration> {
+        self.default_timeout
+    }
+
+    /// Sets the default timeout of [`AdbCommand::output`] and [`AdbCommand::status`].
+    ///
+    /// When set, every command executed by these methods is killed if it doesn't finish in time,
+    /// returning [`AdbError::Timeout`]. [`AdbCommand::output_timeout`] and [`AdbCommand::status_timeout`]
+    /// override the default timeout, while [`AdbCommand::spawn`] is never bounded.
+    ///
+    /// `None` means no timeout, which is the default.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use adbr::{Adb, AdbCommand, AdbError};
+    /// use adbr::command::DeviceState;
+    ///
+    /// let mut adb = Adb::new().unwrap();
+    /// adb.with_default_timeout(Some(Duration::from_secs(30)));
+    /// match adb.wait_for(DeviceState::Device).status() {
+    ///     Err(AdbError::Timeout(_)) => println!("no device in 30 seconds"),
+    ///     result => println!("{:?}", result),
+    /// }
+    /// ```
+    pub fn with_default_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.default_timeout = timeout;
+        self
+    }
+
+    /// The global options preset on every command, see [`Self::add_default_global_option`].
+    pub fn default_global_options(&self) -> impl Iterator<Item = &AdbGlobalOption> {
+        self.default_global_options.iter()
+    }
+
+    /// Adds a global option preset on every command built from this instance,
+    /// e.g. `-s SERIAL` for a fixed target device.
+    ///
+    /// An existing default option of the same kind (e.g. another `-s SERIAL`) is replaced.
+    /// Like the other global options, a default one is overridden by the same kind of option
+    /// added to a command (e.g. [`AdbCommandBuilder::s_serial`]),
+    /// and removed by [`AdbCommandBuilder::without_global_options`].
+    /// The `-H` and `-P` options of a [remote server](Self::remote) take precedence over the default ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::{Adb, AdbGlobalOption};
+    /// let mut adb = Adb::default();
+    /// adb.add_default_global_option(AdbGlobalOption::Serial("emulator-5554".to_string()));
+    /// assert_eq!(adb.builder().to_string(), "adb -s emulator-5554");
+    /// assert_eq!(adb.s_serial("emulator-5556").to_string(), "adb -s emulator-5556");
+    /// ```
+    pub fn add_default_global_option(&mut self, opt: AdbGlobalOption) -> &mut Self {
+        let kind = std::mem::discriminant(&opt);
+        self.default_global_options
+            .retain(|existing| std::mem::discriminant(existing) != kind);
+        self.default_global_options.insert(opt);
+        self
+    }
+
+    /// Removes all the global options preset on every command, see [`Self::add_default_global_option`].
+    pub fn clear_default_global_options(&mut self) -> &mut Self {
+        self.default_global_options.clear();
+        self
+    }
+
+    /// Creates a new [`AdbCommandBuilder`] with the [default global options](Self::add_default_global_option),
+    /// and `-H` and `-P` if the instance targets a [remote server](Self::remote).
+    /// Use [`AdbCommandBuilder::without_global_options`] to opt out of them.
+    ///
+    /// The builder borrows this instance, so it (and the commands built from it)
+    /// can't outlive the [`Adb`] instance, nor can the instance be modified meanwhile.
+    ///
+    /// Since [`AdbCommandBuilder`] is [`Clone`], a builder configured once
+    /// (e.g. with global options) can be used as a template for many commands.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use adbr::{Adb, AdbCommand};
+    /// # let adb = Adb::new().unwrap();
+    /// let usb = adb.builder().d_usb().exit_on_write_error();
+    ///
+    /// usb.clone().root().status().expect("`adb -d --exit-on-write-error root` failed");
+    /// usb.shell().arg("id").status().expect("`adb -d --exit-on-write-error shell id` failed");
+    /// ```
+    pub fn builder(&self) -> AdbCommandBuilder<'_> {
+        self.command()
+    }
+
+    /// Creates a new [`AdbCommandBuilder`] with the preset global options.
+    fn command(&self) -> AdbCommandBuilder<'_> {
+        let acb = self
+            .default_global_options
+            .iter()
+            .cloned()
+            .fold(AdbCommandBuilder::new(self), |acb, opt| {
+                acb.add_global_option_unchecked(opt)
+            });
+        match self.remote_server {
+            Some((host, port)) => acb.server(host, port),
+            None => acb,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_global_options() {
+        let mut adb = Adb::default();
+        adb.add_default_global_option(AdbGlobalOption::Serial("a".to_string()))
+            .add_default_global_option(AdbGlobalOption::Usb)
+            .add_default_global_option(AdbGlobalOption::Serial("b".to_string()));
+        assert_eq!(adb.default_global_options().count(), 2);
+        assert_eq!(adb.builder().to_string(), "adb -d -s b");
+        assert_eq!(adb.s_serial("c").to_string(), "adb -d -s c");
+        assert_eq!(adb.builder().without_global_options().to_string(), "adb");
+
+        adb.add_default_global_option(AdbGlobalOption::Port(5038));
+        adb.set_remote_server(Some((IpAddr::from([127, 0, 0, 1]), 5039)));
+        assert_eq!(
+            adb.builder().to_string(),
+            "adb -d -s b -H 127.0.0.1 -P 5039"
+        );
+
+        adb.clear_default_global_options();
+        assert_eq!(adb.builder().to_string(), "adb -H 127.0.0.1 -P 5039");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_in_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = env::temp_dir().join(format!("adbr-resolve-{}", std::process::id()));
+        let (empty, plain, bin) = (root.join("empty"), root.join("plain"), root.join("bin"));
+        for dir in [&empty, &plain, &bin] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(plain.join("adb"), "").unwrap();
+        std::fs::write(bin.join("adb"), "").unwrap();
+        std::fs::set_permissions(bin.join("adb"), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let path = env::join_paths([&empty, &plain, &bin]).unwrap();
+        assert_eq!(resolve_in_path(&path, "adb"), Some(bin.join("adb")));
+        assert_eq!(resolve_in_path(&path, "fastboot"), None);
+        let relative = env::join_paths([Path::new("relative"), &bin]).unwrap();
+        assert_eq!(resolve_in_path(&relative, "adb"), None);
+
+        let mut adb = Adb::default();
+        let resolved = env::var_os("PATH").and_then(|path| resolve_in_path(&path, ADB));
+        assert_eq!(
+            adb.program(),
+            resolved
+                .clone()
+                .map_or_else(|| ADB.into(), PathBuf::into_os_string)
+        );
+        assert_eq!(adb.binary.get(), Some(&resolved));
+        adb.set_working_directory(&bin).unwrap();
+        assert_eq!(adb.binary.get(), None);
+        adb.program();
+        adb.reset_working_directory();
+        assert_eq!(adb.binary.get(), None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_same_config() {
+        let root = std::env::temp_dir().join(format!("adbr-same-config-{}", std::process::id()));
+        let dir = root.join("platform-tools");
+        let link = root.join("link");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+
+        let mut a = Adb::default();
+        a.set_working_directory(&dir).unwrap();
+        let mut b = Adb::default();
+        b.set_working_directory(root.join("link/../platform-tools"))
+            .unwrap();
+        b.with_default_timeout(Some(Duration::from_secs(1)));
+        assert!(a.same_config(&b));
+        b.set_working_directory(&link).unwrap();
+        assert!(a.same_config(&b));
+        assert!(!a.same_config(&Adb::default()));
+        b.envs_mut().set_android_serial("emulator-5554".to_string());
+        assert!(!a.same_config(&b));
+
+        std::fs::remove_dir_all(&root).unwrap();
     }
 }
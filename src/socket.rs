@@ -21,6 +21,142 @@ pub enum AdbSocketFamily {
     AcceptFd(AcceptFd),
 }
 
+impl AdbSocketFamily {
+    /// Creates a `(local, remote)` pair of `tcp:PORT` sockets with the same `port`,
+    /// the most common arguments of `forward` and `reverse`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::socket::AdbSocketFamily;
+    /// let (local, remote) = AdbSocketFamily::tcp_pair(8080);
+    /// assert_eq!(local.to_string(), "tcp:8080");
+    /// assert_eq!(remote.to_string(), "tcp:8080");
+    /// ```
+    pub fn tcp_pair(port: u16) -> (Self, Self) {
+        let tcp = Self::Tcp(Tcp::with_port(port));
+        (tcp.clone(), tcp)
+    }
+
+    /// Whether the socket can be listened on, e.g. by the adb server with `-L`.
+    ///
+    /// `dev`, `dev-raw` and `jdwp` sockets can only be connected to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::socket::AdbSocketFamily;
+    /// assert!("localabstract:adb".parse::<AdbSocketFamily>().unwrap().is_listenable());
+    /// assert!(!"jdwp:1234".parse::<AdbSocketFamily>().unwrap().is_listenable());
+    /// ```
+    pub fn is_listenable(&self) -> bool {
+        !matches!(
+            self,
+            AdbSocketFamily::Dev(_) | AdbSocketFamily::DevRaw(_) | AdbSocketFamily::Jdwp(_)
+        )
+    }
+
+    /// Whether the socket displays as a parsable argument, i.e. it round-trips through [`FromStr`].
+    ///
+    /// A [`Tcp`] without an address and a port, or a socket with an empty name, is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::socket::{AdbSocketFamily, LocalAbstract, Tcp};
+    /// assert!(AdbSocketFamily::Tcp(Tcp::with_port(5037)).is_valid());
+    /// assert!(!AdbSocketFamily::Tcp(Tcp { ip: None, port: None }).is_valid());
+    /// assert!(!AdbSocketFamily::LocalAbstract(LocalAbstract(String::new())).is_valid());
+    /// ```
+    pub fn is_valid(&self) -> bool {
+        match self {
+            AdbSocketFamily::Tcp(tcp) => tcp.is_valid(),
+            AdbSocketFamily::LocalAbstract(LocalAbstract(name))
+            | AdbSocketFamily::LocalReserved(LocalReserved(name))
+            | AdbSocketFamily::LocalFileSystem(LocalFileSystem(name))
+            | AdbSocketFamily::Dev(Dev(name))
+            | AdbSocketFamily::DevRaw(DevRaw(name)) => !name.is_empty(),
+            AdbSocketFamily::Jdwp(_) | AdbSocketFamily::Vsock(_) | AdbSocketFamily::AcceptFd(_) => {
+                true
+            }
+        }
+    }
+
+    /// Parses a socket of `forward` or `reverse` leniently,
+    /// where a bare port number (e.g. `1234`) means `tcp:PORT`.
+    ///
+    /// Other strings are parsed strictly with [`FromStr`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::socket::{AdbSocketFamily, Tcp};
+    /// assert_eq!(
+    ///     AdbSocketFamily::parse_forward_spec("1234").unwrap(),
+    ///     AdbSocketFamily::Tcp(Tcp::with_port(1234))
+    /// );
+    /// assert_eq!(
+    ///     AdbSocketFamily::parse_forward_spec("localabstract:foo").unwrap().to_string(),
+    ///     "localabstract:foo"
+    /// );
+    /// ```
+    pub fn parse_forward_spec(s: &str) -> AdbResult<Self> {
+        let s = s.trim();
+        match s.parse::<u16>() {
+            Ok(port) => Ok(AdbSocketFamily::Tcp(Tcp::with_port(port))),
+            Err(_) => s.parse(),
+        }
+    }
+
+    /// Returns the inner [`Tcp`] if this is a `tcp` socket, otherwise [`None`].
+    pub fn as_tcp(&self) -> Option<&Tcp> {
+        match self {
+            AdbSocketFamily::Tcp(tcp) => Some(tcp),
+            _ => None,
+        }
+    }
+
+    /// Returns the port if this is a `tcp` socket with a port, otherwise [`None`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use adbr::socket::AdbSocketFamily;
+    /// let (local, _) = AdbSocketFamily::tcp_pair(8080);
+    /// assert_eq!(local.tcp_port(), Some(8080));
+    /// ```
+    pub fn tcp_port(&self) -> Option<u16> {
+        self.as_tcp().and_then(|tcp| tcp.port)
+    }
+
+    /// Returns the [`SocketAddr`] if this is a fully specified `tcp` socket, otherwise [`None`].
+    ///
+    /// A [`Tcp`] without an address or a port (e.g. `tcp:8080`) yields [`None`],
+    /// since adb fills in the missing part itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::net::{Ipv4Addr, SocketAddr};
+    /// # use adbr::socket::AdbSocketFamily;
+    /// let socket: AdbSocketFamily = "tcp:127.0.0.1:8080".parse().unwrap();
+    /// assert_eq!(
+    ///     socket.as_socket_addr(),
+    ///     Some(SocketAddr::from((Ipv4Addr::LOCALHOST, 8080)))
+    /// );
+    /// assert_eq!(AdbSocketFamily::tcp_pair(8080).0.as_socket_addr(), None);
+    /// ```
+    pub fn as_socket_addr(&self) -> Option<SocketAddr> {
+        match self.as_tcp()? {
+            Tcp {
+                ip: Some(ip),
+                port: Some(port),
+            } => Some(SocketAddr::new(*ip, *port)),
+            _ => None,
+        }
+    }
+}
+
 impl Display for AdbSocketFamily {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -75,6 +211,28 @@ impl From<Tcp> for AdbSocketFamily {
     }
 }
 
+impl From<SocketAddr> for AdbSocketFamily {
+    fn from(addr: SocketAddr) -> Self {
+        AdbSocketFamily::Tcp(addr.into())
+    }
+}
+
+impl TryFrom<AdbSocketFamily> for SocketAddr {
+    type Error = AdbError;
+
+    /// Converts a fully specified `tcp` socket, see [`AdbSocketFamily::as_socket_addr`].
+    fn try_from(socket: AdbSocketFamily) -> Result<Self, Self::Error> {
+        socket.as_socket_addr().ok_or_else(|| {
+            ParseError::with_description(
+                socket,
+                "SocketAddr",
+                "expected a tcp socket with both an address and a port",
+            )
+            .into()
+        })
+    }
+}
+
 impl From<LocalAbstract> for AdbSocketFamily {
     fn from(local_abstract: LocalAbstract) -> Self {
         AdbSocketFamily::LocalAbstract(local_abstract)
@@ -130,7 +288,7 @@ impl From<AcceptFd> for AdbSocketFamily {
 /// `tcp:[host:[port]]`
 ///
 /// - `host`: Optional hostname or IP address.
-///     If an IPv6 address is provided, it should be enclosed in square brackets.
+///   If an IPv6 address is provided, it should be enclosed in square brackets.
 /// - `port`: Optional port number.
 ///
 /// # Note
@@ -155,6 +313,14 @@ pub struct Tcp {
 }
 
 impl Tcp {
+    /// Whether the socket has an IP address or a port number.
+    ///
+    /// `Tcp { ip: None, port: None }` is displayed as an empty string,
+    /// which can't be parsed back nor passed to adb.
+    pub const fn is_valid(&self) -> bool {
+        self.ip.is_some() || self.port.is_some()
+    }
+
     /// Creates a new `Tcp` socket with the given IP address and port number.
     pub const fn new(host: IpAddr, port: u16) -> Self {
         Self {
@@ -216,13 +382,7 @@ impl Tcp {
         match host.parse() {
             Ok(tcp) => Ok(tcp),
             Err(_) => {
-                let host = host.strip_prefix("tcp:").ok_or_else(|| {
-                    ParseError::with_description(
-                        host,
-                        "Tcp",
-                        "incomplete or invalid tcp syntax, expected `tcp:[host:[port]]`",
-                    )
-                })?;
+                let host = Self::strip_prefix(host)?;
                 Self::resolve(host).or_else(|e| {
                     // ToSocketAddrs requires a hostname with a port number.
                     // Retry if the input hostname does not contain a port number,
@@ -235,10 +395,69 @@ impl Tcp {
         }
     }
 
+    /// Like [`Tcp::from_host`], but resolves the hostname with [`tokio::net::lookup_host`]
+    /// without blocking the async runtime.
+    ///
+    /// The first IPv4 address is preferred as well, so the result is consistent with [`Tcp::from_host`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn example() {
+    /// use adbr::socket::Tcp;
+    /// use std::net::Ipv4Addr;
+    ///
+    /// let tcp = Tcp::from_host_async("tcp:localhost").await.unwrap();
+    /// assert_eq!(tcp, Tcp::with_ipv4(Ipv4Addr::new(127, 0, 0, 1)));
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn from_host_async(host: &str) -> AdbResult<Self> {
+        match host.parse() {
+            Ok(tcp) => Ok(tcp),
+            Err(_) => {
+                let host = Self::strip_prefix(host)?;
+                match Self::resolve_async(host).await {
+                    Ok(tcp) => Ok(tcp),
+                    // lookup_host requires a hostname with a port number as well.
+                    Err(e) => match Self::resolve_async(&format!("{host}:0")).await {
+                        Ok(tcp) => Ok(Self::with_ip(tcp.ip.unwrap())),
+                        _ => Err(e),
+                    },
+                }
+            }
+        }
+    }
+
+    fn strip_prefix(host: &str) -> AdbResult<&str> {
+        host.strip_prefix("tcp:").ok_or_else(|| {
+            ParseError::with_description(
+                host,
+                "Tcp",
+                "incomplete or invalid tcp syntax, expected `tcp:[host:[port]]`",
+            )
+            .into()
+        })
+    }
+
     fn resolve(host: &str) -> AdbResult<Self> {
-        let mut addrs = host
+        let addrs = host
             .to_socket_addrs()
             .map_err(|e| ParseError::with_source(host, "std::vec::IntoIter<SocketAddr>", e))?;
+        Self::select(host, addrs)
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn resolve_async(host: &str) -> AdbResult<Self> {
+        let addrs = tokio::net::lookup_host(host)
+            .await
+            .map_err(|e| ParseError::with_source(host, "std::vec::IntoIter<SocketAddr>", e))?;
+        Self::select(host, addrs)
+    }
+
+    /// Selects the first IPv4 address of the resolved addresses (`addrs`),
+    /// or the first address if there is no IPv4 address.
+    fn select(host: &str, mut addrs: impl Iterator<Item = SocketAddr>) -> AdbResult<Self> {
         let first = addrs.next();
         match first {
             None => Err(AdbError::Parse(ParseError::with_description(
@@ -386,7 +605,17 @@ pub struct DevRaw(pub String);
 ///
 /// # Syntax
 ///
-/// `jdwp:<process pid>`
+/// `jdwp:<process pid>`, where the pid is a decimal [`u32`] (`0..=4294967295`).
+/// Negative or overflowing pids are rejected.
+///
+/// # Examples
+///
+/// ```
+/// # use adbr::socket::Jdwp;
+/// assert_eq!("jdwp:4294967295".parse::<Jdwp>().unwrap(), Jdwp(u32::MAX));
+/// assert!("jdwp:4294967296".parse::<Jdwp>().is_err());
+/// assert!("jdwp:-1".parse::<Jdwp>().is_err());
+/// ```
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct Jdwp(pub u32);
 
@@ -394,7 +623,20 @@ pub struct Jdwp(pub u32);
 ///
 /// # Syntax
 ///
-/// `vsock:<cid>:<port>`
+/// `vsock:<cid>:<port>`, where both the cid and the port are decimal [`u32`]s (`0..=4294967295`).
+/// Negative or overflowing values are rejected.
+///
+/// # Examples
+///
+/// ```
+/// # use adbr::socket::Vsock;
+/// assert_eq!(
+///     "vsock:2:4294967295".parse::<Vsock>().unwrap(),
+///     Vsock { cid: 2, port: u32::MAX }
+/// );
+/// assert!("vsock:2:4294967296".parse::<Vsock>().is_err());
+/// assert!("vsock:-1:5555".parse::<Vsock>().is_err());
+/// ```
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct Vsock {
     pub cid: u32,
@@ -427,7 +669,7 @@ impl FromStr for Vsock {
                         .map_err(|e| ParseError::with_source(port, "port (u32)", e))?,
                 }),
                 None => Err(AdbError::Parse(ParseError::with_description(
-                    value,
+                    s,
                     "Vsock",
                     "missing port",
                 ))),
@@ -445,7 +687,17 @@ impl FromStr for Vsock {
 ///
 /// # Syntax
 ///
-/// `acceptfd:<fd>`
+/// `acceptfd:<fd>`, where the fd is a decimal [`u32`] (`0..=4294967295`).
+/// Negative or overflowing fds are rejected.
+///
+/// # Examples
+///
+/// ```
+/// # use adbr::socket::AcceptFd;
+/// assert_eq!("acceptfd:4294967295".parse::<AcceptFd>().unwrap(), AcceptFd(u32::MAX));
+/// assert!("acceptfd:4294967296".parse::<AcceptFd>().is_err());
+/// assert!("acceptfd:-1".parse::<AcceptFd>().is_err());
+/// ```
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct AcceptFd(pub u32);
 
@@ -525,10 +777,79 @@ from_str!(
     (AcceptFd, "acceptfd", "fd (u32)")
 );
 
+/// Generates a non-empty socket or device name without whitespace.
+#[cfg(any(test, feature = "arbitrary"))]
+pub(crate) fn arbitrary_name(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<String> {
+    const CHARS: &[u8] = b"abcXYZ019_-./:@[]";
+    let len = u.int_in_range(1..=12)?;
+    (0..len)
+        .map(|_| u.choose(CHARS).map(|&c| char::from(c)))
+        .collect()
+}
+
+/// Generates valid sockets only, i.e. a `Tcp` has an address or a port,
+/// so that `Display` and `FromStr` round-trip.
+#[cfg(any(test, feature = "arbitrary"))]
+impl<'a> arbitrary::Arbitrary<'a> for Tcp {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let ip = Option::<IpAddr>::arbitrary(u)?;
+        let port = match ip {
+            Some(_) => Option::<u16>::arbitrary(u)?,
+            None => Some(u16::arbitrary(u)?),
+        };
+        Ok(Tcp { ip, port })
+    }
+}
+
+/// Generates valid sockets only, see [`AdbSocketFamily::is_valid`].
+#[cfg(any(test, feature = "arbitrary"))]
+impl<'a> arbitrary::Arbitrary<'a> for AdbSocketFamily {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.choose_index(9)? {
+            0 => AdbSocketFamily::Tcp(Tcp::arbitrary(u)?),
+            1 => AdbSocketFamily::LocalAbstract(LocalAbstract(arbitrary_name(u)?)),
+            2 => AdbSocketFamily::LocalReserved(LocalReserved(arbitrary_name(u)?)),
+            3 => AdbSocketFamily::LocalFileSystem(LocalFileSystem(arbitrary_name(u)?)),
+            4 => AdbSocketFamily::Dev(Dev(arbitrary_name(u)?)),
+            5 => AdbSocketFamily::DevRaw(DevRaw(arbitrary_name(u)?)),
+            6 => AdbSocketFamily::Jdwp(Jdwp(u32::arbitrary(u)?)),
+            7 => AdbSocketFamily::Vsock(Vsock {
+                cid: u32::arbitrary(u)?,
+                port: u32::arbitrary(u)?,
+            }),
+            _ => AdbSocketFamily::AcceptFd(AcceptFd(u32::arbitrary(u)?)),
+        })
+    }
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
+    #[test]
+    fn test_socket_addr() {
+        let addr = SocketAddr::from((Ipv6Addr::LOCALHOST, 5555));
+        let socket = AdbSocketFamily::from(addr);
+        assert_eq!(socket.to_string(), "tcp:[::1]:5555");
+        assert_eq!(socket.as_socket_addr(), Some(addr));
+        assert_eq!(SocketAddr::try_from(socket).unwrap(), addr);
+        for socket in [
+            AdbSocketFamily::Tcp(Tcp::with_port(5555)),
+            AdbSocketFamily::Tcp(Tcp::with_ipv4(Ipv4Addr::LOCALHOST)),
+            AdbSocketFamily::Tcp(Tcp {
+                ip: None,
+                port: None,
+            }),
+            AdbSocketFamily::Jdwp(Jdwp(1234)),
+        ] {
+            assert_eq!(socket.as_socket_addr(), None);
+            assert!(matches!(
+                SocketAddr::try_from(socket),
+                Err(AdbError::Parse(_))
+            ));
+        }
+    }
+
     const TCP_COMMON: [(&str, Tcp); 5] = [
         ("tcp:5555", Tcp::with_port(5555)),
         ("tcp:127.0.0.1", Tcp::with_ipv4(Ipv4Addr::new(127, 0, 0, 1))),
@@ -585,6 +906,70 @@ mod tests {
         "tcp:a.b.c.d:p",
     ];
 
+    /// Feeds `f` with pseudo-random bytes (xorshift) for the property tests.
+    pub(crate) fn with_unstructured(mut f: impl FnMut(&mut arbitrary::Unstructured<'_>)) {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let bytes = (0..1 << 20)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+        let mut u = arbitrary::Unstructured::new(&bytes);
+        while !u.is_empty() {
+            f(&mut u);
+        }
+    }
+
+    #[test]
+    fn test_socket_round_trip() {
+        use arbitrary::Arbitrary;
+
+        with_unstructured(|u| {
+            let socket = AdbSocketFamily::arbitrary(u).unwrap();
+            let s = socket.to_string();
+            assert_eq!(s.parse::<AdbSocketFamily>().unwrap(), socket, "{}", s);
+        });
+    }
+
+    #[test]
+    fn test_parse_forward_spec() {
+        let tcp = |port| AdbSocketFamily::Tcp(Tcp::with_port(port));
+        assert_eq!(
+            AdbSocketFamily::parse_forward_spec("1234").unwrap(),
+            tcp(1234)
+        );
+        assert_eq!(
+            AdbSocketFamily::parse_forward_spec(" 5678 ").unwrap(),
+            tcp(5678)
+        );
+        assert_eq!(
+            AdbSocketFamily::parse_forward_spec("tcp:1234").unwrap(),
+            tcp(1234)
+        );
+        assert_eq!(
+            AdbSocketFamily::parse_forward_spec("jdwp:42").unwrap(),
+            AdbSocketFamily::Jdwp(Jdwp(42))
+        );
+        assert!(AdbSocketFamily::parse_forward_spec("70000").is_err());
+        assert!(AdbSocketFamily::parse_forward_spec("-1").is_err());
+        assert!(AdbSocketFamily::parse_forward_spec("").is_err());
+    }
+
+    #[test]
+    fn test_as_tcp() {
+        let tcp = AdbSocketFamily::Tcp(Tcp::with_port(5555));
+        assert_eq!(tcp.as_tcp(), Some(&Tcp::with_port(5555)));
+        assert_eq!(tcp.tcp_port(), Some(5555));
+        let tcp = AdbSocketFamily::Tcp(Tcp::with_ipv4(Ipv4Addr::LOCALHOST));
+        assert_eq!(tcp.tcp_port(), None);
+        let abs = AdbSocketFamily::LocalAbstract(LocalAbstract("abs".to_string()));
+        assert_eq!(abs.as_tcp(), None);
+        assert_eq!(abs.tcp_port(), None);
+    }
+
     #[test]
     fn test_tcp_display() {
         for (s, tcp) in TCP_COMMON {
@@ -629,6 +1014,21 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_tcp_resolve_async() {
+        for (s, tcp) in TCP_RESOLVE_OK {
+            assert_eq!(tcp, Tcp::from_host_async(s).await.unwrap());
+            assert_eq!(
+                Tcp::from_host(s).unwrap(),
+                Tcp::from_host_async(s).await.unwrap()
+            );
+        }
+        for s in TCP_RESOLVE_ERR {
+            assert!(Tcp::from_host_async(s).await.is_err(), "{}", s);
+        }
+    }
+
     #[test]
     fn test_local_abstract_display() {
         let local_abstract = LocalAbstract("socket".to_string());
@@ -733,6 +1133,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_u32_range() {
+        let max = u32::MAX.to_string();
+        let values = [
+            format!("jdwp:{}", max),
+            format!("acceptfd:{}", max),
+            format!("vsock:{}:{}", max, max),
+            "jdwp:0".to_string(),
+            "acceptfd:0".to_string(),
+            "vsock:0:0".to_string(),
+        ];
+        for s in &values {
+            let family = s.parse::<AdbSocketFamily>().unwrap();
+            assert_eq!(&family.to_string(), s);
+        }
+        // the macro-generated parsers and `Vsock` report the same kind of errors
+        for (s, target) in [
+            (format!("jdwp:{}", OVERFLOW), "process pid (u32)"),
+            (format!("acceptfd:{}", OVERFLOW), "fd (u32)"),
+            (format!("vsock:{}:1", OVERFLOW), "cid (u32)"),
+            (format!("vsock:1:{}", OVERFLOW), "port (u32)"),
+            ("jdwp:-1".to_string(), "process pid (u32)"),
+            ("acceptfd:-1".to_string(), "fd (u32)"),
+            ("vsock:-1:1".to_string(), "cid (u32)"),
+        ] {
+            let err = match s.split_once(':').unwrap().0 {
+                "jdwp" => s.parse::<Jdwp>().unwrap_err(),
+                "acceptfd" => s.parse::<AcceptFd>().unwrap_err(),
+                _ => s.parse::<Vsock>().unwrap_err(),
+            };
+            match err {
+                AdbError::Parse(e) => {
+                    assert_eq!(e.target, target, "{}", s);
+                    assert!(e.source.is_some(), "{}", s);
+                }
+                e => panic!("unexpected error for {}: {:?}", s, e),
+            }
+        }
+        for s in ["vsock:1", "vsock:"] {
+            match s.parse::<Vsock>().unwrap_err() {
+                AdbError::Parse(e) => assert_eq!(e.value, s),
+                e => panic!("unexpected error for {}: {:?}", s, e),
+            }
+        }
+    }
+
     #[test]
     fn test_vsock_display() {
         let vsock = Vsock { cid: 1, port: 2 };